@@ -1,40 +1,33 @@
-use rust6502::address_bus::*;
-use rust6502::mc6821::*;
-use rust6502::memory::*;
+use std::path::PathBuf;
+
+use rust6502::apple1_keyboard::KeyQueue;
+use rust6502::machine::{Machine, MachineDescription, MachineError};
 use rust6502::mos6502::*;
 
 use crate::wasm_terminal::WasmTerminal;
 
+// the browser has no file system, so the description and its ROM images are built in
+const DESCRIPTION: &str = include_str!("../../machines/apple1.toml");
+const MONITOR_IMAGE: &str = "../roms/Apple1_HexMonitor.bin";
+const BASIC_IMAGE: &str = "../roms/Apple1_Basic.bin";
+
 pub struct Apple1Compact<'a> {
     pub cpu: Option<Cpu<'a>>,
-    pub bus: Option<Apple1CompactBus>,
+    pub machine: Option<Machine>,
+    pub keyboard: Option<KeyQueue>,
     pub terminal: Option<WasmTerminal>,
     pub check_input: Option<Box<dyn Fn()>>,
 }
 
-pub struct Apple1CompactBus {
-    pub mem: Option<Memory>,
-    pub rom_monitor: Option<Memory>,
-    pub pia: Option<MC6821>,
-}
-
-impl ExternalAddressing for Apple1CompactBus {
-    fn read(&mut self, addr: u16) -> Result<u8, AddressingError> {
-        match addr {
-            0x0000..=0x1000 => Ok(self.mem.as_mut().unwrap().int_read(addr)),
-            0x1001..=0xCFFF => Err(AddressingError::new("read", addr)),
-            0xD000..=0xD1FF => Ok(self.pia.as_mut().unwrap().int_read(addr)),
-            0xD200..=0xFEFF => Err(AddressingError::new("read", addr)),
-            0xFF00..=0xFFFF => Ok(self.rom_monitor.as_mut().unwrap().int_read(addr)),
-        }
-    }
-
-    fn write(&mut self, addr: u16, data: u8) -> Result<(), AddressingError> {
-        match addr {
-            0x0000..=0x1000 => Ok(self.mem.as_mut().unwrap().int_write(addr, data)),
-            0x1001..=0xCFFF => Err(AddressingError::new("read", addr)),
-            0xD000..=0xD1FF => Ok(self.pia.as_mut().unwrap().int_write(addr, data)),
-            0xD200..=0xFFFF => Err(AddressingError::new("write", addr)),
-        }
-    }
+/// The Apple-1 of `machines/apple1.toml`.
+pub fn build_machine() -> Result<Machine, MachineError> {
+    let description = MachineDescription::parse(DESCRIPTION)?;
+    Machine::build_with(description, |image| match image {
+        MONITOR_IMAGE => Ok(include_bytes!("../../roms/Apple1_HexMonitor.bin").to_vec()),
+        BASIC_IMAGE => Ok(include_bytes!("../../roms/Apple1_Basic.bin").to_vec()),
+        _ => Err(MachineError::Io {
+            path: PathBuf::from(image),
+            message: "not built into the Wasm module".to_string(),
+        }),
+    })
 }
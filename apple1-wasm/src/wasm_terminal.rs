@@ -154,80 +154,75 @@ impl WasmTerminal {
     pub fn event_loop(&mut self) {
         let mut screen_changed = false;
 
-        loop {
-            match self.rx_output.try_recv() {
-                Ok(b) => {
-                    // log(&format!("B:{} {} {}", b, self.cursor_x, self.cursor_y));
+        while let Ok(b) = self.rx_output.try_recv() {
+            // log(&format!("B:{} {} {}", b, self.cursor_x, self.cursor_y));
 
-                    // clear cursor
-                    let current_buffer_pos = self.cursor_x + self.cursor_y * COLS as usize;
-                    self.display_char(
-                        self.cursor_x,
-                        self.cursor_y,
-                        self.char_buffer[current_buffer_pos],
-                        false,
-                    );
+            // clear cursor
+            let current_buffer_pos = self.cursor_x + self.cursor_y * COLS as usize;
+            self.display_char(
+                self.cursor_x,
+                self.cursor_y,
+                self.char_buffer[current_buffer_pos],
+                false,
+            );
 
-                    let c = (b & !0x80).to_ascii_uppercase() as u8;
-                    match c {
-                        0x0A | 0x0D => {
-                            self.cursor_x = 0;
-                            self.cursor_y += 1;
-                        }
-                        _ => {
-                            if b >= 0x20 && b <= 0x5F {
-                                self.char_buffer[current_buffer_pos] = b;
-                                self.display_char(self.cursor_x, self.cursor_y, b, false);
-                                self.cursor_x += 1;
-                            }
-                        }
+            let c = (b & !0x80).to_ascii_uppercase();
+            match c {
+                0x0A | 0x0D => {
+                    self.cursor_x = 0;
+                    self.cursor_y += 1;
+                }
+                _ => {
+                    if (0x20..=0x5F).contains(&b) {
+                        self.char_buffer[current_buffer_pos] = b;
+                        self.display_char(self.cursor_x, self.cursor_y, b, false);
+                        self.cursor_x += 1;
                     }
+                }
+            }
 
-                    // log(&format!("A:{} {} {}", b, self.cursor_x, self.cursor_y));
-
-                    // check cursor position
-                    if self.cursor_x == COLS as usize {
-                        self.cursor_x = 0;
-                        self.cursor_y += 1;
-                    }
+            // log(&format!("A:{} {} {}", b, self.cursor_x, self.cursor_y));
 
-                    if self.cursor_y == ROWS as usize {
-                        // scroll up
-                        for y in 0..(ROWS - 1) as usize {
-                            for x in 0..COLS as usize {
-                                let new_buffer_pos = x + y * COLS as usize;
-                                self.char_buffer[new_buffer_pos] =
-                                    self.char_buffer[new_buffer_pos + COLS as usize];
-                                self.display_char(x, y, self.char_buffer[new_buffer_pos], false);
-                            }
-                        }
+            // check cursor position
+            if self.cursor_x == COLS as usize {
+                self.cursor_x = 0;
+                self.cursor_y += 1;
+            }
 
-                        for x in 0..COLS as usize {
-                            let new_buffer_pos = x + (ROWS as usize - 1) * COLS as usize;
-                            self.char_buffer[new_buffer_pos] = 0x00;
-                            self.display_char(
-                                x,
-                                ROWS as usize - 1,
-                                self.char_buffer[new_buffer_pos],
-                                false,
-                            );
-                        }
-                        self.cursor_y -= 1;
+            if self.cursor_y == ROWS as usize {
+                // scroll up
+                for y in 0..(ROWS - 1) as usize {
+                    for x in 0..COLS as usize {
+                        let new_buffer_pos = x + y * COLS as usize;
+                        self.char_buffer[new_buffer_pos] =
+                            self.char_buffer[new_buffer_pos + COLS as usize];
+                        self.display_char(x, y, self.char_buffer[new_buffer_pos], false);
                     }
+                }
 
-                    // display cursor
-                    let new_buffer_pos = self.cursor_x + self.cursor_y * COLS as usize;
+                for x in 0..COLS as usize {
+                    let new_buffer_pos = x + (ROWS as usize - 1) * COLS as usize;
+                    self.char_buffer[new_buffer_pos] = 0x00;
                     self.display_char(
-                        self.cursor_x,
-                        self.cursor_y,
+                        x,
+                        ROWS as usize - 1,
                         self.char_buffer[new_buffer_pos],
-                        true,
+                        false,
                     );
-
-                    screen_changed = true;
                 }
-                Err(_) => break,
+                self.cursor_y -= 1;
             }
+
+            // display cursor
+            let new_buffer_pos = self.cursor_x + self.cursor_y * COLS as usize;
+            self.display_char(
+                self.cursor_x,
+                self.cursor_y,
+                self.char_buffer[new_buffer_pos],
+                true,
+            );
+
+            screen_changed = true;
         }
 
         if screen_changed {
@@ -259,7 +254,7 @@ impl WasmTerminal {
                 let char_index = r * CHAR_WIDTH + c;
                 let pixel_index =
                     ((y * CHAR_HEIGHT + r) * 40 * CHAR_WIDTH + (x * CHAR_WIDTH + c)) * 4;
-                self.pixel_buffer[pixel_index + 0] = char_pixels[char_index] * 0x33;
+                self.pixel_buffer[pixel_index] = char_pixels[char_index] * 0x33;
                 self.pixel_buffer[pixel_index + 1] = char_pixels[char_index] * 0xff;
                 self.pixel_buffer[pixel_index + 2] = char_pixels[char_index] * 0x66;
                 self.pixel_buffer[pixel_index + 3] = 0xff;
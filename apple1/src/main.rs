@@ -3,14 +3,29 @@ extern crate ncurses;
 
 use ncurses::*;
 
-use core::time;
 use crossbeam_channel::*;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::process;
 use std::thread;
+use std::time::{Duration, Instant};
 
-use rust6502::address_bus::*;
+use apple1::options::{load_programs, recording_path, start_program, Options, OptionsError};
+use rust6502::address_bus::ExternalAddressing;
+use rust6502::apple1_keyboard::KeyQueue;
+use rust6502::loader::export_file;
 use rust6502::mc6821::*;
-use rust6502::memory::*;
-use rust6502::mos6502::*;
+
+trait Terminal {
+    fn check_input(&self) -> Result<u8, TryRecvError>;
+    fn print(&self, text: &str);
+    /// progress report outside the Apple-1 output, empty to remove it
+    fn status(&self, text: &str);
+}
+
+// one line of at most 80 columns, status messages replace it
+const HEADER: &str =
+    "Apple1 - Ctrl-C quit, Ctrl-F fast, Ctrl-P paste, Ctrl-E export, Ctrl-T rewind\n\n";
 
 struct ConsoleTerminal {
     pub rx_input: Receiver<u8>,
@@ -20,115 +35,393 @@ impl ConsoleTerminal {
     pub fn new(rx_output: Receiver<u8>) -> ConsoleTerminal {
         initscr();
         noecho();
-        addstr("Apple1 console - hit Ctrl-C to quit\n\n");
+        addstr(HEADER);
 
         let (tx_input, rx_input) = unbounded();
         thread::spawn(move || loop {
             tx_input.send(getch() as u8).unwrap();
         });
 
-        thread::spawn(move || loop {
-            match rx_output.try_recv() {
-                Ok(b) => {
-                    let c = (b & !0x80).to_ascii_uppercase() as u8;
-                    match c {
-                        0x0A | 0x0D => {
-                            addch('\n' as chtype);
-                        }
-                        _ => {
-                            addch(c as chtype);
-                        }
+        thread::spawn(move || {
+            for b in rx_output.iter() {
+                let c = (b & !0x80).to_ascii_uppercase();
+                match c {
+                    0x0A | 0x0D => {
+                        addch('\n' as chtype);
+                    }
+                    _ => {
+                        addch(c as chtype);
                     }
-                    refresh();
-                }
-                Err(_) => {
-                    thread::sleep(time::Duration::from_millis(100));
                 }
+                refresh();
             }
         });
 
         ConsoleTerminal { rx_input }
     }
+}
 
-    pub fn check_input(&self) -> Result<u8, TryRecvError> {
+impl Terminal for ConsoleTerminal {
+    fn check_input(&self) -> Result<u8, TryRecvError> {
         self.rx_input.try_recv()
     }
+
+    fn print(&self, text: &str) {
+        addstr(text);
+        refresh();
+    }
+
+    // in place of the header line
+    fn status(&self, text: &str) {
+        let (mut y, mut x) = (0, 0);
+        getyx(stdscr(), &mut y, &mut x);
+        mvaddstr(
+            0,
+            0,
+            if text.is_empty() {
+                HEADER.trim_end()
+            } else {
+                text
+            },
+        );
+        clrtoeol();
+        mv(y, x);
+        refresh();
+    }
 }
 
-fn main() {
-    let mut address_bus = AddressBus::new(0x100);
+// --headless: the emulator's own stdin and stdout, e.g. to pipe a session through it
+struct HeadlessTerminal {
+    pub rx_input: Receiver<u8>,
+}
 
-    let mut mem = Memory::new(0, 4 * 1024);
-    if address_bus.add_component(0, mem.len(), &mut (mem)).is_err() {
-        panic!("add_component for RAM failed");
+impl HeadlessTerminal {
+    pub fn new(rx_output: Receiver<u8>) -> HeadlessTerminal {
+        let (tx_input, rx_input) = unbounded();
+        thread::spawn(move || {
+            for b in io::stdin().lock().bytes() {
+                match b {
+                    Ok(b) if tx_input.send(b).is_ok() => (),
+                    _ => break,
+                }
+            }
+        });
+
+        thread::spawn(move || {
+            for b in rx_output.iter() {
+                let c = match (b & !0x80).to_ascii_uppercase() {
+                    0x0D => b'\n',
+                    c => c,
+                };
+                let mut stdout = io::stdout().lock();
+                let _ = stdout.write_all(&[c]).and_then(|_| stdout.flush());
+            }
+        });
+
+        HeadlessTerminal { rx_input }
     }
+}
 
-    let mut pia = MC6821::new();
+impl Terminal for HeadlessTerminal {
+    fn check_input(&self) -> Result<u8, TryRecvError> {
+        self.rx_input.try_recv()
+    }
 
-    // channel from PIA to terminal (PIA=tx, terminal=rx)
-    let (tx_apple_output, rx_apple_output): (Sender<u8>, Receiver<u8>) = unbounded();
-    pia.set_output_channel_b(tx_apple_output);
-    let terminal = ConsoleTerminal::new(rx_apple_output);
-
-    // channel from keyboard to PIA (keyboard=tx, PIA=rx)
-    let (tx_apple_input, rx_apple_input): (Sender<InputSignal>, Receiver<InputSignal>) =
-        unbounded();
-    pia.set_input_channel(rx_apple_input);
-    let check_input = || -> bool {
-        let mut stop = false;
-        if let Ok(mut c) = terminal.check_input() {
-            match c {
-                0x03 => stop = true, // ^c
-                0x0A => c = 0x0D,
-                _ => {}
-            };
-
-            tx_apple_input.send(InputSignal::CA1(Signal::Fall)).unwrap();
-            tx_apple_input
-                .send(InputSignal::IRA(c.to_ascii_uppercase() as u8 | 0x80))
-                .unwrap();
-            tx_apple_input.send(InputSignal::CA1(Signal::Rise)).unwrap();
-            tx_apple_input.send(InputSignal::CA1(Signal::Fall)).unwrap();
+    fn print(&self, text: &str) {
+        let mut stdout = io::stdout().lock();
+        let _ = stdout
+            .write_all(text.as_bytes())
+            .and_then(|_| stdout.flush());
+    }
+
+    // on stderr, so stdout only carries the Apple-1 output
+    fn status(&self, text: &str) {
+        if text.is_empty() {
+            eprintln!();
+        } else {
+            eprint!("\r{}", text);
         }
-        stop
-    };
+    }
+}
 
-    if address_bus
-        .add_component(0xD000, 0x100, &mut (pia))
-        .is_err()
-    {
-        panic!("add_component PIA failed");
+// "0280.0FFF basic.woz": Woz monitor style range and a file, the format follows its extension
+fn export_memory(bus: &dyn ExternalAddressing, command: &str) -> String {
+    let mut parts = command.split_whitespace();
+    let range = parts.next().and_then(|range| {
+        let (from, to) = range.split_once('.')?;
+        Some((
+            u16::from_str_radix(from, 16).ok()?,
+            u16::from_str_radix(to, 16).ok()?,
+        ))
+    });
+    match (range, parts.next()) {
+        (Some((from_addr, to_addr)), Some(file)) => {
+            match export_file(bus, from_addr, to_addr, file, None) {
+                Ok(()) => format!("exported {:04X}.{:04X} to {}", from_addr, to_addr, file),
+                Err(e) => format!("export failed: {}", e),
+            }
+        }
+        _ => "usage: FROM.TO FILE (.bin .hex .s19 .txt .woz)".to_string(),
     }
+}
+
+enum Prompt {
+    Export,
+    Paste,
+}
+
+const PASTE_PROGRESS_MIN_KEYS: usize = 16;
 
-    let mut rom_monitor = Memory::load_rom(0xFF00, "./roms/Apple1_HexMonitor.bin".to_string());
-    if address_bus
-        .add_component(0xFF00, rom_monitor.len(), &mut (rom_monitor))
-        .is_err()
-    {
-        panic!("add_component for ROM failed");
+fn paste(keyboard: &Option<KeyQueue>, text: &[u8], terminal: &dyn Terminal) {
+    match keyboard {
+        Some(keyboard) => keyboard.type_text(text),
+        None => terminal.print("no Apple-1 keyboard to paste to\n"),
     }
+}
 
-    let mut rom_basic = Memory::load_rom(0xE000, "./roms/Apple1_Basic.bin".to_string());
-    if address_bus
-        .add_component(0xE000, rom_basic.len(), &mut (rom_basic))
-        .is_err()
-    {
-        panic!("add_component for ROM failed");
+// problems with the command line or the files it names end the program before the terminal
+// is set up
+fn exit_with(message: impl std::fmt::Display) -> ! {
+    eprintln!("apple1: {}", message);
+    process::exit(2);
+}
+
+fn main() {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(OptionsError::Help) => {
+            println!("{}", OptionsError::Help);
+            return;
+        }
+        Err(e) => exit_with(format!("{}\n(see apple1 --help)", e)),
+    };
+
+    // any described machine with a PIA named "pia" can be booted, the Apple-1 is the default
+    let programs = options.programs().unwrap_or_else(|e| exit_with(e));
+    let mut machine = options
+        .build_machine(&programs)
+        .unwrap_or_else(|e| exit_with(format!("could not build machine: {}", e)));
+    if let Err(e) = load_programs(&mut machine.bus, &programs) {
+        exit_with(e);
     }
+    let run_address = options.run_address(&programs);
+    let pia = match machine.take_pia_channels("pia") {
+        Some(pia) => pia,
+        None => exit_with(format!(
+            "machine {} has no PIA named pia",
+            machine.description.name
+        )),
+    };
+    let cassette = options
+        .connect_cassette(&mut machine)
+        .unwrap_or_else(|e| exit_with(e));
+    let mut trace = options.trace.as_ref().map(|path| {
+        File::create(path).unwrap_or_else(|e| exit_with(format!("{}: {}", path.display(), e)))
+    });
+
+    let pasted = options.paste.as_ref().map(|path| {
+        std::fs::read(path).unwrap_or_else(|e| exit_with(format!("{}: {}", path.display(), e)))
+    });
+
+    let clock_hz = machine.description.clock_hz;
+    let fast_display = pia.fast_display;
+    let keyboard = pia.keyboard;
+
+    // channel from PIA to terminal (PIA=tx, terminal=rx)
+    let terminal: Box<dyn Terminal> = if options.headless {
+        Box::new(HeadlessTerminal::new(pia.output_b))
+    } else {
+        Box::new(ConsoleTerminal::new(pia.output_b))
+    };
+    let notes: Vec<String> = machine
+        .warnings
+        .iter()
+        .map(|warning| format!("WARNING: {}", warning))
+        .chain(
+            machine
+                .serial_endpoints()
+                .iter()
+                .map(|(name, endpoint)| format!("{} ON {}", name, endpoint)),
+        )
+        .collect();
+    if !notes.is_empty() {
+        terminal.status(&notes.join("; "));
+    }
+
+    // keys go to the Apple-1 keyboard as the program reads them; machines without one get
+    // them on the PIA input channel (keyboard=tx, PIA=rx)
+    let tx_apple_input = pia.input;
+    let send_key = |mut c: u8| {
+        if let Some(keyboard) = &keyboard {
+            keyboard.type_text(&[c]);
+            return;
+        }
+        if c == 0x0A {
+            c = 0x0D;
+        }
 
-    let mut cpu = Cpu::new(CpuRegisters::default(), &mut address_bus);
+        tx_apple_input.send(InputSignal::CA1(Signal::Fall)).unwrap();
+        tx_apple_input
+            .send(InputSignal::IRA(c.to_ascii_uppercase() | 0x80))
+            .unwrap();
+        tx_apple_input.send(InputSignal::CA1(Signal::Rise)).unwrap();
+        tx_apple_input.send(InputSignal::CA1(Signal::Fall)).unwrap();
+    };
+
+    let uninitialized_logs = machine.uninitialized_logs();
+    let mut cpu = machine.cpu();
 
     cpu.reset();
     cpu.wait_for_system_reset_cycles();
+    match (options.start, run_address) {
+        (Some(start), _) => cpu.set_pc(start),
+        (None, Some(entry_point)) => start_program(&mut cpu, entry_point, clock_hz),
+        (None, None) => (),
+    }
+    if let Some(text) = pasted {
+        paste(&keyboard, &text, &*terminal);
+    }
+
+    // a recording is written once the ACI output has been quiet for a second
+    let mut recordings = 0;
+
+    // ^E reads an export command, ^P a file to paste instead of passing keys to the PIA
+    let mut prompt: Option<(Prompt, String)> = None;
+    let mut paste_progress = (0, 0);
+    let mut uninitialized_reported = vec![0; uninitialized_logs.len()];
+    // --stop-uninitialized: no cycles until the next key
+    let mut stopped = false;
 
-    // main emulation loop
+    // main emulation loop, kept to the machine's clock
+    const CYCLES_PER_SLICE: u64 = 1_000;
+    let mut started = Instant::now();
+    let mut started_cycles = cpu.cycles();
     loop {
         // check input from the terminal and send to PIA
-        check_input();
+        while let Ok(c) = terminal.check_input() {
+            match prompt.as_mut() {
+                None if stopped => {
+                    stopped = false;
+                    terminal.status("");
+                }
+                None if c == 0x06 => {
+                    if let Some(fast_mode) = &fast_display {
+                        fast_mode.set(!fast_mode.is_set());
+                    }
+                }
+                None if c == 0x14 => match &cassette {
+                    Some(cassette) => {
+                        cassette.rewind();
+                        terminal.status("TAPE REWOUND");
+                    }
+                    None => terminal.status("NO ACI"),
+                },
+                None if c == 0x05 => {
+                    terminal.print("\nEXPORT FROM.TO FILE: ");
+                    prompt = Some((Prompt::Export, String::new()));
+                }
+                None if c == 0x10 => {
+                    terminal.print("\nPASTE FILE: ");
+                    prompt = Some((Prompt::Paste, String::new()));
+                }
+                None => send_key(c),
+                Some((kind, entry)) => match c {
+                    0x0A | 0x0D => {
+                        match kind {
+                            Prompt::Export => {
+                                let result = export_memory(cpu.address_bus(), entry);
+                                terminal.print(&format!("\n{}\n", result));
+                            }
+                            Prompt::Paste => {
+                                terminal.print("\n");
+                                match std::fs::read(entry.trim()) {
+                                    Ok(text) => paste(&keyboard, &text, &*terminal),
+                                    Err(e) => terminal.print(&format!("{}: {}\n", entry, e)),
+                                }
+                            }
+                        }
+                        prompt = None;
+                    }
+                    0x1B => {
+                        terminal.print("\n");
+                        prompt = None;
+                    }
+                    _ => {
+                        entry.push(c as char);
+                        terminal.print(&(c as char).to_string());
+                    }
+                },
+            }
+        }
+
+        // progress of pastes longer than a few typed keys
+        if let Some(keyboard) = &keyboard {
+            let progress = keyboard.progress();
+            if progress != paste_progress && progress.1 > PASTE_PROGRESS_MIN_KEYS {
+                terminal.status(&if progress.0 < progress.1 {
+                    format!("PASTE {}/{} KEYS", progress.0, progress.1)
+                } else {
+                    String::new()
+                });
+            }
+            paste_progress = progress;
+        }
+
+        // processor cycles; tracing runs whole instructions
+        let slice_end = cpu.cycles() + CYCLES_PER_SLICE;
+        let mut stop = None;
+        while !stopped && cpu.cycles() < slice_end {
+            match trace.as_mut() {
+                Some(file) => cpu.cycle_file(file),
+                None => cpu.cycle(false),
+            }
+            stop = uninitialized_logs.iter().find_map(|log| log.take_stop());
+            stopped = stop.is_some();
+        }
 
-        // processor cycle
-        cpu.cycle(false);
+        // --log-uninitialized: the latest read of RAM never written
+        for (log, reported) in uninitialized_logs
+            .iter()
+            .zip(uninitialized_reported.iter_mut())
+        {
+            if log.len() > *reported {
+                *reported = log.len();
+                if let Some(read) = log.last() {
+                    terminal.status(&format!(
+                        "UNINITIALIZED READ OF {:04X} AT PC {:04X}",
+                        read.addr, read.pc
+                    ));
+                }
+            }
+        }
+        if let Some(read) = stop {
+            terminal.status(&format!(
+                "STOPPED AT UNINITIALIZED READ OF {:04X} AT PC {:04X}, ANY KEY CONTINUES",
+                read.addr, read.pc
+            ));
+        }
 
-        thread::sleep(time::Duration::from_micros(100));
+        if let (Some(cassette), Some(path)) = (&cassette, &options.tape_out) {
+            if let Some(wav) = cassette.take_recording(clock_hz) {
+                recordings += 1;
+                let path = recording_path(path, recordings);
+                terminal.status(&match wav.write_file(&path) {
+                    Ok(()) => format!("TAPE SAVED TO {}", path.display()),
+                    Err(e) => format!("TAPE NOT SAVED: {}", e),
+                });
+            }
+        }
+
+        if stopped {
+            thread::sleep(Duration::from_millis(10));
+            started = Instant::now();
+            started_cycles = cpu.cycles();
+            continue;
+        }
+        let cycles = cpu.cycles() - started_cycles;
+        let due = Duration::from_secs_f64(cycles as f64 / clock_hz as f64);
+        if let Some(ahead) = due.checked_sub(started.elapsed()) {
+            thread::sleep(ahead);
+        }
     }
 }
@@ -0,0 +1,565 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rust6502::aci::{Cassette, Wav, RECORDING_SAMPLE_RATE};
+use rust6502::address_bus::{Decoding, ExternalAddressing};
+use rust6502::loader::{Format, Image, LoadError};
+use rust6502::machine::{
+    DeviceDescription, DeviceKind, Machine, MachineDescription, MachineError, RamRegion, RomImage,
+    UninitializedRam,
+};
+use rust6502::mos6502::Cpu;
+use rust6502::rom_catalogue::{self, RomCheck};
+
+pub const DEFAULT_MACHINE: &str = "./machines/apple1.toml";
+
+const MONITOR_ADDR: u16 = 0xFF00;
+const BASIC_ADDR: u16 = 0xE000;
+const ACI_ADDR: u16 = 0xC000;
+
+/// After reset the Woz monitor waits for a key here (NEXTCHAR).
+pub const MONITOR_KEY_WAIT: u16 = 0xFF29;
+const WOZ_MONITOR: &str = "Woz Monitor"; // name in the ROM catalogue
+
+pub const USAGE: &str = "usage: apple1 [OPTIONS] [MACHINE]
+
+  MACHINE, --machine FILE  machine description (default ./machines/apple1.toml)
+  --monitor FILE           Woz monitor ROM at $FF00
+  --basic FILE             Integer BASIC ROM at $E000
+  --no-basic               leave $E000 empty
+  --ram SIZE               RAM at $0000, e.g. 8K, 32K or 0x2000
+  --load [ADDR:]FILE       place an image into memory before reset; raw binaries need
+                           a hex load address unless they are known dumps, .hex .s19
+                           .prg files bring their own; RAM grows where needed
+  --run [ADDR:]FILE[@ENTRY]
+                           as --load, then jump to the entry point like XXXXR;
+                           ENTRY replaces the entry point of the image
+  --clock HZ               CPU clock, e.g. 1022727, 2M or 500k
+  --start ADDR             start at hex ADDR instead of the reset vector
+  --trace FILE             write every executed instruction to FILE
+  --log-uninitialized      report reads of RAM not written since power-on
+  --stop-uninitialized     stop at such reads until a key is pressed
+  --paste FILE             type a text file, e.g. a BASIC listing, as the program reads keys
+  --aci FILE               Apple Cassette Interface at $C000 with its PROM image at $C100
+  --tape-in FILE           WAV recording to play into the ACI, e.g. for C100R then 0300.0FFFR
+  --tape-out FILE          record the ACI output to a WAV file, numbered after the first save
+  --headless               keyboard from stdin and display to stdout, without ncurses
+  -h, --help               show this help";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionsError {
+    Help,
+    Invalid(String),
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OptionsError::Help => write!(f, "{}", USAGE),
+            OptionsError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// An image for `--load` or `--run`; without a load address the format follows the file
+/// extension, raw binaries are looked up in the ROM catalogue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageOption {
+    pub load_addr: Option<u16>,
+    pub path: PathBuf,
+    pub run: bool,
+    /// `@ENTRY` of `--run`
+    pub entry_point: Option<u16>,
+}
+
+/// An image read from its file, to be placed with `load_programs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub path: PathBuf,
+    pub image: Image,
+    /// from `@ENTRY`, the file, the ROM catalogue or else the load address
+    pub entry_point: u16,
+    pub run: bool,
+}
+
+/// Command line of the native Apple-1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub machine: PathBuf,
+    pub monitor: Option<PathBuf>,
+    pub basic: Option<PathBuf>,
+    pub no_basic: bool,
+    pub ram_size: Option<usize>,
+    pub images: Vec<ImageOption>,
+    pub clock_hz: Option<u64>,
+    pub start: Option<u16>,
+    pub trace: Option<PathBuf>,
+    pub log_uninitialized: bool,
+    pub stop_uninitialized: bool,
+    pub paste: Option<PathBuf>,
+    pub aci: Option<PathBuf>,
+    pub tape_in: Option<PathBuf>,
+    pub tape_out: Option<PathBuf>,
+    pub headless: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            machine: PathBuf::from(DEFAULT_MACHINE),
+            monitor: None,
+            basic: None,
+            no_basic: false,
+            ram_size: None,
+            images: vec![],
+            clock_hz: None,
+            start: None,
+            trace: None,
+            log_uninitialized: false,
+            stop_uninitialized: false,
+            paste: None,
+            aci: None,
+            tape_in: None,
+            tape_out: None,
+            headless: false,
+        }
+    }
+}
+
+impl Options {
+    /// Parse the arguments after the program name; `--name VALUE` and `--name=VALUE` both work.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, OptionsError> {
+        let mut options = Options::default();
+        let mut machine_given = false;
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| invalid(format!("{} needs a value", name)))
+            };
+
+            match name {
+                "-h" | "--help" => return Err(OptionsError::Help),
+                "--machine" => {
+                    options.machine = PathBuf::from(value()?);
+                    machine_given = true;
+                }
+                "--monitor" => options.monitor = Some(PathBuf::from(value()?)),
+                "--basic" => options.basic = Some(PathBuf::from(value()?)),
+                "--no-basic" => options.no_basic = true,
+                "--ram" => options.ram_size = Some(parse_ram_size(&value()?)?),
+                "--load" => options.images.push(parse_image(&value()?, false)?),
+                "--run" => options.images.push(parse_image(&value()?, true)?),
+                "--clock" => options.clock_hz = Some(parse_clock(&value()?)?),
+                "--start" => options.start = Some(parse_addr(&value()?)?),
+                "--trace" => options.trace = Some(PathBuf::from(value()?)),
+                "--log-uninitialized" => options.log_uninitialized = true,
+                "--stop-uninitialized" => options.stop_uninitialized = true,
+                "--paste" => options.paste = Some(PathBuf::from(value()?)),
+                "--aci" => options.aci = Some(PathBuf::from(value()?)),
+                "--tape-in" => options.tape_in = Some(PathBuf::from(value()?)),
+                "--tape-out" => options.tape_out = Some(PathBuf::from(value()?)),
+                "--headless" => options.headless = true,
+                _ if name.starts_with('-') => {
+                    return Err(invalid(format!("unknown option {}", name)))
+                }
+                _ if !machine_given => {
+                    options.machine = PathBuf::from(name);
+                    machine_given = true;
+                }
+                _ => return Err(invalid(format!("unexpected argument {}", name))),
+            }
+        }
+
+        if options.no_basic && options.basic.is_some() {
+            return Err(invalid(
+                "--basic and --no-basic exclude each other".to_string(),
+            ));
+        }
+        Ok(options)
+    }
+
+    /// Read the `--load` and `--run` images.
+    pub fn programs(&self) -> Result<Vec<Program>, String> {
+        self.images.iter().map(read_program).collect()
+    }
+
+    /// The machine description with ROM paths resolved, the options applied and RAM added
+    /// for `programs`.
+    pub fn description(&self, programs: &[Program]) -> Result<MachineDescription, MachineError> {
+        let text = fs::read_to_string(&self.machine).map_err(|e| io_error(&self.machine, e))?;
+        let mut description = MachineDescription::parse(&text)?;
+
+        // images of the description are relative to it, those from the command line are not
+        let base_dir = self.machine.parent().unwrap_or(Path::new("."));
+        for rom in description.roms.iter_mut() {
+            rom.image = base_dir.join(&rom.image).to_string_lossy().into_owned();
+        }
+        for device in description.devices.iter_mut() {
+            if let Some(image) = device.image.as_mut() {
+                *image = base_dir.join(&*image).to_string_lossy().into_owned();
+            }
+        }
+
+        if let Some(monitor) = &self.monitor {
+            replace_rom(&mut description, MONITOR_ADDR, monitor);
+        }
+        if let Some(basic) = &self.basic {
+            replace_rom(&mut description, BASIC_ADDR, basic);
+        }
+        if self.no_basic {
+            description.roms.retain(|rom| rom.start != BASIC_ADDR);
+        }
+        if let Some(aci) = &self.aci {
+            replace_aci(&mut description, aci);
+        }
+        let has_aci = description
+            .devices
+            .iter()
+            .any(|device| device.kind == DeviceKind::Aci);
+        if (self.tape_in.is_some() || self.tape_out.is_some()) && !has_aci {
+            return Err(MachineError::Invalid(
+                "--tape-in and --tape-out need an ACI, see --aci".to_string(),
+            ));
+        }
+        if let Some(size) = self.ram_size {
+            description.rams.retain(|ram| ram.start != 0x0000);
+            description.rams.insert(
+                0,
+                RamRegion {
+                    start: 0x0000,
+                    size,
+                    wait_states: 0,
+                    on_uninitialized: UninitializedRam::Allow,
+                },
+            );
+        }
+        if let Some(clock_hz) = self.clock_hz {
+            description.clock_hz = clock_hz;
+        }
+        for program in programs {
+            for range in program.image.ranges() {
+                expand_ram(&mut description, range.from_addr, range.to_addr);
+            }
+        }
+        let on_uninitialized = if self.stop_uninitialized {
+            UninitializedRam::Stop
+        } else if self.log_uninitialized {
+            UninitializedRam::Log
+        } else {
+            UninitializedRam::Allow
+        };
+        if on_uninitialized != UninitializedRam::Allow {
+            for ram in description.rams.iter_mut() {
+                ram.on_uninitialized = on_uninitialized;
+            }
+        }
+
+        description.validate()?;
+        Ok(description)
+    }
+
+    pub fn build_machine(&self, programs: &[Program]) -> Result<Machine, MachineError> {
+        Machine::build_with(self.description(programs)?, |image| {
+            fs::read(image).map_err(|e| io_error(Path::new(image), e))
+        })
+    }
+
+    /// Take the tape deck of the machine's ACI, with the `--tape-in` recording inserted and
+    /// recording if there is a `--tape-out` file.
+    pub fn connect_cassette(&self, machine: &mut Machine) -> Result<Option<Cassette>, String> {
+        let name = match machine
+            .description
+            .devices
+            .iter()
+            .find(|device| device.kind == DeviceKind::Aci)
+        {
+            Some(device) => device.name.clone(),
+            None => return Ok(None),
+        };
+        let cassette = match machine.take_cassette(&name) {
+            Some(cassette) => cassette,
+            None => return Ok(None),
+        };
+        if let Some(path) = &self.tape_in {
+            cassette.insert(Wav::read_file(path).map_err(|e| e.to_string())?);
+        }
+        if self.tape_out.is_some() {
+            cassette.record(RECORDING_SAMPLE_RATE);
+        }
+        Ok(Some(cassette))
+    }
+
+    /// Where to continue after reset: the entry point of the last `--run` image.
+    pub fn run_address(&self, programs: &[Program]) -> Option<u16> {
+        programs
+            .iter()
+            .rev()
+            .find(|program| program.run)
+            .map(|program| program.entry_point)
+    }
+}
+
+/// Place the images, e.g. into `Machine::bus` before the CPU is reset.
+pub fn load_programs(
+    target: &mut dyn ExternalAddressing,
+    programs: &[Program],
+) -> Result<(), String> {
+    for program in programs {
+        program
+            .image
+            .write_to(target)
+            .map_err(|e| format!("loading {}: {}", program.path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Like typing `XXXXR`: let the Woz monitor set up the PIA after reset until it waits for a
+/// key, then continue at `entry_point`. Gives up waiting after `max_cycles`; with any other
+/// ROM at `$FF00`, or none, the program starts right away.
+pub fn start_program(cpu: &mut Cpu, entry_point: u16, max_cycles: u64) {
+    if has_woz_monitor(cpu) {
+        let give_up = cpu.cycles() + max_cycles;
+        while cpu.cycles() < give_up {
+            cpu.cycle(false);
+            if cpu.completed_operation_cycles() && cpu.pc() == MONITOR_KEY_WAIT {
+                break;
+            }
+        }
+    }
+    cpu.set_pc(entry_point);
+}
+
+fn has_woz_monitor(cpu: &Cpu) -> bool {
+    let monitor: Result<Vec<u8>, _> = (MONITOR_ADDR..=0xFFFF).map(|addr| cpu.peek(addr)).collect();
+    matches!(
+        monitor.map(|data| rom_catalogue::check(&data, MONITOR_ADDR)),
+        Ok(RomCheck::Known(rom)) if rom.name == WOZ_MONITOR
+    )
+}
+
+fn read_program(option: &ImageOption) -> Result<Program, String> {
+    let data = fs::read(&option.path).map_err(|e| format!("{}: {}", option.path.display(), e))?;
+    let known = rom_catalogue::identify(&data);
+    let format = match option.load_addr {
+        Some(load_addr) => Format::Binary { load_addr },
+        None => match Format::from_path(&option.path).or_else(|| {
+            Some(Format::Binary {
+                load_addr: known?.load_addr?,
+            })
+        }) {
+            Some(format) => format,
+            None => return Err(LoadError::UnknownFormat(option.path.clone()).to_string()),
+        },
+    };
+    let image = Image::parse(format, &data)
+        .map_err(|e| format!("loading {}: {}", option.path.display(), e))?;
+
+    let load_addr = image.blocks.first().map(|(addr, _)| *addr).unwrap_or(0);
+    let known_entry = known
+        .filter(|rom| rom.load_addr == Some(load_addr))
+        .and_then(|rom| rom.entry_point);
+    Ok(Program {
+        path: option.path.clone(),
+        entry_point: option
+            .entry_point
+            .or(image.entry_point)
+            .or(known_entry)
+            .unwrap_or(load_addr),
+        image,
+        run: option.run,
+    })
+}
+
+// Apple-1 RAM was added in 4K steps: the RAM at $0000 grows up to a program where nothing lies
+// in between, elsewhere the program gets 4K blocks of its own
+fn expand_ram(description: &mut MachineDescription, from_addr: u16, to_addr: u16) {
+    let roms: Vec<(usize, usize)> = description
+        .roms
+        .iter()
+        .map(|rom| {
+            let size = fs::metadata(&rom.image).map_or(0, |m| m.len() as usize);
+            (rom.start as usize, size)
+        })
+        .collect();
+    let covered = |description: &MachineDescription, addr: usize| {
+        let within = |start: usize, size: usize| (start..start + size).contains(&addr);
+        description
+            .rams
+            .iter()
+            .any(|ram| within(ram.start as usize, ram.size))
+            || roms.iter().any(|(start, size)| within(*start, *size))
+            || description
+                .devices
+                .iter()
+                .any(|device| match device.decode_mask {
+                    Some(mask) => Decoding::new(mask, device.base).selects(addr as u16),
+                    None => within(device.base as usize, device.kind.register_count()),
+                })
+    };
+
+    for block in (from_addr >> 12)..=(to_addr >> 12) {
+        let block_start = (block as usize) << 12;
+        let block_end = block_start + 0x1000;
+        let first = (from_addr as usize).max(block_start);
+        let last = (to_addr as usize).min(block_end - 1);
+        if (first..=last).all(|addr| covered(description, addr)) {
+            continue;
+        }
+
+        let ram_end = description
+            .rams
+            .iter()
+            .find(|ram| ram.start == 0x0000)
+            .map(|ram| ram.size);
+        match ram_end {
+            Some(end) if end < block_end && !(end..first).any(|a| covered(description, a)) => {
+                if let Some(ram) = description.rams.iter_mut().find(|ram| ram.start == 0x0000) {
+                    ram.size = block_end;
+                }
+            }
+            _ => description.rams.push(RamRegion {
+                start: block_start as u16,
+                size: 0x1000,
+                wait_states: 0,
+                on_uninitialized: UninitializedRam::Allow,
+            }),
+        }
+    }
+}
+
+fn replace_rom(description: &mut MachineDescription, start: u16, path: &Path) {
+    let image = path.to_string_lossy().into_owned();
+    match description.roms.iter_mut().find(|rom| rom.start == start) {
+        Some(rom) => rom.image = image,
+        None => description.roms.push(RomImage {
+            start,
+            image,
+            on_write: Default::default(),
+            wait_states: 0,
+        }),
+    }
+}
+
+fn replace_aci(description: &mut MachineDescription, path: &Path) {
+    let image = Some(path.to_string_lossy().into_owned());
+    match description
+        .devices
+        .iter_mut()
+        .find(|device| device.kind == DeviceKind::Aci)
+    {
+        Some(device) => device.image = image,
+        None => description.devices.push(DeviceDescription {
+            name: "aci".to_string(),
+            kind: DeviceKind::Aci,
+            base: ACI_ADDR,
+            decode_mask: None,
+            wait_states: 0,
+            irq: false,
+            serial: None,
+            serial_clock_hz: None,
+            ram_base: None,
+            apple1_display: false,
+            apple1_keyboard: false,
+            image,
+        }),
+    }
+}
+
+/// File for the `number`th recording of a session: `tape.wav`, then `tape-2.wav` and so on.
+pub fn recording_path(path: &Path, number: usize) -> PathBuf {
+    if number <= 1 {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}-{}.{}", stem, number, extension.to_string_lossy()),
+        None => format!("{}-{}", stem, number),
+    };
+    path.with_file_name(name)
+}
+
+fn invalid(message: String) -> OptionsError {
+    OptionsError::Invalid(message)
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> MachineError {
+    MachineError::Io {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    }
+}
+
+/// Hex address as typed into the Woz monitor, optionally with `$` or `0x`.
+pub fn parse_addr(text: &str) -> Result<u16, OptionsError> {
+    let digits = text
+        .strip_prefix('$')
+        .or_else(|| text.strip_prefix("0x"))
+        .unwrap_or(text);
+    u16::from_str_radix(digits, 16).map_err(|_| invalid(format!("invalid address {}", text)))
+}
+
+/// Decimal bytes with an optional `K` suffix, or hex with `$` or `0x`; at least zero page
+/// and stack.
+pub fn parse_ram_size(text: &str) -> Result<usize, OptionsError> {
+    let error = || invalid(format!("invalid RAM size {}, $200 to $10000 bytes", text));
+    let size = if let Some(hex) = text.strip_prefix('$').or_else(|| text.strip_prefix("0x")) {
+        usize::from_str_radix(hex, 16).map_err(|_| error())?
+    } else if let Some(kb) = text.strip_suffix(['K', 'k']) {
+        kb.parse::<usize>().map_err(|_| error())? * 1024
+    } else {
+        text.parse().map_err(|_| error())?
+    };
+    match size {
+        0x200..=0x10000 => Ok(size),
+        _ => Err(error()),
+    }
+}
+
+/// Hz with an optional `k` or `M` suffix.
+pub fn parse_clock(text: &str) -> Result<u64, OptionsError> {
+    let error = || invalid(format!("invalid clock {}", text));
+    let (digits, factor) = match text.strip_suffix(['M', 'm']) {
+        Some(digits) => (digits, 1_000_000.0),
+        None => match text.strip_suffix(['K', 'k']) {
+            Some(digits) => (digits, 1_000.0),
+            None => (text, 1.0),
+        },
+    };
+    let hz = digits.parse::<f64>().map_err(|_| error())? * factor;
+    // also rejects inf and NaN, which parse as f64
+    if !(1.0..=u64::MAX as f64).contains(&hz) {
+        return Err(error());
+    }
+    Ok(hz as u64)
+}
+
+fn parse_image(text: &str, run: bool) -> Result<ImageOption, OptionsError> {
+    let (text, entry_point) = match text.rsplit_once('@') {
+        Some((image, entry)) if run => (image, Some(parse_addr(entry)?)),
+        _ => (text, None),
+    };
+    // a hex address before the colon, so Windows paths like C:\prog.bin stay paths
+    match text.split_once(':') {
+        Some((addr, path)) if addr.len() > 1 && !path.is_empty() => Ok(ImageOption {
+            load_addr: Some(parse_addr(addr)?),
+            path: PathBuf::from(path),
+            run,
+            entry_point,
+        }),
+        _ => Ok(ImageOption {
+            load_addr: None,
+            path: PathBuf::from(text),
+            run,
+            entry_point,
+        }),
+    }
+}
@@ -1,16 +1,17 @@
+use crate::options::{load_programs, Options};
 use crossbeam_channel::{unbounded, Receiver, Sender, TryRecvError};
-use rust6502::address_bus::{AddressBus, InternalAddressing};
-use rust6502::mc6821::{InputSignal, MC6821, Signal};
-use rust6502::memory::Memory;
-use rust6502::mos6502::{Cpu, CpuRegisters};
-use std::collections::VecDeque;
-use std::path::PathBuf;
+use rust6502::memory::UninitializedRead;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::thread::{self, JoinHandle};
 
 enum HarnessCommand {
     Type(Vec<u8>),
-    RunCycles(usize, Sender<()>),
-    Peek(u16, Sender<u8>),
+    KeyProgress(Sender<(usize, usize)>),
+    RunCycles(usize, Sender<Option<UninitializedRead>>),
+    Peek(u16, Sender<Result<u8, String>>),
+    Poke(u16, u8),
     Stop,
 }
 
@@ -21,84 +22,81 @@ pub struct Apple1ConsoleHarness {
 }
 
 impl Apple1ConsoleHarness {
+    /// Boot with the display in fast mode, so output does not wait for the display.
     pub fn start() -> Apple1ConsoleHarness {
-        let (tx_command, rx_command): (Sender<HarnessCommand>, Receiver<HarnessCommand>) =
-            unbounded();
-        let (tx_output, rx_output): (Sender<u8>, Receiver<u8>) = unbounded();
-
-        let worker = thread::spawn(move || {
-            let mut address_bus = AddressBus::new(0x100);
-
-            let mut mem = Memory::new(0, 4 * 1024);
-            if address_bus.add_component(0, mem.len(), &mut mem).is_err() {
-                panic!("add_component for RAM failed");
-            }
+        Self::launch(default_options(), true)
+    }
 
-            let mut pia = MC6821::new();
-            pia.set_output_channel_b(tx_output);
+    /// Boot with the display taking one character per frame, as on the real machine.
+    pub fn start_with_display_timing() -> Apple1ConsoleHarness {
+        Self::launch(default_options(), false)
+    }
 
-            let (tx_apple_input, rx_apple_input): (Sender<InputSignal>, Receiver<InputSignal>) =
-                unbounded();
-            pia.set_input_channel(rx_apple_input);
+    /// Boot the machine the command line `options` describe, with the display in fast mode.
+    pub fn start_with_options(options: Options) -> Apple1ConsoleHarness {
+        Self::launch(options, true)
+    }
 
-            if address_bus.add_component(0xD000, 0x100, &mut pia).is_err() {
-                panic!("add_component PIA failed");
-            }
+    fn launch(options: Options, fast_display: bool) -> Apple1ConsoleHarness {
+        let (tx_command, rx_command): (Sender<HarnessCommand>, Receiver<HarnessCommand>) =
+            unbounded();
 
-            let mut rom_monitor =
-                Memory::load_rom(0xFF00, rom_path("Apple1_HexMonitor.bin"));
-            if address_bus
-                .add_component(0xFF00, rom_monitor.len(), &mut rom_monitor)
-                .is_err()
-            {
-                panic!("add_component for monitor ROM failed");
-            }
+        let (tx_ready, rx_ready): (Sender<Receiver<u8>>, Receiver<Receiver<u8>>) = unbounded();
 
-            let mut rom_basic = Memory::load_rom(0xE000, rom_path("Apple1_Basic.bin"));
-            if address_bus
-                .add_component(0xE000, rom_basic.len(), &mut rom_basic)
-                .is_err()
-            {
-                panic!("add_component for BASIC ROM failed");
+        let worker = thread::spawn(move || {
+            let programs = options.programs().unwrap();
+            let mut machine = match options.build_machine(&programs) {
+                Ok(machine) => machine,
+                Err(e) => panic!("could not load machine: {}", e),
+            };
+            load_programs(&mut machine.bus, &programs).unwrap();
+            let pia = machine
+                .take_pia_channels("pia")
+                .expect("machine without PIA");
+            if let Some(fast_mode) = &pia.fast_display {
+                fast_mode.set(fast_display);
             }
+            // keys go to the program as fast as it reads them from KBD
+            let keyboard = pia.keyboard.expect("machine without Apple-1 keyboard");
+            tx_ready.send(pia.output_b).unwrap();
 
-            let mut cpu = Cpu::new(CpuRegisters::default(), &mut address_bus);
+            let uninitialized_logs = machine.uninitialized_logs();
+            let mut cpu = machine.cpu();
             cpu.reset();
             cpu.wait_for_system_reset_cycles();
 
-            let mut pending_input: VecDeque<u8> = VecDeque::new();
-            const KEYBOARD_INPUT_EVERY_N_CYCLES: usize = 5_000;
-            let mut key_input_counter = KEYBOARD_INPUT_EVERY_N_CYCLES;
-
             while let Ok(command) = rx_command.recv() {
                 match command {
-                    HarnessCommand::Type(bytes) => {
-                        for b in bytes {
-                            pending_input.push_back(b);
-                        }
+                    HarnessCommand::Type(bytes) => keyboard.type_text(&bytes),
+                    HarnessCommand::KeyProgress(tx_progress) => {
+                        tx_progress.send(keyboard.progress()).unwrap();
                     }
                     HarnessCommand::RunCycles(cycles, tx_done) => {
+                        let mut stop = None;
                         for _ in 0..cycles {
-                            if key_input_counter >= KEYBOARD_INPUT_EVERY_N_CYCLES {
-                                if let Some(c) = pending_input.pop_front() {
-                                    inject_keyboard_byte(c, &tx_apple_input);
-                                    key_input_counter = 0;
-                                }
-                            }
-
                             cpu.cycle(false);
-                            key_input_counter += 1;
+                            stop = uninitialized_logs.iter().find_map(|log| log.take_stop());
+                            if stop.is_some() {
+                                break;
+                            }
                         }
-                        tx_done.send(()).unwrap();
+                        tx_done.send(stop).unwrap();
                     }
                     HarnessCommand::Peek(addr, tx_value) => {
-                        tx_value.send(cpu.read(addr)).unwrap();
+                        tx_value
+                            .send(cpu.peek(addr).map_err(|e| e.to_string()))
+                            .unwrap();
+                    }
+                    HarnessCommand::Poke(addr, data) => {
+                        let _ = cpu.poke(addr, data);
                     }
                     HarnessCommand::Stop => break,
                 }
             }
         });
 
+        let rx_output = rx_ready.recv().unwrap();
+
         Apple1ConsoleHarness {
             tx_command,
             rx_output,
@@ -106,26 +104,68 @@ impl Apple1ConsoleHarness {
         }
     }
 
+    /// Queue keys; newlines become CR and letters upper case.
     pub fn type_text(&self, input: &str) {
         self.tx_command
             .send(HarnessCommand::Type(input.as_bytes().to_vec()))
             .unwrap();
     }
 
-    pub fn run_cycles(&self, cycles: usize) {
+    /// Queue the contents of a text file, e.g. a BASIC listing or a Woz monitor dump.
+    pub fn paste_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let text = fs::read(path)?;
+        self.tx_command.send(HarnessCommand::Type(text)).unwrap();
+        Ok(())
+    }
+
+    /// Keys read by the program and keys typed since the queue last ran empty.
+    pub fn key_progress(&self) -> (usize, usize) {
+        let (tx_progress, rx_progress) = unbounded();
+        self.tx_command
+            .send(HarnessCommand::KeyProgress(tx_progress))
+            .unwrap();
+        rx_progress.recv().unwrap()
+    }
+
+    /// Run until all typed keys have been read, at most `max_cycles`; false if some are left.
+    pub fn run_until_typed(&self, max_cycles: usize) -> bool {
+        const CYCLES_PER_CHECK: usize = 10_000;
+        for _ in 0..max_cycles.div_ceil(CYCLES_PER_CHECK) {
+            self.run_cycles(CYCLES_PER_CHECK);
+            let (read, total) = self.key_progress();
+            if read == total {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Run `cycles` cycles or up to a read that stops under `--stop-uninitialized`.
+    pub fn run_cycles(&self, cycles: usize) -> Option<UninitializedRead> {
         let (tx_done, rx_done) = unbounded();
         self.tx_command
             .send(HarnessCommand::RunCycles(cycles, tx_done))
             .unwrap();
-        rx_done.recv().unwrap();
+        rx_done.recv().unwrap()
     }
 
+    /// Memory as the debugger sees it; panics where nothing is mapped.
     pub fn peek_memory(&self, addr: u16) -> u8 {
         let (tx_value, rx_value) = unbounded();
         self.tx_command
             .send(HarnessCommand::Peek(addr, tx_value))
             .unwrap();
-        rx_value.recv().unwrap()
+        rx_value
+            .recv()
+            .unwrap()
+            .unwrap_or_else(|e| panic!("peek_memory: {}", e))
+    }
+
+    /// Pokes to addresses without a component are dropped.
+    pub fn poke_memory(&self, addr: u16, data: u8) {
+        self.tx_command
+            .send(HarnessCommand::Poke(addr, data))
+            .unwrap();
     }
 
     pub fn drain_output_string(&self) -> String {
@@ -156,23 +196,17 @@ impl Drop for Apple1ConsoleHarness {
     }
 }
 
-fn rom_path(file: &str) -> String {
+fn machine_path(file: &str) -> PathBuf {
     let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     path.pop();
-    path.push("roms");
+    path.push("machines");
     path.push(file);
-    path.to_string_lossy().to_string()
+    path
 }
 
-fn inject_keyboard_byte(mut c: u8, tx_apple_input: &Sender<InputSignal>) {
-    if c == b'\n' {
-        c = b'\r';
+fn default_options() -> Options {
+    Options {
+        machine: machine_path("apple1.toml"),
+        ..Options::default()
     }
-
-    tx_apple_input.send(InputSignal::CA1(Signal::Fall)).unwrap();
-    tx_apple_input
-        .send(InputSignal::IRA(c.to_ascii_uppercase() | 0x80))
-        .unwrap();
-    tx_apple_input.send(InputSignal::CA1(Signal::Rise)).unwrap();
-    tx_apple_input.send(InputSignal::CA1(Signal::Fall)).unwrap();
 }
@@ -0,0 +1,26 @@
+use apple1::test_harness::Apple1ConsoleHarness;
+
+// 1.023 MHz / 60 frames per second
+const CYCLES_PER_CHARACTER: usize = 17_045;
+
+#[test]
+fn prompt_is_paced_by_display() {
+    let harness = Apple1ConsoleHarness::start_with_display_timing();
+
+    harness.run_cycles(CYCLES_PER_CHARACTER / 2);
+    let first_frame = harness.drain_output_string();
+    harness.run_cycles(CYCLES_PER_CHARACTER);
+    let second_frame = harness.drain_output_string();
+
+    assert_eq!(first_frame, "\\");
+    assert_eq!(second_frame, "\n");
+}
+
+#[test]
+fn fast_mode_prints_prompt_at_once() {
+    let harness = Apple1ConsoleHarness::start();
+
+    harness.run_cycles(CYCLES_PER_CHARACTER / 2);
+
+    assert_eq!(harness.drain_output_string(), "\\\n");
+}
@@ -0,0 +1,323 @@
+use apple1::options::{
+    load_programs, recording_path, start_program, ImageOption, Options, OptionsError,
+};
+use rust6502::aci::Wav;
+use rust6502::address_bus::ExternalAddressing;
+use rust6502::machine::MachineError;
+use std::path::{Path, PathBuf};
+
+fn args(line: &str) -> Vec<String> {
+    line.split_whitespace().map(str::to_string).collect()
+}
+
+fn repo_path(file: &str) -> String {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.pop();
+    path.push(file);
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn parses_all_options() {
+    let options = Options::parse(args(
+        "--ram 8K --basic=b.bin --monitor m.bin --load 0300:lunar.bin --run prog.hex@0310 \
+         --clock 2M --start $0300 --trace trace.txt --log-uninitialized --stop-uninitialized \
+         --headless other.toml",
+    ))
+    .unwrap();
+
+    assert_eq!(options.machine, PathBuf::from("other.toml"));
+    assert_eq!(options.ram_size, Some(0x2000));
+    assert_eq!(options.basic, Some(PathBuf::from("b.bin")));
+    assert_eq!(options.monitor, Some(PathBuf::from("m.bin")));
+    assert_eq!(
+        options.images,
+        vec![
+            ImageOption {
+                load_addr: Some(0x0300),
+                path: PathBuf::from("lunar.bin"),
+                run: false,
+                entry_point: None,
+            },
+            ImageOption {
+                load_addr: None,
+                path: PathBuf::from("prog.hex"),
+                run: true,
+                entry_point: Some(0x0310),
+            },
+        ]
+    );
+    assert_eq!(options.clock_hz, Some(2_000_000));
+    assert_eq!(options.start, Some(0x0300));
+    assert_eq!(options.trace, Some(PathBuf::from("trace.txt")));
+    assert!(options.log_uninitialized);
+    assert!(options.stop_uninitialized);
+    assert!(options.headless);
+}
+
+#[test]
+fn rejects_bad_arguments() {
+    let errors: Vec<OptionsError> = [
+        "--ram",
+        "--ram 65K",
+        "--ram 256",
+        "--start G000",
+        "--clock 0",
+        "--frobnicate",
+        "a.toml b.toml",
+        "--no-basic --basic b.bin",
+        "--clock inf",
+        "--clock NaN",
+        "--clock 0.5",
+        "--clock -1M",
+        "--run prog.bin@G000",
+    ]
+    .iter()
+    .map(|line| Options::parse(args(line)).unwrap_err())
+    .collect();
+
+    assert_eq!(
+        errors[0],
+        OptionsError::Invalid("--ram needs a value".to_string())
+    );
+    assert_eq!(
+        errors[5],
+        OptionsError::Invalid("unknown option --frobnicate".to_string())
+    );
+    assert!(errors.iter().all(|e| matches!(e, OptionsError::Invalid(_))));
+    assert_eq!(Options::parse(args("-h")), Err(OptionsError::Help));
+}
+
+#[test]
+fn overrides_ram_roms_and_clock() {
+    let mut options = Options::parse(args("--ram 0x8000 --no-basic --clock 2M")).unwrap();
+    options.machine = PathBuf::from(repo_path("machines/apple1.toml"));
+
+    let mut machine = options.build_machine(&[]).unwrap();
+
+    assert_eq!(machine.description.clock_hz, 2_000_000);
+    assert!(machine.bus.write(0x7FFF, 0x55).is_ok());
+    machine.bus.read(0xE000).unwrap(); // open bus on the Apple-1
+    assert_eq!(machine.bus.unmapped_count(), 1);
+    assert_eq!(machine.bus.read(0xFFFC).unwrap(), 0x00); // reset vector of the monitor
+}
+
+#[test]
+fn missing_rom_is_reported_with_its_path() {
+    let mut options = Options::parse(args("--basic ./no/such/basic.bin")).unwrap();
+    options.machine = PathBuf::from(repo_path("machines/apple1.toml"));
+
+    let error = options.build_machine(&[]).err().unwrap();
+
+    assert!(
+        matches!(&error, MachineError::Io { path, .. } if path == &PathBuf::from("./no/such/basic.bin")),
+        "unexpected error {error}"
+    );
+}
+
+#[test]
+fn logs_reads_of_uninitialized_ram() {
+    let options = options_for("--log-uninitialized");
+    let mut machine = options.build_machine(&[]).unwrap();
+    let logs = machine.uninitialized_logs();
+
+    machine.bus.write(0x0300, 0x01).unwrap();
+    machine.bus.read(0x0300).unwrap();
+    let actual = machine.bus.read(0x0301);
+
+    assert_eq!(actual.unwrap(), 0x00);
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].last().map(|read| read.addr), Some(0x0301));
+    assert_eq!(logs[0].len(), 1);
+}
+
+fn options_for(line: &str) -> Options {
+    let mut options = Options::parse(args(line)).unwrap();
+    options.machine = PathBuf::from(repo_path("machines/apple1.toml"));
+    options
+}
+
+#[test]
+fn known_dumps_need_no_load_address() {
+    let options = options_for(&format!(
+        "--load {} --run {}",
+        repo_path("roms/Apple1_ASMmchess.bin"),
+        repo_path("roms/Apple1_Lunar.bin")
+    ));
+
+    let programs = options.programs().unwrap();
+
+    assert_eq!(programs[0].image.ranges()[0].from_addr, 0x0300);
+    assert_eq!(programs[1].entry_point, 0x0300);
+    assert_eq!(options.run_address(&programs), Some(0x0300));
+}
+
+#[test]
+fn raw_binary_at_other_address_starts_there() {
+    let options = options_for(&format!(
+        "--run 4000:{}",
+        repo_path("roms/Apple1_Lunar.bin")
+    ));
+
+    let programs = options.programs().unwrap();
+
+    assert_eq!(options.run_address(&programs), Some(0x4000));
+}
+
+#[test]
+fn entry_point_after_at_replaces_that_of_the_image() {
+    let options = options_for(&format!(
+        "--run 4000:{}@4010",
+        repo_path("roms/Apple1_Lunar.bin")
+    ));
+
+    let programs = options.programs().unwrap();
+
+    assert_eq!(programs[0].image.ranges()[0].from_addr, 0x4000);
+    assert_eq!(options.run_address(&programs), Some(0x4010));
+}
+
+#[test]
+fn anniversary_demo_needs_no_load_address() {
+    let options = options_for(&format!("--load {}", repo_path("roms/Apple1_Basic30.bin")));
+
+    let programs = options.programs().unwrap();
+
+    assert_eq!(programs[0].image.ranges()[0].from_addr, 0x0300);
+    assert_eq!(programs[0].entry_point, 0x0300);
+}
+
+#[test]
+fn unknown_raw_binary_needs_load_address() {
+    let path = std::env::temp_dir().join("apple1_options_unknown.bin");
+    std::fs::write(&path, [0xA9, 0x42, 0x60]).unwrap();
+    let options = options_for(&format!("--load {}", path.display()));
+
+    let error = options.programs().unwrap_err();
+    let _ = std::fs::remove_file(&path);
+
+    assert!(
+        error.ends_with("unknown format, load address needed"),
+        "{error}"
+    );
+}
+
+#[test]
+fn ram_grows_to_programs() {
+    let options = options_for(&format!(
+        "--no-basic --load {} --load E100:{}",
+        repo_path("roms/Apple1_Life.bin"),
+        repo_path("roms/Apple1_Lunar.bin")
+    ));
+    let programs = options.programs().unwrap();
+
+    let description = options.description(&programs).unwrap();
+
+    let rams: Vec<(u16, usize)> = description
+        .rams
+        .iter()
+        .map(|ram| (ram.start, ram.size))
+        .collect();
+    assert_eq!(rams, vec![(0x0000, 0x3000), (0xE000, 0x1000)]);
+}
+
+#[test]
+fn loads_programs_and_jumps_after_monitor_reset() {
+    let options = options_for(&format!("--run {}", repo_path("roms/Apple1_Life.bin")));
+    let programs = options.programs().unwrap();
+    let mut machine = options.build_machine(&programs).unwrap();
+    let pia = machine.take_pia_channels("pia").unwrap();
+    load_programs(&mut machine.bus, &programs).unwrap();
+    let life = std::fs::read(repo_path("roms/Apple1_Life.bin")).unwrap();
+
+    let mut cpu = machine.cpu();
+    cpu.reset();
+    cpu.wait_for_system_reset_cycles();
+    start_program(&mut cpu, 0x2000, 1_000_000);
+
+    assert_eq!(cpu.pc(), 0x2000);
+    assert_eq!(cpu.peek(0x2000).unwrap(), life[0]);
+    assert_eq!(cpu.peek(0x21B7).unwrap(), life[439]);
+    assert!(cpu.cycles() < 1_000_000, "monitor did not wait for a key");
+    assert_eq!(cpu.peek(0xD011).unwrap(), 0x27); // KBDCR as set by the monitor, without IRQ flags
+    assert_eq!(pia.output_b.try_recv().map(|b| b & 0x7F), Ok(b'\\'));
+}
+
+#[test]
+fn starts_program_at_once_without_woz_monitor() {
+    let options = options_for("--no-basic");
+    let mut machine = options.build_machine(&[]).unwrap();
+    // another monitor: JMP $FF00 forever, it would never wait at $FF29
+    for (addr, b) in [(0xFF00, 0x4C), (0xFF01, 0x00), (0xFF02, 0xFF)] {
+        machine.bus.poke(addr, b).unwrap();
+    }
+
+    let mut cpu = machine.cpu();
+    cpu.reset();
+    cpu.wait_for_system_reset_cycles();
+    let started = cpu.cycles();
+    start_program(&mut cpu, 0x0300, 1_000_000);
+
+    assert_eq!(cpu.pc(), 0x0300);
+    assert_eq!(cpu.cycles(), started);
+}
+
+#[test]
+fn aci_with_tapes() {
+    let tape_in = std::env::temp_dir().join("apple1_options_tape_in.wav");
+    Wav {
+        sample_rate: 1000,
+        samples: vec![0x4000; 10],
+    }
+    .write_file(&tape_in)
+    .unwrap();
+    // the monitor image stands in for the ACI PROM, both are 256 bytes
+    let options = options_for(&format!(
+        "--aci {} --tape-in {} --tape-out tape.wav",
+        repo_path("roms/Apple1_HexMonitor.bin"),
+        tape_in.display()
+    ));
+    let monitor = std::fs::read(repo_path("roms/Apple1_HexMonitor.bin")).unwrap();
+
+    let mut machine = options.build_machine(&[]).unwrap();
+    let cassette = options.connect_cassette(&mut machine).unwrap().unwrap();
+    let tape_level = machine.bus.read(0xC081).unwrap();
+    machine.bus.tick();
+
+    assert_eq!(machine.bus.read(0xC100).unwrap(), monitor[0]);
+    assert_eq!(tape_level, monitor[0x80]); // before the first sample the input is low
+    assert_eq!(machine.bus.read(0xC081).unwrap(), monitor[0x81]);
+    assert!(cassette.is_playing());
+    assert!(cassette.take_recording(0).is_none()); // TAPEIN is not recorded
+    machine.bus.read(0xC000).unwrap();
+    assert_eq!(
+        cassette.take_recording(0).map(|wav| wav.sample_rate),
+        Some(22_050)
+    );
+    let _ = std::fs::remove_file(tape_in);
+}
+
+#[test]
+fn tapes_need_an_aci() {
+    let options = options_for("--tape-out tape.wav");
+
+    let error = options.build_machine(&[]).err().unwrap();
+
+    assert!(matches!(error, MachineError::Invalid(_)), "{error}");
+}
+
+#[test]
+fn numbers_recordings_after_the_first() {
+    assert_eq!(
+        recording_path(Path::new("out/tape.wav"), 1),
+        PathBuf::from("out/tape.wav")
+    );
+    assert_eq!(
+        recording_path(Path::new("out/tape.wav"), 3),
+        PathBuf::from("out/tape-3.wav")
+    );
+    assert_eq!(
+        recording_path(Path::new("tape"), 2),
+        PathBuf::from("tape-2")
+    );
+}
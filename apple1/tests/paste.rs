@@ -0,0 +1,59 @@
+use apple1::options::Options;
+use apple1::test_harness::Apple1ConsoleHarness;
+use std::path::PathBuf;
+
+fn text_file(name: &str, text: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("apple1-paste-{}-{}", std::process::id(), name));
+    std::fs::write(&path, text).unwrap();
+    path
+}
+
+#[test]
+fn pasted_dump_keeps_pace_with_display() {
+    let harness = Apple1ConsoleHarness::start_with_display_timing();
+    harness.run_cycles(50_000);
+    let dump = (0..4)
+        .map(|line| {
+            let bytes: Vec<String> = (0..8).map(|i| format!("{:02X}", line * 8 + i)).collect();
+            format!("{:04X}: {}\n", 0x0300 + line * 8, bytes.join(" "))
+        })
+        .collect::<String>();
+    let path = text_file("dump.txt", &dump);
+
+    harness.paste_file(&path).unwrap();
+    let typed = harness.run_until_typed(10_000_000);
+    harness.run_cycles(300_000); // the monitor stores the last line after echoing its address
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(typed, "keys left: {:?}", harness.key_progress());
+    assert_eq!(harness.key_progress(), (dump.len(), dump.len()));
+    for addr in 0x0300..0x0320 {
+        assert_eq!(harness.peek_memory(addr), (addr - 0x0300) as u8);
+    }
+}
+
+#[test]
+fn pasted_basic_listing_runs() {
+    let options = Options {
+        machine: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../machines/apple1.toml"),
+        ..Options::default()
+    };
+    let harness = Apple1ConsoleHarness::start_with_options(options);
+    harness.run_cycles(50_000);
+    let path = text_file(
+        "listing.bas",
+        "e000r\r\n10 for i = 1 to 3\r\n20 print i * 7\r\n30 next i\r\n40 end\r\nrun\r\n",
+    );
+
+    harness.paste_file(&path).unwrap();
+    let typed = harness.run_until_typed(5_000_000);
+    harness.run_cycles(200_000);
+    std::fs::remove_file(&path).unwrap();
+    let output = harness.drain_output_string();
+
+    assert!(typed, "keys left: {:?}", harness.key_progress());
+    assert!(
+        output.contains(">RUN\n7\n14\n21\n"),
+        "expected program output, got: {output:?}"
+    );
+}
@@ -1,4 +1,6 @@
+use apple1::options::Options;
 use apple1::test_harness::Apple1ConsoleHarness;
+use std::path::PathBuf;
 
 fn boot_console() -> Apple1ConsoleHarness {
     let harness = Apple1ConsoleHarness::start();
@@ -47,3 +49,46 @@ fn wozmon_examine_instruction_prints_memory_line() {
         "expected formatted memory line from examine command, got: {output:?}"
     );
 }
+
+#[test]
+fn poked_memory_shows_in_wozmon_examine() {
+    let harness = boot_console();
+    let _ = harness.drain_output_string();
+
+    harness.poke_memory(0x0300, 0xC3);
+    harness.type_text("0300\r");
+    harness.run_cycles(250_000);
+
+    let output = harness.drain_output_string();
+    assert!(
+        output.contains("0300: C3"),
+        "expected poked byte in examine output, got: {output:?}"
+    );
+}
+
+#[test]
+fn stops_at_uninitialized_read_before_next_instruction() {
+    let options = Options {
+        machine: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../machines/apple1.toml"),
+        stop_uninitialized: true,
+        ..Options::default()
+    };
+    let harness = Apple1ConsoleHarness::start_with_options(options);
+    assert_eq!(harness.run_cycles(100_000), None);
+    let _ = harness.drain_output_string();
+
+    harness.type_text("300\r");
+    let stop = harness.run_cycles(300_000);
+    let stopped_output = harness.drain_output_string();
+    let continued = harness.run_cycles(300_000);
+    let continued_output = harness.drain_output_string();
+
+    assert_eq!(stop.map(|read| read.addr), Some(0x0300));
+    // the monitor read the byte but did not print it yet
+    assert!(
+        stopped_output.ends_with("0300: "),
+        "expected output to stop after the address, got: {stopped_output:?}"
+    );
+    assert_eq!(continued, None);
+    assert_eq!(continued_output, "00\n");
+}
@@ -0,0 +1,271 @@
+#[cfg(test)]
+mod tests;
+
+mod wav;
+
+use std::cell::RefCell;
+use std::mem;
+use std::rc::Rc;
+
+use crate::address_bus::{AddressingError, InternalAddressing};
+
+pub use wav::{Wav, WavError};
+
+/// The ACI PROM, mapped in the upper page of the card.
+pub const ROM_SIZE: usize = 256;
+
+/// Sample rate for recordings; the PROM writes at most about 2 kHz.
+pub const RECORDING_SAMPLE_RATE: u32 = 22_050;
+
+const RECORDING_AMPLITUDE: i16 = 0x4000;
+
+/// Apple Cassette Interface, 512 bytes: the I/O page ($C000-$C0FF) and the PROM ($C100-$C1FF).
+/// Any access to the I/O page toggles the output flip-flop; reads there return the PROM byte
+/// with A0 driven by the tape input, which is how the PROM samples it at $C081.
+pub struct Aci {
+    rom: Vec<u8>,
+    output: bool,
+    tape: Rc<RefCell<Tape>>,
+}
+
+struct Tape {
+    clock_hz: u64,
+    cycle: u64,
+    input: bool,
+    playback: Option<Playback>,
+    recording: Option<Recording>,
+}
+
+struct Playback {
+    wav: Wav,
+    threshold: i16,       // hysteresis around zero, from the loudest sample
+    started: Option<u64>, // cycle of the first input sample
+}
+
+struct Recording {
+    sample_rate: u32,
+    samples: Vec<i16>,
+    started: Option<u64>, // cycle of the first output change
+    last_change: u64,
+    level: bool,
+}
+
+impl Aci {
+    /// An ACI for a CPU clock of `clock_hz` with its PROM, `ROM_SIZE` bytes.
+    pub fn new(clock_hz: u64, rom: Vec<u8>) -> Aci {
+        Aci {
+            rom,
+            output: false,
+            tape: Rc::new(RefCell::new(Tape {
+                clock_hz: clock_hz.max(1),
+                cycle: 0,
+                input: false,
+                playback: None,
+                recording: None,
+            })),
+        }
+    }
+
+    /// Tape deck of the interface, to be kept by the host after the ACI is mapped.
+    pub fn cassette(&self) -> Cassette {
+        Cassette(self.tape.clone())
+    }
+
+    fn rom_byte(&self, index: usize) -> u8 {
+        self.rom.get(index).copied().unwrap_or(0xFF)
+    }
+
+    fn io_byte(&self, addr: u16) -> u8 {
+        let input = self.tape.borrow().input as usize;
+        self.rom_byte((addr as usize & 0xFE) | input)
+    }
+
+    // every I/O access toggles the output, but only TAPEOUT (even addresses) is meant to
+    // write the tape; the TAPEIN reads of a load would otherwise be recorded as well
+    fn toggle_output(&mut self, addr: u16) {
+        self.output = !self.output;
+        if addr & 0x01 == 0 {
+            self.tape.borrow_mut().output_toggled();
+        }
+    }
+}
+
+impl InternalAddressing for Aci {
+    fn int_read(&mut self, addr: u16) -> u8 {
+        if addr & 0x100 != 0 {
+            return self.rom_byte(addr as usize & 0xFF);
+        }
+        // the PROM writes by reading TAPEOUT at $C000 and reads the tape at TAPEIN, $C081
+        if addr & 0x01 != 0 {
+            self.tape.borrow_mut().start_playback();
+        }
+        let data = self.io_byte(addr);
+        self.toggle_output(addr);
+        data
+    }
+
+    fn int_peek(&self, addr: u16) -> u8 {
+        if addr & 0x100 != 0 {
+            self.rom_byte(addr as usize & 0xFF)
+        } else {
+            self.io_byte(addr)
+        }
+    }
+
+    fn int_write(&mut self, addr: u16, _data: u8) {
+        if addr & 0x100 == 0 {
+            self.toggle_output(addr);
+        }
+    }
+
+    fn int_poke(&mut self, addr: u16, data: u8) {
+        let index = addr as usize & 0xFF;
+        if addr & 0x100 != 0 && index < self.rom.len() {
+            self.rom[index] = data;
+        }
+    }
+
+    fn len(&self) -> usize {
+        0x200
+    }
+
+    fn tick(&mut self) {
+        self.tape.borrow_mut().tick();
+    }
+
+    fn describe(&self) -> String {
+        "Apple Cassette Interface".to_string()
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        vec![self.output as u8]
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), AddressingError> {
+        if state.len() != 1 {
+            return Err(AddressingError::new("load_state", 0));
+        }
+        self.output = state[0] != 0;
+        Ok(())
+    }
+}
+
+impl Tape {
+    // position in samples at `sample_rate` since cycle `started`
+    fn position(&self, started: u64, sample_rate: u32) -> usize {
+        ((self.cycle - started) as u128 * sample_rate as u128 / self.clock_hz as u128) as usize
+    }
+
+    fn tick(&mut self) {
+        self.cycle += 1;
+
+        let recorded = self.recording.as_ref().and_then(|recording| {
+            let due = self.position(recording.started?, recording.sample_rate);
+            Some((due, recording.level))
+        });
+        if let (Some((due, level)), Some(recording)) = (recorded, self.recording.as_mut()) {
+            let sample = if level {
+                RECORDING_AMPLITUDE
+            } else {
+                -RECORDING_AMPLITUDE
+            };
+            recording
+                .samples
+                .resize(due.max(recording.samples.len()), sample);
+        }
+
+        if let Some(playback) = &self.playback {
+            if let Some(started) = playback.started {
+                let position = self.position(started, playback.wav.sample_rate);
+                match playback.wav.samples.get(position) {
+                    Some(sample) if *sample > playback.threshold => self.input = true,
+                    Some(sample) if *sample < -playback.threshold => self.input = false,
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    fn start_playback(&mut self) {
+        if let Some(playback) = self.playback.as_mut() {
+            playback.started.get_or_insert(self.cycle);
+        }
+    }
+
+    // the recording keeps its own level, so edges are not lost to toggles it does not see
+    fn output_toggled(&mut self) {
+        if let Some(recording) = self.recording.as_mut() {
+            recording.started.get_or_insert(self.cycle);
+            recording.last_change = self.cycle;
+            recording.level = !recording.level;
+        }
+    }
+}
+
+/// Host side of an `Aci`: the recording played into its input and the one made of its output.
+#[derive(Clone)]
+pub struct Cassette(Rc<RefCell<Tape>>);
+
+impl Cassette {
+    /// Put a recording in; it starts playing when the ACI first reads TAPEIN.
+    pub fn insert(&self, wav: Wav) {
+        let peak = wav
+            .samples
+            .iter()
+            .map(|sample| sample.saturating_abs())
+            .max()
+            .unwrap_or(0);
+        self.0.borrow_mut().playback = Some(Playback {
+            wav,
+            threshold: (peak / 4).max(1),
+            started: None,
+        });
+    }
+
+    /// Wind the inserted recording back, to play again at the next read.
+    pub fn rewind(&self) {
+        if let Some(playback) = self.0.borrow_mut().playback.as_mut() {
+            playback.started = None;
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        let tape = self.0.borrow();
+        match &tape.playback {
+            Some(Playback {
+                wav,
+                started: Some(started),
+                ..
+            }) => tape.position(*started, wav.sample_rate) < wav.samples.len(),
+            _ => false,
+        }
+    }
+
+    /// Record the output, from its next change on.
+    pub fn record(&self, sample_rate: u32) {
+        self.0.borrow_mut().recording = Some(Recording {
+            sample_rate,
+            samples: vec![],
+            started: None,
+            last_change: 0,
+            level: false,
+        });
+    }
+
+    /// What was recorded, once the output has not changed for `idle_cycles`; recording goes
+    /// on with the next change.
+    pub fn take_recording(&self, idle_cycles: u64) -> Option<Wav> {
+        let mut tape = self.0.borrow_mut();
+        let cycle = tape.cycle;
+        let recording = tape.recording.as_mut()?;
+        recording.started?;
+        if cycle - recording.last_change < idle_cycles {
+            return None;
+        }
+        recording.started = None;
+        Some(Wav {
+            sample_rate: recording.sample_rate,
+            samples: mem::take(&mut recording.samples),
+        })
+    }
+}
@@ -0,0 +1,164 @@
+#[cfg(test)]
+use super::*;
+
+const TAPEOUT: u16 = 0xC000;
+const TAPEIN: u16 = 0xC081;
+const CLOCK_HZ: u64 = 1_000_000;
+
+fn rom() -> Vec<u8> {
+    (0..ROM_SIZE).map(|i| i as u8).collect()
+}
+
+fn ticks(aci: &mut Aci, n: u64) {
+    for _ in 0..n {
+        aci.tick();
+    }
+}
+
+// square wave of `half_period` cycles per level, `changes` times
+fn square_wave(aci: &mut Aci, half_period: u64, changes: usize) {
+    for _ in 0..changes {
+        aci.int_read(TAPEOUT);
+        ticks(aci, half_period);
+    }
+}
+
+#[test]
+fn prom_in_upper_page_and_input_on_a0() {
+    // arrange
+    let mut aci = Aci::new(CLOCK_HZ, rom());
+    let cassette = aci.cassette();
+    cassette.insert(Wav {
+        sample_rate: 1000,
+        samples: vec![-1000, 1000],
+    });
+
+    // act
+    let prom = (aci.int_read(0xC100), aci.int_read(0xC1FF));
+    let low = aci.int_read(TAPEIN);
+    ticks(&mut aci, 1000);
+    let high = (aci.int_read(TAPEIN), aci.int_read(0xC080));
+    let playing = cassette.is_playing();
+    ticks(&mut aci, 1000);
+
+    // assert
+    assert_eq!(prom, (0x00, 0xFF));
+    assert_eq!(low, 0x80);
+    assert_eq!(high, (0x81, 0x81));
+    assert!(playing);
+    assert!(!cassette.is_playing());
+}
+
+#[test]
+fn any_io_access_toggles_output() {
+    // arrange
+    let mut aci = Aci::new(CLOCK_HZ, rom());
+    let cassette = aci.cassette();
+    cassette.record(1000);
+
+    // act
+    aci.int_read(TAPEOUT);
+    ticks(&mut aci, 2000);
+    aci.int_write(0xC0FE, 0);
+    ticks(&mut aci, 2000);
+    aci.int_read(0xC100); // PROM, no toggle
+    aci.int_peek(TAPEOUT); // no side effects
+    ticks(&mut aci, 1000);
+    let too_early = cassette.take_recording(3500);
+    ticks(&mut aci, 1000);
+    let recording = cassette.take_recording(3500).unwrap();
+
+    // assert
+    assert!(too_early.is_none());
+    assert_eq!(recording.sample_rate, 1000);
+    assert_eq!(
+        recording.samples,
+        [vec![RECORDING_AMPLITUDE; 2], vec![-RECORDING_AMPLITUDE; 4]].concat()
+    );
+    assert!(cassette.take_recording(0).is_none());
+}
+
+#[test]
+fn loading_from_tape_records_nothing() {
+    // arrange
+    let mut aci = Aci::new(CLOCK_HZ, rom());
+    let cassette = aci.cassette();
+    cassette.insert(Wav {
+        sample_rate: 1000,
+        samples: vec![0x4000, -0x4000, 0x4000, -0x4000],
+    });
+    cassette.record(1000);
+
+    // act: the PROM's read loop polls TAPEIN, which toggles the output as well
+    while cassette.is_playing() {
+        aci.int_read(TAPEIN);
+        ticks(&mut aci, 100);
+    }
+    ticks(&mut aci, 1000);
+
+    // assert
+    assert!(cassette.take_recording(0).is_none());
+}
+
+#[test]
+fn recording_plays_back_into_input() {
+    // arrange
+    let mut writer = Aci::new(CLOCK_HZ, rom());
+    writer.cassette().record(RECORDING_SAMPLE_RATE);
+    square_wave(&mut writer, 500, 20); // 1 kHz
+    square_wave(&mut writer, 250, 20); // 2 kHz
+    let recording = writer.cassette().take_recording(250).unwrap();
+    let mut reader = Aci::new(CLOCK_HZ, rom());
+    reader
+        .cassette()
+        .insert(Wav::parse(&recording.to_bytes()).unwrap());
+
+    // act: count the cycles between input changes, as the PROM's read loop does
+    let mut last = reader.int_read(TAPEIN);
+    let mut since_change = 0;
+    let mut half_periods = vec![];
+    while reader.cassette().is_playing() {
+        reader.tick();
+        since_change += 1;
+        let input = reader.int_peek(TAPEIN);
+        if input != last {
+            half_periods.push(since_change);
+            since_change = 0;
+            last = input;
+        }
+    }
+
+    // assert
+    // the first change is the input leaving its idle level; edges are a sample apart at most
+    assert_eq!(half_periods.len(), 40);
+    assert!(half_periods[1..=20].iter().all(|n| (450..=550).contains(n)));
+    assert!(half_periods[21..].iter().all(|n| (200..=300).contains(n)));
+}
+
+#[test]
+fn parses_8_bit_stereo_wav() {
+    // arrange
+    let mut data = Wav {
+        sample_rate: 8000,
+        samples: vec![],
+    }
+    .to_bytes();
+    data[22] = 2; // channels
+    data[34] = 8; // bits per sample
+    data.truncate(40);
+    data.extend_from_slice(&4u32.to_le_bytes());
+    data.extend_from_slice(&[0xFF, 0x80, 0x00, 0x80]);
+
+    // act
+    let actual = Wav::parse(&data);
+
+    // assert
+    assert_eq!(
+        actual,
+        Ok(Wav {
+            sample_rate: 8000,
+            samples: vec![0x7F00, -0x8000],
+        })
+    );
+    assert!(matches!(Wav::parse(b"RIFF...."), Err(WavError::Format(_))));
+}
@@ -0,0 +1,118 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Mono 16 bit PCM samples, as recorded from or played into a cassette interface.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wav {
+    pub sample_rate: u32,
+    pub samples: Vec<i16>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WavError {
+    Io(String),
+    Format(String),
+}
+
+impl fmt::Display for WavError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WavError::Io(message) => write!(f, "{}", message),
+            WavError::Format(message) => write!(f, "not a PCM WAV file: {}", message),
+        }
+    }
+}
+
+impl Wav {
+    /// Parse a RIFF WAV file of 8 or 16 bit PCM; of several channels only the first is kept.
+    pub fn parse(data: &[u8]) -> Result<Wav, WavError> {
+        let format_error = |message: &str| Err(WavError::Format(message.to_string()));
+        if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+            return format_error("no RIFF WAVE header");
+        }
+
+        let mut format: Option<(u16, u16, u32, u16)> = None; // tag, channels, rate, bits
+        let mut pos = 12;
+        while pos + 8 <= data.len() {
+            let id = &data[pos..pos + 4];
+            let size =
+                u32::from_le_bytes([data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]])
+                    as usize;
+            let body = &data[pos + 8..data.len().min(pos + 8 + size)];
+            match id {
+                b"fmt " if body.len() >= 16 => {
+                    format = Some((
+                        u16::from_le_bytes([body[0], body[1]]),
+                        u16::from_le_bytes([body[2], body[3]]),
+                        u32::from_le_bytes([body[4], body[5], body[6], body[7]]),
+                        u16::from_le_bytes([body[14], body[15]]),
+                    ));
+                }
+                b"data" => {
+                    let (tag, channels, sample_rate, bits) = match format {
+                        Some(format) => format,
+                        None => return format_error("data before fmt chunk"),
+                    };
+                    if tag != 1 || channels == 0 || sample_rate == 0 {
+                        return format_error("only uncompressed PCM is supported");
+                    }
+                    let samples = match bits {
+                        8 => body
+                            .iter()
+                            .step_by(channels as usize)
+                            .map(|b| ((*b as i16) - 128) << 8)
+                            .collect(),
+                        16 => body
+                            .chunks_exact(2)
+                            .step_by(channels as usize)
+                            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+                            .collect(),
+                        _ => return format_error("only 8 and 16 bit samples are supported"),
+                    };
+                    return Ok(Wav {
+                        sample_rate,
+                        samples,
+                    });
+                }
+                _ => (),
+            }
+            pos += 8 + size + size % 2; // chunks are padded to even sizes
+        }
+        format_error("no data chunk")
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let data_size = self.samples.len() as u32 * 2;
+        let mut bytes = Vec::with_capacity(44 + data_size as usize);
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // mono
+        bytes.extend_from_slice(&self.sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(self.sample_rate * 2).to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes()); // block align
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_size.to_le_bytes());
+        for sample in &self.samples {
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn read_file(path: impl AsRef<Path>) -> Result<Wav, WavError> {
+        let path = path.as_ref();
+        let data =
+            fs::read(path).map_err(|e| WavError::Io(format!("{}: {}", path.display(), e)))?;
+        Wav::parse(&data)
+    }
+
+    pub fn write_file(&self, path: impl AsRef<Path>) -> Result<(), WavError> {
+        let path = path.as_ref();
+        fs::write(path, self.to_bytes())
+            .map_err(|e| WavError::Io(format!("{}: {}", path.display(), e)))
+    }
+}
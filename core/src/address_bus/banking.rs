@@ -0,0 +1,155 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::address_bus::{AccessContext, AddressingError, InternalAddressing};
+use crate::memory::Memory;
+
+/// Window showing one of up to 256 `Memory` banks. All banks are built for the window address;
+/// the selection is shared with the `BankRegister` handed out by `register`.
+pub struct BankSwitched {
+    banks: Vec<Memory>,
+    selected: Rc<Cell<usize>>,
+}
+
+impl BankSwitched {
+    pub fn new(banks: Vec<Memory>) -> BankSwitched {
+        assert!(!banks.is_empty(), "bank switched component without banks");
+        // the bank register and the saved state hold the bank number in one byte
+        assert!(banks.len() <= 0x100, "more than 256 banks");
+        BankSwitched {
+            banks,
+            selected: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// Memory-mapped register selecting the bank; reads return the current bank number.
+    pub fn register(&self) -> BankRegister {
+        BankRegister {
+            selected: self.selected.clone(),
+            bank_count: self.banks.len(),
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected.get()
+    }
+
+    pub fn select(&mut self, bank: usize) {
+        self.selected.set(bank % self.banks.len());
+    }
+
+    pub fn bank_count(&self) -> usize {
+        self.banks.len()
+    }
+
+    pub fn bank(&self, bank: usize) -> Option<&Memory> {
+        self.banks.get(bank)
+    }
+}
+
+impl InternalAddressing for BankSwitched {
+    fn int_read(&mut self, addr: u16) -> u8 {
+        self.banks[self.selected.get()].int_read(addr)
+    }
+
+    fn int_write(&mut self, addr: u16, data: u8) {
+        self.banks[self.selected.get()].int_write(addr, data)
+    }
+
+    fn int_peek(&self, addr: u16) -> u8 {
+        self.banks[self.selected.get()].int_peek(addr)
+    }
+
+    fn int_poke(&mut self, addr: u16, data: u8) {
+        self.banks[self.selected.get()].int_poke(addr, data)
+    }
+
+    fn int_read_checked(
+        &mut self,
+        addr: u16,
+        context: AccessContext,
+    ) -> Result<u8, AddressingError> {
+        self.banks[self.selected.get()].int_read_checked(addr, context)
+    }
+
+    fn int_write_checked(
+        &mut self,
+        addr: u16,
+        data: u8,
+        context: AccessContext,
+    ) -> Result<(), AddressingError> {
+        self.banks[self.selected.get()].int_write_checked(addr, data, context)
+    }
+
+    fn len(&self) -> usize {
+        self.banks.iter().map(|bank| bank.len()).min().unwrap_or(0)
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "bank {} of {}, {}",
+            self.selected.get(),
+            self.banks.len(),
+            self.banks[self.selected.get()].describe()
+        )
+    }
+
+    // selected bank followed by the contents of all banks
+    fn save_state(&self) -> Vec<u8> {
+        let mut state = vec![self.selected.get() as u8];
+        for bank in &self.banks {
+            state.extend(bank.save_state());
+        }
+        state
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), AddressingError> {
+        let expected = 1 + self.banks.iter().map(|bank| bank.len()).sum::<usize>();
+        if state.len() != expected || state[0] as usize >= self.banks.len() {
+            return Err(AddressingError::new("load_state", 0));
+        }
+
+        let mut pos = 1;
+        for bank in self.banks.iter_mut() {
+            bank.load_state(&state[pos..pos + bank.len()])?;
+            pos += bank.len();
+        }
+        self.selected.set(state[0] as usize);
+        Ok(())
+    }
+}
+
+pub struct BankRegister {
+    selected: Rc<Cell<usize>>,
+    bank_count: usize,
+}
+
+impl InternalAddressing for BankRegister {
+    fn int_read(&mut self, _addr: u16) -> u8 {
+        self.selected.get() as u8
+    }
+
+    fn int_write(&mut self, _addr: u16, data: u8) {
+        self.selected.set(data as usize % self.bank_count);
+    }
+
+    fn int_peek(&self, _addr: u16) -> u8 {
+        self.selected.get() as u8
+    }
+
+    fn int_poke(&mut self, addr: u16, data: u8) {
+        self.int_write(addr, data)
+    }
+
+    fn len(&self) -> usize {
+        1
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "bank register ({} of {})",
+            self.selected.get(),
+            self.bank_count
+        )
+    }
+}
@@ -0,0 +1,237 @@
+use std::fmt;
+
+use crate::address_bus::ComponentId;
+
+/// Incomplete address decoding as done by real hardware: a component is selected for every
+/// address with `addr & mask == matches` and only sees the address lines in `lines`, placed
+/// on top of `base`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decoding {
+    pub mask: u16,
+    pub matches: u16,
+    pub lines: u16,
+    pub base: u16,
+}
+
+impl Decoding {
+    pub fn new(mask: u16, matches: u16) -> Decoding {
+        Decoding {
+            mask,
+            matches,
+            lines: 0xFFFF,
+            base: 0,
+        }
+    }
+
+    /// Only pass `lines` of the address to the component, e.g. to fold a mirror back onto
+    /// the range the component was built for.
+    pub fn with_lines(self, lines: u16, base: u16) -> Decoding {
+        Decoding {
+            lines,
+            base,
+            ..self
+        }
+    }
+
+    pub fn selects(&self, addr: u16) -> bool {
+        addr & self.mask == self.matches
+    }
+
+    pub fn translate(&self, addr: u16) -> u16 {
+        self.base.wrapping_add(addr & self.lines)
+    }
+}
+
+/// Where a mapping places a component on the bus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    /// 1:1 at `from_addr..from_addr + size`
+    Range { from_addr: u16, size: usize },
+    /// at `from_addr..from_addr + size`, seen by the component as `target_addr..`
+    Mirror {
+        from_addr: u16,
+        size: usize,
+        target_addr: u16,
+    },
+    /// at every address selected by the decoding
+    Decoded(Decoding),
+}
+
+impl Region {
+    pub fn contains(&self, addr: u16) -> bool {
+        match *self {
+            Region::Range { from_addr, size }
+            | Region::Mirror {
+                from_addr, size, ..
+            } => addr >= from_addr && ((addr - from_addr) as usize) < size,
+            Region::Decoded(decoding) => decoding.selects(addr),
+        }
+    }
+
+    /// Lowest and highest address the region can contain.
+    pub fn bounds(&self) -> (u16, u16) {
+        match *self {
+            Region::Range { from_addr, size }
+            | Region::Mirror {
+                from_addr, size, ..
+            } => (from_addr, (from_addr as usize + size - 1) as u16),
+            Region::Decoded(decoding) => {
+                let lowest = decoding.matches & decoding.mask;
+                (lowest, lowest | !decoding.mask)
+            }
+        }
+    }
+
+    #[inline]
+    pub fn translate(&self, addr: u16) -> u16 {
+        match *self {
+            Region::Range { .. } => addr,
+            Region::Mirror {
+                from_addr,
+                target_addr,
+                ..
+            } => addr.wrapping_sub(from_addr).wrapping_add(target_addr),
+            Region::Decoded(decoding) => decoding.translate(addr),
+        }
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Region::Range { from_addr, size } => {
+                write!(
+                    f,
+                    "${:04X}-${:04X}",
+                    from_addr,
+                    from_addr as usize + size - 1
+                )
+            }
+            Region::Mirror {
+                from_addr,
+                size,
+                target_addr,
+            } => write!(
+                f,
+                "${:04X}-${:04X} -> ${:04X}",
+                from_addr,
+                from_addr as usize + size - 1,
+                target_addr
+            ),
+            Region::Decoded(decoding) => write!(
+                f,
+                "addr & ${:04X} == ${:04X}",
+                decoding.mask, decoding.matches
+            ),
+        }
+    }
+}
+
+/// Bus directions a mapping claims; a read-only mapping lets writes fall through to
+/// whatever is mapped below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    ReadWrite,
+    ReadOnly,
+    WriteOnly,
+}
+
+impl Access {
+    pub fn reads(&self) -> bool {
+        *self != Access::WriteOnly
+    }
+
+    pub fn writes(&self) -> bool {
+        *self != Access::ReadOnly
+    }
+}
+
+/// Access and priority of a mapping. Where mappings overlap the one with the higher
+/// priority wins; overlaps with equal priority are rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MapOptions {
+    pub access: Access,
+    pub priority: u8,
+    pub wait_states: u8, // extra CPU cycles for every access through this mapping
+}
+
+impl MapOptions {
+    pub fn new(access: Access, priority: u8) -> MapOptions {
+        MapOptions {
+            access,
+            priority,
+            wait_states: 0,
+        }
+    }
+
+    pub fn with_wait_states(self, wait_states: u8) -> MapOptions {
+        MapOptions {
+            wait_states,
+            ..self
+        }
+    }
+}
+
+impl Default for MapOptions {
+    fn default() -> Self {
+        MapOptions::new(Access::ReadWrite, 0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MappingError {
+    UnknownComponent(ComponentId),
+    InvalidRange {
+        from_addr: u16,
+        size: usize,
+    },
+    ComponentTooSmall {
+        region: Region,
+        len: usize,
+    },
+    Overlap {
+        addr: u16,
+        region: Region,
+        existing: Region,
+        priority: u8,
+    },
+}
+
+impl fmt::Display for MappingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MappingError::UnknownComponent(id) => write!(f, "unknown component #{}", id.0),
+            MappingError::InvalidRange { from_addr, size } => {
+                write!(f, "invalid range of {} bytes from ${:04X}", size, from_addr)
+            }
+            MappingError::ComponentTooSmall { region, len } => {
+                write!(f, "mapping {} exceeds component of {} bytes", region, len)
+            }
+            MappingError::Overlap {
+                addr,
+                region,
+                existing,
+                priority,
+            } => write!(
+                f,
+                "mapping {} overlaps {} at ${:04X} with same priority {}",
+                region, existing, addr, priority
+            ),
+        }
+    }
+}
+
+pub(crate) struct Mapping {
+    pub component: usize,
+    pub region: Region,
+    pub options: MapOptions,
+}
+
+/// One row of `AddressBus::memory_map`: a run of addresses resolved to the same mappings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryMapEntry {
+    pub from_addr: u16,
+    pub to_addr: u16,
+    pub read: Option<ComponentId>,
+    pub write: Option<ComponentId>,
+}
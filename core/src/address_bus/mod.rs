@@ -1,7 +1,18 @@
 #[cfg(test)]
 mod tests;
 
+mod banking;
+mod mapping;
+mod observer;
+
 use std::fmt;
+use std::ops::{Deref, DerefMut, Range};
+
+pub use banking::{BankRegister, BankSwitched};
+use mapping::Mapping;
+pub use mapping::{Access, Decoding, MapOptions, MappingError, MemoryMapEntry, Region};
+use observer::Observers;
+pub use observer::{BusAccess, BusObserver, ObservedBus};
 
 #[derive(Debug)]
 pub struct AddressingError {
@@ -13,7 +24,7 @@ impl AddressingError {
     pub fn new(operation: &str, addr: u16) -> AddressingError {
         AddressingError {
             operation: operation.to_string(),
-            addr: addr,
+            addr,
         }
     }
 }
@@ -28,25 +39,235 @@ pub trait InternalAddressing {
     fn int_read(&mut self, addr: u16) -> u8;
     fn int_write(&mut self, addr: u16, data: u8);
     fn len(&self) -> usize;
+
+    /// Debugger access: like `int_read` / `int_write`, but without side effects such as
+    /// clearing flags, consuming input or refusing writes to ROM. Components that do not
+    /// override them read as open bus ($FF) and drop pokes.
+    fn int_peek(&self, _addr: u16) -> u8 {
+        0xFF
+    }
+
+    fn int_poke(&mut self, _addr: u16, _data: u8) {}
+
+    /// Read as issued through a bus, with the CPU context; components refusing a read
+    /// report it here.
+    fn int_read_checked(
+        &mut self,
+        addr: u16,
+        _context: AccessContext,
+    ) -> Result<u8, AddressingError> {
+        Ok(self.int_read(addr))
+    }
+
+    /// Write as issued through a bus, with the CPU context; components refusing a write
+    /// report it here.
+    fn int_write_checked(
+        &mut self,
+        addr: u16,
+        data: u8,
+        _context: AccessContext,
+    ) -> Result<(), AddressingError> {
+        self.int_write(addr, data);
+        Ok(())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Extra CPU cycles the access just made takes, on top of the wait states of the mapping.
+    fn wait_states(&self, _addr: u16, _direction: Direction) -> u8 {
+        0
+    }
+
+    /// One clock cycle passed; for devices with timing of their own.
+    fn tick(&mut self) {}
+
+    fn describe(&self) -> String {
+        "component".to_string()
+    }
+
+    // components without state worth saving keep the defaults
+    fn save_state(&self) -> Vec<u8> {
+        vec![]
+    }
+
+    fn load_state(&mut self, _state: &[u8]) -> Result<(), AddressingError> {
+        Ok(())
+    }
 }
 
 pub trait ExternalAddressing {
     fn read(&mut self, addr: u16) -> Result<u8, AddressingError>;
     fn write(&mut self, addr: u16, data: u8) -> Result<(), AddressingError>;
+
+    /// Debugger access, see `InternalAddressing::int_peek`; not seen by bus observers.
+    fn peek(&self, addr: u16) -> Result<u8, AddressingError>;
+    fn poke(&mut self, addr: u16, data: u8) -> Result<(), AddressingError>;
+
+    /// Called by the CPU before each instruction, so accesses can be attributed to it.
+    fn set_context(&mut self, _context: AccessContext) {}
+
+    /// Wait cycles accumulated by accesses since the last call; the CPU adds them to the
+    /// instruction just executed.
+    fn take_wait_cycles(&mut self) -> u64 {
+        0
+    }
+
+    /// Called by the CPU once per clock cycle, to be passed on to the components.
+    fn tick(&mut self) {}
+}
+
+/// CPU state the following bus accesses belong to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccessContext {
+    pub pc: u16,
+    /// start cycle of the instruction at `pc`
+    pub cycle: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Read,
+    Write,
+}
+
+/// How an `AddressBus` answers accesses to addresses without a component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnmappedPolicy {
+    /// fail the access with an `AddressingError`
+    Error,
+    /// reads return the last byte seen on the data bus, writes are dropped
+    OpenBus,
+    /// reads return the given byte, writes are dropped
+    Fixed(u8),
+    /// like `OpenBus`, but every access is kept in the unmapped access log
+    IgnoreWithLog,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnmappedAccess {
+    pub addr: u16,
+    pub data: u8,
+    pub direction: Direction,
+    pub pc: u16,
+}
+
+const UNMAPPED_LOG_LIMIT: usize = 1024; // keep the first accesses, they show where things went wrong
+
+/// Handle to a component attached to an `AddressBus`, used to map it at further addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComponentId(usize);
+
+// components are borrowed from the caller or, for self-contained machines, owned by the bus
+enum Component<'a> {
+    Borrowed(&'a mut dyn InternalAddressing),
+    Owned(Box<dyn InternalAddressing>),
+}
+
+impl<'a> Deref for Component<'a> {
+    type Target = dyn InternalAddressing + 'a;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Component::Borrowed(component) => &**component,
+            Component::Owned(component) => &**component,
+        }
+    }
+}
+
+impl DerefMut for Component<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Component::Borrowed(component) => &mut **component,
+            Component::Owned(component) => &mut **component,
+        }
+    }
 }
 
 pub struct AddressBus<'a> {
     block_size: usize,
-    block_component_map: Vec<usize>, // map a 1..n blocks to 1 components
-    component_addr: Vec<&'a mut dyn InternalAddressing>, // 1:1 map component to its addressing
+    block_read_map: Vec<usize>, // map a 1..n blocks to 1 mapping, or to a split block
+    block_write_map: Vec<usize>,
+    split_map: Vec<usize>, // per address mapping of blocks shared by several mappings
+    mappings: Vec<Mapping>, // n:1 map mappings to components
+    component_addr: Vec<Component<'a>>, // 1:1 map component to its addressing
+    unmapped_policy: UnmappedPolicy,
+    unmapped_count: u64,
+    unmapped_log: Vec<UnmappedAccess>,
+    last_data: u8, // last byte on the data bus, returned for open bus reads
+    wait_cycles: u64,
+    context: AccessContext,
+    observers: Observers<'a>,
 }
 
+const UNMAPPED: usize = usize::MAX;
+const SPLIT_BLOCK: usize = 1 << (usize::BITS - 1);
+
 impl<'a> AddressBus<'a> {
     pub fn new(block_size: usize) -> AddressBus<'a> {
         AddressBus {
-            block_size: block_size,
-            block_component_map: vec![usize::MAX; 0x10000 / block_size], // assume 64kB max addressable space
+            block_size,
+            block_read_map: vec![UNMAPPED; 0x10000 / block_size], // assume 64kB max addressable space
+            block_write_map: vec![UNMAPPED; 0x10000 / block_size],
+            split_map: vec![],
+            mappings: vec![],
             component_addr: vec![],
+            unmapped_policy: UnmappedPolicy::Error,
+            unmapped_count: 0,
+            unmapped_log: vec![],
+            last_data: 0,
+            wait_cycles: 0,
+            context: AccessContext::default(),
+            observers: Observers::default(),
+        }
+    }
+
+    /// Observers see every access that reaches the bus, in the order they were added.
+    pub fn add_observer(&mut self, observer: &'a mut dyn BusObserver) {
+        self.observers.add(observer);
+    }
+
+    pub fn clear_observers(&mut self) {
+        self.observers.clear();
+    }
+
+    pub fn set_unmapped_policy(&mut self, policy: UnmappedPolicy) {
+        self.unmapped_policy = policy;
+    }
+
+    /// Number of accesses to unmapped addresses, regardless of policy.
+    pub fn unmapped_count(&self) -> u64 {
+        self.unmapped_count
+    }
+
+    /// First accesses to unmapped addresses while `UnmappedPolicy::IgnoreWithLog` was active.
+    pub fn unmapped_log(&self) -> &[UnmappedAccess] {
+        &self.unmapped_log
+    }
+
+    pub fn clear_unmapped_log(&mut self) {
+        self.unmapped_count = 0;
+        self.unmapped_log.clear();
+    }
+
+    fn unmapped_access(&mut self, addr: u16, data: u8, direction: Direction) -> Option<u8> {
+        self.unmapped_count += 1;
+        match self.unmapped_policy {
+            UnmappedPolicy::Error => None,
+            UnmappedPolicy::OpenBus => Some(self.last_data),
+            UnmappedPolicy::Fixed(value) => Some(value),
+            UnmappedPolicy::IgnoreWithLog => {
+                if self.unmapped_log.len() < UNMAPPED_LOG_LIMIT {
+                    self.unmapped_log.push(UnmappedAccess {
+                        addr,
+                        data,
+                        direction,
+                        pc: self.context.pc,
+                    });
+                }
+                Some(self.last_data)
+            }
         }
     }
 
@@ -55,54 +276,425 @@ impl<'a> AddressBus<'a> {
         from_addr: u16,
         size: usize,
         component: &'a mut dyn InternalAddressing,
-    ) -> Result<(), AddressingError> {
-        let size_outside_blocks = size % self.block_size as usize;
-        let mem_outside_block = component.len() % self.block_size as usize;
+    ) -> Result<ComponentId, MappingError> {
+        self.add_component_with(from_addr, size, component, MapOptions::default())
+    }
 
-        if size_outside_blocks == 0 && mem_outside_block == 0 {
-            let component_key = self.component_addr.len();
-            self.component_addr.push(component);
+    pub fn add_component_with(
+        &mut self,
+        from_addr: u16,
+        size: usize,
+        component: &'a mut dyn InternalAddressing,
+        options: MapOptions,
+    ) -> Result<ComponentId, MappingError> {
+        let id = self.attach_component(component);
+        if let Err(e) = self.map_with(id, Region::Range { from_addr, size }, options) {
+            self.component_addr.pop();
+            return Err(e);
+        }
+        Ok(id)
+    }
+
+    /// Register a component without mapping it; use `map_range`, `map_mirror`, `map_decoded`
+    /// or `map_with` to make it visible on the bus.
+    pub fn attach_component(&mut self, component: &'a mut dyn InternalAddressing) -> ComponentId {
+        self.component_addr.push(Component::Borrowed(component));
+        ComponentId(self.component_addr.len() - 1)
+    }
+
+    /// Like `add_component`, but the bus takes ownership of the component.
+    pub fn add_owned_component(
+        &mut self,
+        from_addr: u16,
+        size: usize,
+        component: Box<dyn InternalAddressing>,
+    ) -> Result<ComponentId, MappingError> {
+        let id = self.attach_owned_component(component);
+        if let Err(e) = self.map_range(id, from_addr, size) {
+            self.component_addr.pop();
+            return Err(e);
+        }
+        Ok(id)
+    }
 
-            let from_block = from_addr as usize / self.block_size;
-            let to_block = ((from_addr as usize + size as usize - 1) / self.block_size) + 1;
-            for block in from_block..to_block {
-                self.block_component_map[block] = component_key;
+    pub fn attach_owned_component(
+        &mut self,
+        component: Box<dyn InternalAddressing>,
+    ) -> ComponentId {
+        self.component_addr.push(Component::Owned(component));
+        ComponentId(self.component_addr.len() - 1)
+    }
+
+    /// Map a component 1:1 at `from_addr..from_addr + size`.
+    pub fn map_range(
+        &mut self,
+        id: ComponentId,
+        from_addr: u16,
+        size: usize,
+    ) -> Result<(), MappingError> {
+        self.map_with(id, Region::Range { from_addr, size }, MapOptions::default())
+    }
+
+    /// Map `from_addr..from_addr + size` so that the component sees the accesses as if they
+    /// went to `target_addr..target_addr + size`.
+    pub fn map_mirror(
+        &mut self,
+        id: ComponentId,
+        from_addr: u16,
+        size: usize,
+        target_addr: u16,
+    ) -> Result<(), MappingError> {
+        let region = Region::Mirror {
+            from_addr,
+            size,
+            target_addr,
+        };
+        self.map_with(id, region, MapOptions::default())
+    }
+
+    /// Map a component at every address selected by `decoding`.
+    pub fn map_decoded(&mut self, id: ComponentId, decoding: Decoding) -> Result<(), MappingError> {
+        self.map_with(id, Region::Decoded(decoding), MapOptions::default())
+    }
+
+    pub fn map_with(
+        &mut self,
+        id: ComponentId,
+        region: Region,
+        options: MapOptions,
+    ) -> Result<(), MappingError> {
+        let component = match self.component_addr.get(id.0) {
+            Some(component) => component,
+            None => return Err(MappingError::UnknownComponent(id)),
+        };
+
+        if let Region::Range { from_addr, size }
+        | Region::Mirror {
+            from_addr, size, ..
+        } = region
+        {
+            if size == 0 || from_addr as usize + size > 0x10000 {
+                return Err(MappingError::InvalidRange { from_addr, size });
+            }
+            // components with a length address their bytes from the mapped start
+            if !component.is_empty() && size > component.len() {
+                return Err(MappingError::ComponentTooSmall {
+                    region,
+                    len: component.len(),
+                });
+            }
+        }
+
+        self.mappings.push(Mapping {
+            component: id.0,
+            region,
+            options,
+        });
+
+        let blocks = self.blocks_of(&region);
+        match self.resolve(self.mappings.len() - 1, blocks.clone()) {
+            Ok((read_keys, write_keys)) => {
+                self.update_lookup(blocks, &read_keys, &write_keys);
+                Ok(())
             }
+            Err(e) => {
+                self.mappings.pop();
+                Err(e)
+            }
+        }
+    }
 
-            Ok(())
+    // determine the winning mapping per address and direction within the blocks the new
+    // mapping touches, starting from what is resolved already
+    fn resolve(
+        &self,
+        key: usize,
+        blocks: Range<usize>,
+    ) -> Result<(Vec<usize>, Vec<usize>), MappingError> {
+        let mapping = &self.mappings[key];
+        let (from_addr, to_addr) = mapping.region.bounds();
+        let first_addr = blocks.start * self.block_size;
+        let addresses = first_addr..blocks.end * self.block_size;
+        let mut read_keys: Vec<usize> = addresses
+            .clone()
+            .map(|addr| self.lookup_key(&self.block_read_map, addr as u16))
+            .collect();
+        let mut write_keys: Vec<usize> = addresses
+            .map(|addr| self.lookup_key(&self.block_write_map, addr as u16))
+            .collect();
+        // a mapping of the same priority may be hidden behind a higher one, so all of them are
+        // checked, not just the winners
+        let rivals: Vec<&Mapping> = self.mappings[..key]
+            .iter()
+            .filter(|other| {
+                let (other_from, other_to) = other.region.bounds();
+                other.options.priority == mapping.options.priority
+                    && other_from <= to_addr
+                    && from_addr <= other_to
+            })
+            .collect();
+
+        for addr in from_addr..=to_addr {
+            if !mapping.region.contains(addr) {
+                continue;
+            }
+            let access = mapping.options.access;
+            if let Some(other) = rivals.iter().find(|other| {
+                other.region.contains(addr)
+                    && (access.reads() && other.options.access.reads()
+                        || access.writes() && other.options.access.writes())
+            }) {
+                return Err(MappingError::Overlap {
+                    addr,
+                    region: mapping.region,
+                    existing: other.region,
+                    priority: mapping.options.priority,
+                });
+            }
+            let index = addr as usize - first_addr;
+            if access.reads() {
+                self.claim(&mut read_keys[index], key);
+            }
+            if access.writes() {
+                self.claim(&mut write_keys[index], key);
+            }
+        }
+
+        Ok((read_keys, write_keys))
+    }
+
+    fn blocks_of(&self, region: &Region) -> Range<usize> {
+        let (from_addr, to_addr) = region.bounds();
+        from_addr as usize / self.block_size..to_addr as usize / self.block_size + 1
+    }
+
+    fn claim(&self, slot: &mut usize, key: usize) {
+        if *slot == UNMAPPED
+            || self.mappings[key].options.priority > self.mappings[*slot].options.priority
+        {
+            *slot = key;
+        }
+    }
+
+    // compress per address keys into per block entries; only blocks shared by several
+    // mappings keep a per address lookup in the split map
+    fn update_lookup(&mut self, blocks: Range<usize>, read_keys: &[usize], write_keys: &[usize]) {
+        for (i, block) in blocks.enumerate() {
+            let keys = i * self.block_size..(i + 1) * self.block_size;
+            self.block_read_map[block] = Self::block_entry(
+                &mut self.split_map,
+                self.block_read_map[block],
+                &read_keys[keys.clone()],
+            );
+            self.block_write_map[block] = Self::block_entry(
+                &mut self.split_map,
+                self.block_write_map[block],
+                &write_keys[keys],
+            );
+        }
+    }
+
+    // a block that was split already keeps its place in the split map
+    fn block_entry(split_map: &mut Vec<usize>, entry: usize, keys: &[usize]) -> usize {
+        if keys.iter().all(|key| *key == keys[0]) {
+            keys[0]
+        } else if entry != UNMAPPED && entry & SPLIT_BLOCK != 0 {
+            let offset = entry & !SPLIT_BLOCK;
+            split_map[offset..offset + keys.len()].copy_from_slice(keys);
+            entry
         } else {
-            Err(AddressingError::new("add_compontent", 0))
+            split_map.extend_from_slice(keys);
+            SPLIT_BLOCK | (split_map.len() - keys.len())
         }
     }
+
+    #[inline]
+    fn lookup_key(&self, block_map: &[usize], addr: u16) -> usize {
+        let key = block_map[addr as usize / self.block_size];
+        if key != UNMAPPED && key & SPLIT_BLOCK != 0 {
+            self.split_map[(key & !SPLIT_BLOCK) + addr as usize % self.block_size]
+        } else {
+            key
+        }
+    }
+
+    #[inline]
+    fn lookup(&self, block_map: &[usize], addr: u16) -> Option<&Mapping> {
+        self.mappings.get(self.lookup_key(block_map, addr))
+    }
+
+    /// Resolved mappings as runs of addresses which read from and write to the same components.
+    pub fn memory_map(&self) -> Vec<MemoryMapEntry> {
+        let mut entries: Vec<MemoryMapEntry> = vec![];
+        for addr in 0..=0xFFFFu16 {
+            let read = self
+                .lookup(&self.block_read_map, addr)
+                .map(|m| ComponentId(m.component));
+            let write = self
+                .lookup(&self.block_write_map, addr)
+                .map(|m| ComponentId(m.component));
+
+            match entries.last_mut() {
+                Some(entry) if entry.read == read && entry.write == write => entry.to_addr = addr,
+                _ => entries.push(MemoryMapEntry {
+                    from_addr: addr,
+                    to_addr: addr,
+                    read,
+                    write,
+                }),
+            }
+        }
+        entries
+            .into_iter()
+            .filter(|entry| entry.read.is_some() || entry.write.is_some())
+            .collect()
+    }
+
+    /// State of all attached components in attaching order, e.g. memory contents and banking.
+    pub fn save_state(&self) -> Vec<Vec<u8>> {
+        self.component_addr
+            .iter()
+            .map(|component| component.save_state())
+            .collect()
+    }
+
+    /// Restore a `save_state`; on any error the components keep the state they had.
+    pub fn load_state(&mut self, state: &[Vec<u8>]) -> Result<(), AddressingError> {
+        // component states have a fixed length, so the current ones tell what fits
+        let current = self.save_state();
+        if state.len() != current.len()
+            || current
+                .iter()
+                .zip(state)
+                .any(|(current, state)| current.len() != state.len())
+        {
+            return Err(AddressingError::new("load_state", 0));
+        }
+        for (i, component_state) in state.iter().enumerate() {
+            if let Err(e) = self.component_addr[i].load_state(component_state) {
+                for (component, previous) in self.component_addr.iter_mut().zip(&current).take(i) {
+                    let _ = component.load_state(previous);
+                }
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// `memory_map` rendered as a text table.
+    pub fn memory_map_table(&self) -> String {
+        let describe = |id: Option<ComponentId>| match id {
+            Some(id) => format!("#{} {}", id.0, self.component_addr[id.0].describe()),
+            None => "-".to_string(),
+        };
+
+        let mut table = format!("{:<11}  {:<32}  {}\n", "range", "read", "write");
+        for entry in self.memory_map() {
+            table.push_str(&format!(
+                "${:04X}-${:04X}  {:<32}  {}\n",
+                entry.from_addr,
+                entry.to_addr,
+                describe(entry.read),
+                describe(entry.write)
+            ));
+        }
+        table
+    }
 }
 
 impl ExternalAddressing for AddressBus<'_> {
     fn read(&mut self, addr: u16) -> Result<u8, AddressingError> {
-        let block = addr as usize / self.block_size;
-        if self.block_component_map[block] == usize::MAX {
-            Err(AddressingError::new("read", addr))
-        } else {
-            let component_key = self.block_component_map[block];
-            match self.component_addr.get_mut(component_key) {
-                Some(component) => Ok(component.int_read(addr)),
-                None => Err(AddressingError::new("read", addr)),
+        let data = match self.lookup(&self.block_read_map, addr) {
+            Some(mapping) => {
+                let (component, component_addr, wait_states) = (
+                    mapping.component,
+                    mapping.region.translate(addr),
+                    mapping.options.wait_states,
+                );
+                let component = &mut self.component_addr[component];
+                let data = component.int_read_checked(component_addr, self.context)?;
+                self.wait_cycles += wait_states as u64
+                    + component.wait_states(component_addr, Direction::Read) as u64;
+                data
             }
-        }
+            None => match self.unmapped_access(addr, self.last_data, Direction::Read) {
+                Some(data) => data,
+                None => return Err(AddressingError::new("read", addr)),
+            },
+        };
+        self.last_data = data;
+        self.observers
+            .notify(addr, data, Direction::Read, self.context);
+        Ok(data)
     }
 
     fn write(&mut self, addr: u16, data: u8) -> Result<(), AddressingError> {
-        let block = addr as usize / self.block_size;
-        if self.block_component_map[block] == usize::MAX {
-            Err(AddressingError::new("write", addr))
-        } else {
-            let component_key = self.block_component_map[block];
-            match self.component_addr.get_mut(component_key) {
-                Some(component) => {
-                    component.int_write(addr, data);
-                    Ok(())
-                }
+        self.last_data = data;
+        let result = match self.lookup(&self.block_write_map, addr) {
+            Some(mapping) => {
+                let (component, component_addr, wait_states) = (
+                    mapping.component,
+                    mapping.region.translate(addr),
+                    mapping.options.wait_states,
+                );
+                let component = &mut self.component_addr[component];
+                let result = component.int_write_checked(component_addr, data, self.context);
+                self.wait_cycles += wait_states as u64
+                    + component.wait_states(component_addr, Direction::Write) as u64;
+                result
+            }
+            None => match self.unmapped_access(addr, data, Direction::Write) {
+                Some(_) => Ok(()),
                 None => Err(AddressingError::new("write", addr)),
+            },
+        };
+        if result.is_ok() {
+            self.observers
+                .notify(addr, data, Direction::Write, self.context);
+        }
+        result
+    }
+
+    fn peek(&self, addr: u16) -> Result<u8, AddressingError> {
+        match self.lookup(&self.block_read_map, addr) {
+            Some(mapping) => {
+                Ok(self.component_addr[mapping.component].int_peek(mapping.region.translate(addr)))
+            }
+            None => match self.unmapped_policy {
+                UnmappedPolicy::Error => Err(AddressingError::new("peek", addr)),
+                UnmappedPolicy::Fixed(value) => Ok(value),
+                UnmappedPolicy::OpenBus | UnmappedPolicy::IgnoreWithLog => Ok(self.last_data),
+            },
+        }
+    }
+
+    // pokes go to the component seen by reads, so a patched ROM shows up in a following peek
+    fn poke(&mut self, addr: u16, data: u8) -> Result<(), AddressingError> {
+        let mapping = self
+            .lookup(&self.block_read_map, addr)
+            .or_else(|| self.lookup(&self.block_write_map, addr));
+        match mapping {
+            Some(mapping) => {
+                let (component, component_addr) =
+                    (mapping.component, mapping.region.translate(addr));
+                self.component_addr[component].int_poke(component_addr, data);
+                Ok(())
             }
+            None => Err(AddressingError::new("poke", addr)),
+        }
+    }
+
+    fn set_context(&mut self, context: AccessContext) {
+        self.context = context;
+    }
+
+    fn take_wait_cycles(&mut self) -> u64 {
+        std::mem::take(&mut self.wait_cycles)
+    }
+
+    fn tick(&mut self) {
+        for component in self.component_addr.iter_mut() {
+            component.tick();
         }
     }
 }
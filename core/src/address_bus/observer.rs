@@ -0,0 +1,120 @@
+use crate::address_bus::{AccessContext, AddressingError, Direction, ExternalAddressing};
+
+/// One access as seen on the bus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusAccess {
+    pub addr: u16,
+    pub data: u8,
+    pub direction: Direction,
+    pub pc: u16,
+    /// CPU cycle the instruction doing the access started in, not the cycle of the access
+    /// itself: the CPU makes all accesses of an instruction when it starts, so they share it.
+    pub cycle: u64,
+}
+
+/// Passive listener for bus traffic, e.g. for bus logs, watchpoints or heatmaps. It sees
+/// only accesses that completed: reads and writes that fail with an `AddressingError` are
+/// not observed.
+pub trait BusObserver {
+    fn observe(&mut self, access: &BusAccess);
+}
+
+impl<F: FnMut(&BusAccess)> BusObserver for F {
+    fn observe(&mut self, access: &BusAccess) {
+        self(access)
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Observers<'a> {
+    observers: Vec<&'a mut dyn BusObserver>,
+}
+
+impl<'a> Observers<'a> {
+    pub fn add(&mut self, observer: &'a mut dyn BusObserver) {
+        self.observers.push(observer);
+    }
+
+    pub fn clear(&mut self) {
+        self.observers.clear();
+    }
+
+    #[inline]
+    pub fn notify(&mut self, addr: u16, data: u8, direction: Direction, context: AccessContext) {
+        if self.observers.is_empty() {
+            return;
+        }
+        let access = BusAccess {
+            addr,
+            data,
+            direction,
+            pc: context.pc,
+            cycle: context.cycle,
+        };
+        for observer in self.observers.iter_mut() {
+            observer.observe(&access);
+        }
+    }
+}
+
+/// Adds observers to any `ExternalAddressing`, e.g. a bus that has none of its own.
+pub struct ObservedBus<'a> {
+    bus: &'a mut dyn ExternalAddressing,
+    observers: Observers<'a>,
+    context: AccessContext,
+}
+
+impl<'a> ObservedBus<'a> {
+    pub fn new(bus: &'a mut dyn ExternalAddressing) -> ObservedBus<'a> {
+        ObservedBus {
+            bus,
+            observers: Observers::default(),
+            context: AccessContext::default(),
+        }
+    }
+
+    pub fn add_observer(&mut self, observer: &'a mut dyn BusObserver) {
+        self.observers.add(observer);
+    }
+
+    pub fn clear_observers(&mut self) {
+        self.observers.clear();
+    }
+}
+
+impl ExternalAddressing for ObservedBus<'_> {
+    fn read(&mut self, addr: u16) -> Result<u8, AddressingError> {
+        let data = self.bus.read(addr)?;
+        self.observers
+            .notify(addr, data, Direction::Read, self.context);
+        Ok(data)
+    }
+
+    fn write(&mut self, addr: u16, data: u8) -> Result<(), AddressingError> {
+        self.bus.write(addr, data)?;
+        self.observers
+            .notify(addr, data, Direction::Write, self.context);
+        Ok(())
+    }
+
+    fn peek(&self, addr: u16) -> Result<u8, AddressingError> {
+        self.bus.peek(addr)
+    }
+
+    fn poke(&mut self, addr: u16, data: u8) -> Result<(), AddressingError> {
+        self.bus.poke(addr, data)
+    }
+
+    fn set_context(&mut self, context: AccessContext) {
+        self.context = context;
+        self.bus.set_context(context);
+    }
+
+    fn take_wait_cycles(&mut self) -> u64 {
+        self.bus.take_wait_cycles()
+    }
+
+    fn tick(&mut self) {
+        self.bus.tick();
+    }
+}
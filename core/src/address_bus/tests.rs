@@ -1,6 +1,8 @@
 #[cfg(test)]
 use super::*;
+use crate::mc6821::*;
 use crate::memory::*;
+use crate::mos6502::*;
 
 #[test]
 fn writes_and_reads_memory_block_zero() {
@@ -19,8 +21,7 @@ fn writes_and_reads_memory_block_zero() {
     let actual = address_bus.read(addr);
 
     // assert
-    assert_eq!(actual.is_ok(), true);
-    assert_eq!(actual.is_err(), false);
+    assert!(actual.is_ok());
     assert_eq!(expected, actual.unwrap());
 }
 
@@ -41,8 +42,7 @@ fn writes_and_reads_memory_block_one() {
     let actual = address_bus.read(addr);
 
     // assert
-    assert_eq!(actual.is_ok(), true);
-    assert_eq!(actual.is_err(), false);
+    assert!(actual.is_ok());
     assert_eq!(expected, actual.unwrap());
 }
 
@@ -67,8 +67,7 @@ fn writes_and_reads_memory_nonzero_offset() {
     let actual = address_bus.read(addr);
 
     // assert
-    assert_eq!(actual.is_ok(), true);
-    assert_eq!(actual.is_err(), false);
+    assert!(actual.is_ok());
     assert_eq!(expected, actual.unwrap());
 }
 
@@ -87,8 +86,7 @@ fn writes_to_invalid_address() {
     let actual = address_bus.write(addr, 42);
 
     // assert
-    assert_eq!(actual.is_ok(), false);
-    assert_eq!(actual.is_err(), true);
+    assert!(actual.is_err());
 }
 
 #[test]
@@ -106,12 +104,11 @@ fn reads_from_invalid_address() {
     let actual = address_bus.read(addr);
 
     // assert
-    assert_eq!(actual.is_ok(), false);
-    assert_eq!(actual.is_err(), true);
+    assert!(actual.is_err());
 }
 
 #[test]
-fn invalid_mem_block_size() {
+fn mapping_larger_than_component_is_rejected() {
     // arrange
     let mut mem = Memory::from_vec(0, vec![0x01, 0x02, 0x03, 0x00]);
     let mut address_bus = AddressBus::new(0x100);
@@ -120,14 +117,16 @@ fn invalid_mem_block_size() {
     let actual = address_bus.add_component(0, 0x100, &mut (mem));
 
     // assert
-    assert_eq!(actual.is_ok(), false);
-    assert_eq!(actual.is_err(), true);
+    assert!(matches!(
+        actual,
+        Err(MappingError::ComponentTooSmall { len: 4, .. })
+    ));
 }
 
 #[test]
 fn load_rom() {
     // arrange
-    let mut rom_monitor = Memory::load_rom(0xFF00, "../roms/Apple1_HexMonitor.bin".to_string());
+    let (mut rom_monitor, _) = Memory::load_rom(0xFF00, "../roms/Apple1_HexMonitor.bin").unwrap();
     let mut address_bus = AddressBus::new(0x100);
     if address_bus
         .add_component(0xFF00, rom_monitor.len(), &mut (rom_monitor))
@@ -142,8 +141,7 @@ fn load_rom() {
     let actual = address_bus.read(addr);
 
     // assert
-    assert_eq!(actual.is_ok(), true);
-    assert_eq!(actual.is_err(), false);
+    assert!(actual.is_ok());
 }
 
 #[test]
@@ -163,3 +161,803 @@ fn add_component_unaligned_start() {
     // assert
     assert_eq!(val, 0xAA);
 }
+
+#[test]
+fn mirrors_component_at_second_range() {
+    // arrange
+    let mut mem = Memory::new(0, 0x100);
+    let mut bus = AddressBus::new(0x100);
+    let id = bus.add_component(0, mem.len(), &mut mem).unwrap();
+    assert!(bus.map_mirror(id, 0x8000, 0x100, 0).is_ok());
+
+    // act
+    bus.write(0x8010, 0x42).expect("write to mirror failed");
+
+    // assert
+    assert_eq!(bus.read(0x0010).unwrap(), 0x42);
+    assert_eq!(bus.read(0x8010).unwrap(), 0x42);
+}
+
+#[test]
+fn maps_one_component_at_several_ranges() {
+    // arrange
+    let mut mem = Memory::new(0x1000, 0x100);
+    let mut bus = AddressBus::new(0x100);
+    let id = bus.attach_component(&mut mem);
+    assert!(bus.map_range(id, 0x1000, 0x100).is_ok());
+    assert!(bus.map_mirror(id, 0x3000, 0x100, 0x1000).is_ok());
+    assert!(bus.map_mirror(id, 0x5000, 0x100, 0x1000).is_ok());
+
+    // act
+    bus.write(0x30FF, 0x5A).expect("write to mirror failed");
+
+    // assert
+    assert_eq!(bus.read(0x10FF).unwrap(), 0x5A);
+    assert_eq!(bus.read(0x50FF).unwrap(), 0x5A);
+    assert!(bus.read(0x20FF).is_err());
+}
+
+#[test]
+fn decodes_partially_selected_component() {
+    // arrange: Apple 1 style PIA selection - A12..A15 = $D and A4 = 1
+    let mut pia = MC6821::new();
+    let mut bus = AddressBus::new(0x10);
+    let id = bus.attach_component(&mut pia);
+    assert!(bus.map_decoded(id, Decoding::new(0xF010, 0xD010)).is_ok());
+
+    // act
+    bus.write(0xD013, 0x04).expect("write to CRB failed");
+
+    // assert
+    assert_eq!(bus.read(0xD0F3).unwrap(), 0x04);
+    assert_eq!(bus.read(0xDF13).unwrap(), 0x04);
+    assert!(bus.read(0xD003).is_err());
+    assert!(bus.read(0xC013).is_err());
+}
+
+#[test]
+fn decoded_lines_fold_mirrors_onto_component() {
+    // arrange: 4kB RAM without A12..A14 decoded, repeating across $0000-$7FFF
+    let mut mem = Memory::new(0, 0x1000);
+    let mut bus = AddressBus::new(0x100);
+    let id = bus.attach_component(&mut mem);
+    let decoding = Decoding::new(0x8000, 0x0000).with_lines(0x0FFF, 0);
+    assert!(bus.map_decoded(id, decoding).is_ok());
+
+    // act
+    bus.write(0x7123, 0x99).expect("write to mirror failed");
+
+    // assert
+    assert_eq!(bus.read(0x0123).unwrap(), 0x99);
+    assert_eq!(bus.read(0x3123).unwrap(), 0x99);
+    assert!(bus.read(0x8123).is_err());
+}
+
+#[test]
+fn decoding_finer_than_block_size() {
+    // arrange
+    let mut pia = MC6821::new();
+    let mut bus = AddressBus::new(0x100);
+    let id = bus.attach_component(&mut pia);
+
+    // act
+    let actual = bus.map_decoded(id, Decoding::new(0xF010, 0xD010));
+
+    // assert
+    assert!(actual.is_ok());
+    assert!(bus.read(0xD011).is_ok());
+    assert!(bus.read(0xD001).is_err());
+}
+
+#[test]
+fn maps_components_at_byte_granularity() {
+    // arrange
+    let mut mem = Memory::from_vec(0x0102, vec![0x01, 0x02, 0x03, 0x04]);
+    let mut bus = AddressBus::new(0x100);
+
+    // act
+    let actual = bus.add_component(0x0102, mem.len(), &mut mem);
+
+    // assert
+    assert!(actual.is_ok());
+    assert_eq!(bus.read(0x0102).unwrap(), 0x01);
+    assert_eq!(bus.read(0x0105).unwrap(), 0x04);
+    assert!(bus.read(0x0101).is_err());
+    assert!(bus.read(0x0106).is_err());
+}
+
+#[test]
+fn read_only_overlay_with_higher_priority() {
+    // arrange
+    let mut ram = Memory::new(0, 0x10000);
+    let mut rom = Memory::from_vec(0xFF00, vec![0xEA; 0x100]);
+    let mut bus = AddressBus::new(0x100);
+    assert!(bus.add_component(0, ram.len(), &mut ram).is_ok());
+    let overlay = MapOptions::new(Access::ReadOnly, 1);
+    assert!(bus
+        .add_component_with(0xFF00, rom.len(), &mut rom, overlay)
+        .is_ok());
+
+    // act
+    bus.write(0xFF10, 0x42)
+        .expect("write to RAM below ROM failed");
+
+    // assert
+    assert_eq!(bus.read(0xFF10).unwrap(), 0xEA);
+    assert_eq!(bus.read(0xFEFF).unwrap(), 0x00);
+}
+
+#[test]
+fn overlap_with_same_priority_is_rejected() {
+    // arrange
+    let mut ram = Memory::new(0, 0x1000);
+    let mut rom = Memory::from_vec(0x0F00, vec![0xEA; 0x200]);
+    let mut bus = AddressBus::new(0x100);
+    assert!(bus.add_component(0, ram.len(), &mut ram).is_ok());
+
+    // act
+    let actual = bus.add_component(0x0F00, rom.len(), &mut rom);
+
+    // assert
+    match actual {
+        Err(MappingError::Overlap { addr, existing, .. }) => {
+            assert_eq!(addr, 0x0F00);
+            assert_eq!(
+                existing,
+                Region::Range {
+                    from_addr: 0,
+                    size: 0x1000
+                }
+            );
+        }
+        _ => panic!("expected overlap error"),
+    }
+    assert_eq!(bus.read(0x0F00).unwrap(), 0x00);
+    assert!(bus.read(0x1000).is_err());
+}
+
+#[test]
+fn overlap_hidden_by_higher_priority_is_rejected() {
+    // arrange: A (priority 0), B (priority 1), C (priority 0) over the same bytes
+    let mut a = Memory::new(0x0200, 0x100);
+    let mut b = Memory::new(0x0200, 0x100);
+    let mut c = Memory::new(0x0200, 0x100);
+    let mut bus = AddressBus::new(0x100);
+    assert!(bus.add_component(0x0200, a.len(), &mut a).is_ok());
+    assert!(bus
+        .add_component_with(
+            0x0200,
+            b.len(),
+            &mut b,
+            MapOptions::new(Access::ReadWrite, 1)
+        )
+        .is_ok());
+
+    // act
+    let actual = bus.add_component(0x0200, c.len(), &mut c);
+
+    // assert
+    assert!(matches!(
+        actual,
+        Err(MappingError::Overlap {
+            addr: 0x0200,
+            priority: 0,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn later_mappings_update_shared_block() {
+    // arrange: two overlays and a rejected one within the same 256 byte block of RAM
+    let mut ram = Memory::new(0, 0x100);
+    let mut low = Memory::from_vec(0x10, vec![0x11; 0x10]);
+    let mut high = Memory::from_vec(0x80, vec![0x22; 0x08]);
+    let mut clash = Memory::from_vec(0x84, vec![0x33; 0x08]);
+    let mut bus = AddressBus::new(0x100);
+    let overlay = MapOptions::new(Access::ReadWrite, 1);
+    assert!(bus.add_component(0, ram.len(), &mut ram).is_ok());
+    assert!(bus
+        .add_component_with(0x10, 0x10, &mut low, overlay)
+        .is_ok());
+
+    // act
+    let added = bus.add_component_with(0x80, 0x08, &mut high, overlay);
+    let rejected = bus.add_component_with(0x84, 0x08, &mut clash, overlay);
+
+    // assert
+    assert!(added.is_ok());
+    assert!(matches!(
+        rejected,
+        Err(MappingError::Overlap { addr: 0x84, .. })
+    ));
+    assert_eq!(bus.read(0x0F).unwrap(), 0x00);
+    assert_eq!(bus.read(0x1F).unwrap(), 0x11);
+    assert_eq!(bus.read(0x87).unwrap(), 0x22);
+    assert_eq!(bus.read(0x8B).unwrap(), 0x00);
+}
+
+#[test]
+fn memory_map_lists_resolved_ranges() {
+    // arrange
+    let mut ram = Memory::new(0, 0x1000);
+    let mut pia = MC6821::new();
+    let mut rom = Memory::from_vec(0xFF00, vec![0xEA; 0x100]);
+    let mut bus = AddressBus::new(0x100);
+    let ram_id = bus.add_component(0, ram.len(), &mut ram).unwrap();
+    let pia_id = bus.attach_component(&mut pia);
+    bus.map_decoded(pia_id, Decoding::new(0xFFFC, 0xD010))
+        .unwrap();
+    let rom_id = bus
+        .add_component_with(
+            0xFF00,
+            rom.len(),
+            &mut rom,
+            MapOptions::new(Access::ReadOnly, 0),
+        )
+        .unwrap();
+
+    // act
+    let map = bus.memory_map();
+    let table = bus.memory_map_table();
+
+    // assert
+    assert_eq!(
+        map,
+        vec![
+            MemoryMapEntry {
+                from_addr: 0x0000,
+                to_addr: 0x0FFF,
+                read: Some(ram_id),
+                write: Some(ram_id)
+            },
+            MemoryMapEntry {
+                from_addr: 0xD010,
+                to_addr: 0xD013,
+                read: Some(pia_id),
+                write: Some(pia_id)
+            },
+            MemoryMapEntry {
+                from_addr: 0xFF00,
+                to_addr: 0xFFFF,
+                read: Some(rom_id),
+                write: None
+            },
+        ]
+    );
+    assert!(table.contains("$D010-$D013  #1 MC6821 PIA"));
+    assert!(table.contains("$FF00-$FFFF  #2 memory $FF00-$FFFF"));
+}
+
+fn banked_window() -> BankSwitched {
+    BankSwitched::new(vec![
+        Memory::from_vec(0xC000, vec![0xA0; 0x100]),
+        Memory::from_vec(0xC000, vec![0xA1; 0x100]),
+        Memory::from_vec(0xC000, vec![0xA2; 0x100]),
+    ])
+}
+
+#[test]
+fn bank_register_switches_window() {
+    // arrange
+    let mut window = banked_window();
+    let mut register = window.register();
+    let mut bus = AddressBus::new(0x100);
+    assert!(bus.add_component(0xC000, window.len(), &mut window).is_ok());
+    assert!(bus.add_component(0xC800, 1, &mut register).is_ok());
+
+    // act
+    bus.write(0xC800, 2).expect("write to bank register failed");
+
+    // assert
+    assert_eq!(bus.read(0xC800).unwrap(), 2);
+    assert_eq!(bus.read(0xC010).unwrap(), 0xA2);
+    assert!(bus.memory_map_table().contains("bank 2 of 3"));
+}
+
+#[test]
+fn writes_go_to_selected_bank_only() {
+    // arrange
+    let mut window = banked_window();
+    window.select(1);
+
+    // act
+    window.int_write(0xC000, 0x11);
+
+    // assert
+    assert_eq!(window.bank(1).unwrap().save_state()[0], 0x11);
+    assert_eq!(window.bank(0).unwrap().save_state()[0], 0xA0);
+}
+
+#[test]
+fn save_state_restores_banking() {
+    // arrange
+    let mut window = banked_window();
+    let mut register = window.register();
+    let mut bus = AddressBus::new(0x100);
+    assert!(bus.add_component(0xC000, window.len(), &mut window).is_ok());
+    assert!(bus.add_component(0xC800, 1, &mut register).is_ok());
+    bus.write(0xC800, 1).unwrap();
+    bus.write(0xC020, 0x42).unwrap();
+    let state = bus.save_state();
+
+    // act
+    bus.write(0xC800, 0).unwrap();
+    bus.write(0xC020, 0x00).unwrap();
+    let actual = bus.load_state(&state);
+
+    // assert
+    assert!(actual.is_ok());
+    assert_eq!(bus.read(0xC800).unwrap(), 1);
+    assert_eq!(bus.read(0xC020).unwrap(), 0x42);
+}
+
+#[test]
+fn failed_load_state_leaves_bus_unchanged() {
+    // arrange
+    let mut ram = Memory::new(0, 0x100);
+    let mut window = banked_window();
+    let mut bus = AddressBus::new(0x100);
+    assert!(bus.add_component(0, ram.len(), &mut ram).is_ok());
+    assert!(bus.add_component(0xC000, window.len(), &mut window).is_ok());
+    let mut bad_bank = bus.save_state();
+    bad_bank[0][0] = 0x42;
+    bad_bank[1][0] = 7; // only 3 banks
+    let mut bad_length = bad_bank.clone();
+    bad_length[1].pop();
+
+    // act
+    let length_error = bus.load_state(&bad_length);
+    let bank_error = bus.load_state(&bad_bank);
+
+    // assert
+    assert!(length_error.is_err());
+    assert!(bank_error.is_err());
+    assert_eq!(bus.read(0x0000).unwrap(), 0x00);
+}
+
+#[test]
+#[should_panic(expected = "more than 256 banks")]
+fn bank_number_fits_register() {
+    // arrange
+    let banks = (0..257).map(|_| Memory::new(0, 1)).collect();
+
+    // act
+    BankSwitched::new(banks);
+}
+
+fn bus_with_low_ram<'a>(mem: &'a mut Memory, policy: UnmappedPolicy) -> AddressBus<'a> {
+    let mut bus = AddressBus::new(0x100);
+    bus.add_component(0, mem.len(), mem).unwrap();
+    bus.set_unmapped_policy(policy);
+    bus
+}
+
+#[test]
+fn unmapped_error_policy_counts_accesses() {
+    // arrange
+    let mut mem = Memory::new(0, 0x100);
+    let mut bus = bus_with_low_ram(&mut mem, UnmappedPolicy::Error);
+
+    // act
+    let actual_read = bus.read(0x2000);
+    let actual_write = bus.write(0x2000, 0x42);
+
+    // assert
+    assert!(actual_read.is_err());
+    assert!(actual_write.is_err());
+    assert_eq!(bus.unmapped_count(), 2);
+    assert!(bus.unmapped_log().is_empty());
+}
+
+#[test]
+fn unmapped_open_bus_returns_last_data() {
+    // arrange
+    let mut mem = Memory::from_vec(0, vec![0x5A; 0x100]);
+    let mut bus = bus_with_low_ram(&mut mem, UnmappedPolicy::OpenBus);
+
+    // act
+    bus.read(0x0010).unwrap();
+    let after_read = bus.read(0x2000);
+    bus.write(0x2000, 0x77).unwrap();
+    let after_write = bus.read(0x2001);
+
+    // assert
+    assert_eq!(after_read.unwrap(), 0x5A);
+    assert_eq!(after_write.unwrap(), 0x77);
+    assert_eq!(bus.unmapped_count(), 3);
+}
+
+#[test]
+fn unmapped_fixed_value() {
+    // arrange
+    let mut mem = Memory::new(0, 0x100);
+    let mut bus = bus_with_low_ram(&mut mem, UnmappedPolicy::Fixed(0xFF));
+
+    // act
+    let actual = bus.read(0x8000);
+
+    // assert
+    assert_eq!(actual.unwrap(), 0xFF);
+    assert!(bus.write(0x8000, 0x01).is_ok());
+}
+
+#[test]
+fn unmapped_ignore_with_log_records_accesses() {
+    // arrange
+    let mut mem = Memory::new(0, 0x100);
+    let mut bus = bus_with_low_ram(&mut mem, UnmappedPolicy::IgnoreWithLog);
+
+    // act
+    bus.write(0x4000, 0x12).unwrap();
+    bus.read(0x4001).unwrap();
+
+    // assert
+    assert_eq!(
+        bus.unmapped_log(),
+        &[
+            UnmappedAccess {
+                addr: 0x4000,
+                data: 0x12,
+                direction: Direction::Write,
+                pc: 0
+            },
+            UnmappedAccess {
+                addr: 0x4001,
+                data: 0x12,
+                direction: Direction::Read,
+                pc: 0
+            }
+        ]
+    );
+    bus.clear_unmapped_log();
+    assert_eq!(bus.unmapped_count(), 0);
+    assert!(bus.unmapped_log().is_empty());
+}
+
+#[test]
+fn rom_write_error_propagates_through_bus() {
+    // arrange
+    let mut rom = Memory::from_vec(0xFF00, vec![0xEA; 0x100]);
+    rom.set_write_policy(WritePolicy::Error);
+    let mut bus = AddressBus::new(0x100);
+    bus.add_component(0xFF00, rom.len(), &mut rom).unwrap();
+
+    // act
+    let actual = bus.write(0xFF00, 0x00);
+
+    // assert
+    assert!(actual.is_err());
+    assert_eq!(bus.read(0xFF00).unwrap(), 0xEA);
+}
+
+#[test]
+fn rom_write_log_has_cpu_pc() {
+    // arrange: STA $FF10 at $0000, followed by an endless loop
+    let mut ram = Memory::from_vec(0, vec![0x8D, 0x10, 0xFF, 0x4C, 0x03, 0x00]);
+    let mut rom = Memory::from_vec(0xFF00, vec![0xEA; 0x100]);
+    rom.set_write_policy(WritePolicy::Log);
+    {
+        let mut bus = AddressBus::new(0x100);
+        bus.add_component(0, ram.len(), &mut ram).unwrap();
+        bus.add_component(0xFF00, rom.len(), &mut rom).unwrap();
+        let mut cpu = Cpu::new(CpuRegisters::default(), &mut bus);
+
+        // act
+        cpu.cycle(false);
+    }
+
+    // assert
+    assert_eq!(
+        rom.rom_writes(),
+        &[RomWrite {
+            addr: 0xFF10,
+            data: 0x00,
+            pc: 0x0000
+        }]
+    );
+}
+
+#[test]
+fn observer_sees_cpu_accesses_with_cycle() {
+    // arrange: LDA $0010, STA $D012, then an endless loop
+    let mut program = vec![0xAD, 0x10, 0x00, 0x8D, 0x12, 0xD0, 0x4C, 0x06, 0x00];
+    program.resize(0x100, 0x00);
+    let mut ram = Memory::from_vec(0, program);
+    ram.int_write(0x0010, 0x41);
+    let mut io = Memory::new(0xD010, 4);
+    let mut log = vec![];
+    {
+        let mut io_trace = |access: &BusAccess| {
+            if access.addr >= 0xD010 {
+                log.push(*access)
+            }
+        };
+        let mut bus = AddressBus::new(0x10);
+        bus.add_component(0, 0x100, &mut ram).unwrap();
+        bus.add_component(0xD010, io.len(), &mut io).unwrap();
+        bus.add_observer(&mut io_trace);
+        let mut cpu = Cpu::new(CpuRegisters::default(), &mut bus);
+
+        // act
+        for _ in 0..8 {
+            cpu.cycle(false);
+        }
+    }
+
+    // assert
+    assert_eq!(
+        log,
+        vec![BusAccess {
+            addr: 0xD012,
+            data: 0x41,
+            direction: Direction::Write,
+            pc: 0x0003,
+            cycle: 4
+        }]
+    );
+}
+
+#[test]
+fn observed_bus_wraps_any_external_addressing() {
+    // arrange
+    let mut ram = Memory::new(0, 0x100);
+    let mut log = vec![];
+    {
+        let mut trace = |access: &BusAccess| log.push((access.addr, access.direction));
+        let mut bus = ObservedBus::new(&mut ram);
+        bus.add_observer(&mut trace);
+
+        // act
+        bus.write(0x0020, 0x12).unwrap();
+        bus.read(0x0020).unwrap();
+        bus.clear_observers();
+        bus.read(0x0021).unwrap();
+    }
+
+    // assert
+    assert_eq!(
+        log,
+        vec![(0x0020, Direction::Write), (0x0020, Direction::Read)]
+    );
+    assert_eq!(ram.int_read(0x0020), 0x12);
+}
+
+#[test]
+fn observers_see_only_completed_accesses() {
+    // arrange
+    let mut ram = Memory::new(0, 0x100);
+    let mut rom = Memory::from_vec(0xFF00, vec![0xEA; 0x100]);
+    rom.set_write_policy(WritePolicy::Error);
+    let mut log = vec![];
+    {
+        let mut trace = |access: &BusAccess| log.push((access.addr, access.direction));
+        let mut bus = AddressBus::new(0x100);
+        bus.add_component(0, ram.len(), &mut ram).unwrap();
+        bus.add_component(0xFF00, rom.len(), &mut rom).unwrap();
+        bus.add_observer(&mut trace);
+
+        // act
+        assert!(bus.read(0x1000).is_err());
+        assert!(bus.write(0x1000, 0x01).is_err());
+        assert!(bus.write(0xFF00, 0x01).is_err());
+        bus.write(0x0010, 0x01).unwrap();
+        bus.read(0xFF00).unwrap();
+    }
+
+    // assert
+    assert_eq!(
+        log,
+        vec![(0x0010, Direction::Write), (0xFF00, Direction::Read)]
+    );
+}
+
+#[test]
+fn peek_and_poke_use_component_seen_by_reads() {
+    // arrange
+    let mut ram = Memory::new(0, 0x10000);
+    let mut rom = Memory::from_vec(0xFF00, vec![0xEA; 0x100]);
+    rom.set_write_policy(WritePolicy::Error);
+    let mut bus = AddressBus::new(0x100);
+    bus.add_component(0, ram.len(), &mut ram).unwrap();
+    bus.add_component_with(
+        0xFF00,
+        rom.len(),
+        &mut rom,
+        MapOptions::new(Access::ReadOnly, 1),
+    )
+    .unwrap();
+
+    // act
+    bus.poke(0xFF10, 0x60).unwrap();
+    bus.poke(0x0010, 0x42).unwrap();
+
+    // assert
+    assert_eq!(bus.peek(0xFF10).unwrap(), 0x60);
+    assert_eq!(bus.peek(0x0010).unwrap(), 0x42);
+    assert!(bus.write(0xFF10, 0x00).is_ok()); // writes still fall through to RAM
+    assert_eq!(bus.peek(0xFF10).unwrap(), 0x60);
+}
+
+// a latch implementing only the required methods
+struct Latch(u8);
+
+impl InternalAddressing for Latch {
+    fn int_read(&mut self, _addr: u16) -> u8 {
+        self.0
+    }
+
+    fn int_write(&mut self, _addr: u16, data: u8) {
+        self.0 = data;
+    }
+
+    fn len(&self) -> usize {
+        1
+    }
+}
+
+#[test]
+fn components_without_peek_read_open_bus() {
+    // arrange
+    let mut latch = Latch(0x42);
+    let mut bus = AddressBus::new(0x10);
+    bus.add_component(0xC000, 1, &mut latch).unwrap();
+
+    // act
+    let poked = bus.poke(0xC000, 0x00);
+
+    // assert
+    assert!(poked.is_ok());
+    assert_eq!(bus.peek(0xC000).unwrap(), 0xFF);
+    assert_eq!(bus.read(0xC000).unwrap(), 0x42);
+}
+
+#[test]
+fn unmapped_peek_is_not_counted() {
+    // arrange
+    let mut ram = Memory::new(0, 0x100);
+    let mut bus = AddressBus::new(0x100);
+    bus.add_component(0, ram.len(), &mut ram).unwrap();
+    bus.set_unmapped_policy(UnmappedPolicy::IgnoreWithLog);
+
+    // act
+    let actual = bus.peek(0x4000);
+
+    // assert
+    assert!(actual.is_ok());
+    assert_eq!(bus.unmapped_count(), 0);
+    assert!(bus.unmapped_log().is_empty());
+    assert!(bus.poke(0x4000, 0x00).is_err());
+}
+
+#[test]
+fn mapping_wait_states_extend_instruction() {
+    // arrange: LDA $8000 from slow memory with 2 wait states
+    let mut program = vec![0xAD, 0x00, 0x80];
+    program.resize(0x100, 0xEA);
+    let mut ram = Memory::from_vec(0, program);
+    let mut slow = Memory::new(0x8000, 0x100);
+    let mut bus = AddressBus::new(0x100);
+    bus.add_component(0, ram.len(), &mut ram).unwrap();
+    bus.add_component_with(
+        0x8000,
+        slow.len(),
+        &mut slow,
+        MapOptions::default().with_wait_states(2),
+    )
+    .unwrap();
+    let mut cpu = Cpu::new(CpuRegisters::default(), &mut bus);
+
+    // act
+    cpu.cycle(false);
+    while !cpu.completed_operation_cycles() {
+        cpu.cycle(false);
+    }
+
+    // assert
+    assert_eq!(cpu.cycles(), 4 + 2);
+}
+
+// slow to write, e.g. an EEPROM
+struct SlowWrites {
+    data: u8,
+}
+
+impl InternalAddressing for SlowWrites {
+    fn int_read(&mut self, _addr: u16) -> u8 {
+        self.data
+    }
+
+    fn int_write(&mut self, _addr: u16, data: u8) {
+        self.data = data;
+    }
+
+    fn int_peek(&self, _addr: u16) -> u8 {
+        self.data
+    }
+
+    fn int_poke(&mut self, _addr: u16, data: u8) {
+        self.data = data;
+    }
+
+    fn len(&self) -> usize {
+        1
+    }
+
+    fn wait_states(&self, _addr: u16, direction: Direction) -> u8 {
+        match direction {
+            Direction::Read => 0,
+            Direction::Write => 3,
+        }
+    }
+}
+
+#[test]
+fn component_wait_states_are_accumulated() {
+    // arrange
+    let mut slow = SlowWrites { data: 0 };
+    let mut bus = AddressBus::new(0x100);
+    bus.add_component_with(
+        0x4000,
+        1,
+        &mut slow,
+        MapOptions::default().with_wait_states(1),
+    )
+    .unwrap();
+
+    // act
+    bus.write(0x4000, 0x12).unwrap();
+    bus.read(0x4000).unwrap();
+    bus.peek(0x4000).unwrap();
+
+    // assert
+    assert_eq!(bus.take_wait_cycles(), (1 + 3) + 1);
+    assert_eq!(bus.take_wait_cycles(), 0);
+}
+
+// counts clock cycles, like a timer
+struct Ticks {
+    count: u64,
+}
+
+impl InternalAddressing for Ticks {
+    fn int_read(&mut self, _addr: u16) -> u8 {
+        self.count as u8
+    }
+
+    fn int_write(&mut self, _addr: u16, _data: u8) {}
+
+    fn int_peek(&self, _addr: u16) -> u8 {
+        self.count as u8
+    }
+
+    fn int_poke(&mut self, _addr: u16, _data: u8) {}
+
+    fn len(&self) -> usize {
+        1
+    }
+
+    fn tick(&mut self) {
+        self.count += 1;
+    }
+}
+
+#[test]
+fn cpu_ticks_components_every_cycle() {
+    // arrange
+    let mut ticks = Ticks { count: 0 };
+    let mut mem = Memory::from_vec(0, vec![0xEA; 0x100]); // NOPs
+    let mut bus = AddressBus::new(0x100);
+    bus.add_component(0x0000, 0x100, &mut mem).unwrap();
+    bus.add_component(0x4000, 1, &mut ticks).unwrap();
+
+    // act
+    let mut cpu = Cpu::new(CpuRegisters::default(), &mut bus);
+    for _ in 0..10 {
+        cpu.cycle(false);
+    }
+    drop(cpu);
+
+    // assert
+    assert_eq!(ticks.count, 10);
+}
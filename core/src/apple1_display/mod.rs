@@ -0,0 +1,81 @@
+#[cfg(test)]
+mod tests;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crossbeam_channel::Sender;
+
+use crate::mc6821::{InputSignal, PiaPeripheral};
+
+/// The terminal section takes a character when the cursor position comes round, once per frame.
+pub const FRAME_RATE_HZ: u64 = 60;
+
+/// Terminal section of the Apple-1 on PIA port B: characters written to the port go to
+/// `output`, after each one PB7 reads busy for a frame; in fast mode it never does.
+pub struct Apple1Display {
+    output: Option<Sender<u8>>,
+    frame_cycles: u64,
+    busy_cycles: u64, // cycles until the next character is taken
+    busy: bool,       // PB7 as last handed to the PIA
+    fast: Rc<Cell<bool>>,
+}
+
+impl Apple1Display {
+    /// A display for a CPU clock of `clock_hz`.
+    pub fn new(clock_hz: u64, output: Option<Sender<u8>>) -> Apple1Display {
+        Apple1Display {
+            output,
+            frame_cycles: (clock_hz / FRAME_RATE_HZ).max(1),
+            busy_cycles: 0,
+            busy: false,
+            fast: Rc::new(Cell::new(false)),
+        }
+    }
+
+    /// Switch for the display delay, to be kept by the host after the display is attached.
+    pub fn fast_mode(&self) -> FastMode {
+        FastMode(self.fast.clone())
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.busy_cycles > 0 && !self.fast.get()
+    }
+}
+
+impl PiaPeripheral for Apple1Display {
+    fn poll(&mut self) -> Option<InputSignal> {
+        let busy = self.is_busy();
+        if busy == self.busy {
+            return None;
+        }
+        self.busy = busy;
+        Some(InputSignal::IRB(if busy { 0x80 } else { 0x00 }))
+    }
+
+    // a program not waiting for PB7 still gets all its characters shown
+    fn port_written(&mut self, data: u8) {
+        if let Some(tx) = &self.output {
+            let _ = tx.send(data);
+        }
+        self.busy_cycles = self.frame_cycles;
+    }
+
+    fn tick(&mut self) {
+        self.busy_cycles = self.busy_cycles.saturating_sub(1);
+    }
+}
+
+/// Shared switch of an `Apple1Display`: with fast mode on, PB7 never reads busy.
+#[derive(Clone)]
+pub struct FastMode(Rc<Cell<bool>>);
+
+impl FastMode {
+    pub fn set(&self, fast: bool) {
+        self.0.set(fast);
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.0.get()
+    }
+}
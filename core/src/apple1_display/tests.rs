@@ -0,0 +1,61 @@
+#[cfg(test)]
+use super::*;
+use crate::address_bus::InternalAddressing;
+use crate::mc6821::{Port, MC6821};
+
+const DSP: u16 = 0x2;
+const DSPCR: u16 = 0x3;
+
+// PIA as set up by the Woz monitor: PB0-6 output, PB7 input
+fn pia_with_display(fast: bool) -> (MC6821, crossbeam_channel::Receiver<u8>) {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let display = Apple1Display::new(600, Some(tx)); // 10 cycles per frame
+    display.fast_mode().set(fast);
+    let mut pia = MC6821::new();
+    pia.attach_peripheral(Port::B, Box::new(display));
+    pia.int_write(DSP, 0x7F);
+    pia.int_write(DSPCR, 0xA7);
+    (pia, rx)
+}
+
+fn ticks(pia: &mut MC6821, n: usize) {
+    for _ in 0..n {
+        pia.tick();
+    }
+}
+
+#[test]
+fn pb7_busy_for_a_frame_after_each_character() {
+    // arrange
+    let (mut pia, output) = pia_with_display(false);
+    let ready_before = pia.int_read(DSP) & 0x80;
+
+    // act
+    pia.int_write(DSP, 0xC1);
+    let busy_after_write = pia.int_read(DSP) & 0x80;
+    ticks(&mut pia, 9);
+    let busy_before_frame_end = pia.int_read(DSP) & 0x80;
+    ticks(&mut pia, 1);
+
+    // assert
+    assert_eq!(ready_before, 0);
+    assert_eq!(busy_after_write, 0x80);
+    assert_eq!(busy_before_frame_end, 0x80);
+    assert_eq!(pia.int_read(DSP) & 0x80, 0);
+    assert_eq!(output.try_recv(), Ok(0x41)); // PB7 is an input
+}
+
+#[test]
+fn fast_mode_is_never_busy() {
+    // arrange
+    let (mut pia, output) = pia_with_display(true);
+
+    // act
+    pia.int_write(DSP, 0xC1);
+    let busy_after_write = pia.int_read(DSP) & 0x80;
+    pia.int_write(DSP, 0xC2);
+
+    // assert
+    assert_eq!(busy_after_write, 0);
+    assert_eq!(output.try_iter().collect::<Vec<_>>(), vec![0x41, 0x42]);
+}
@@ -0,0 +1,109 @@
+#[cfg(test)]
+mod tests;
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use crate::mc6821::{InputSignal, PiaPeripheral, Signal};
+
+/// Keyboard of the Apple-1 on PIA port A with its strobe on CA1: keys come from a `KeyQueue`
+/// one at a time, the next one only after the program has read the previous one from the
+/// port, which also clears the strobe flag in CRA bit 7.
+pub struct Apple1Keyboard {
+    keys: Rc<RefCell<Keys>>,
+    signals: VecDeque<InputSignal>, // strobe sequence of the key being pressed
+    awaiting_read: bool,
+}
+
+#[derive(Default)]
+struct Keys {
+    pending: VecDeque<u8>,
+    read: usize,
+    total: usize,
+    last_cr: bool,
+}
+
+impl Default for Apple1Keyboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Apple1Keyboard {
+    pub fn new() -> Apple1Keyboard {
+        Apple1Keyboard {
+            keys: Rc::new(RefCell::new(Keys::default())),
+            signals: VecDeque::new(),
+            awaiting_read: false,
+        }
+    }
+
+    /// Queue of the keys to type, to be kept by the host after the keyboard is attached.
+    pub fn queue(&self) -> KeyQueue {
+        KeyQueue(self.keys.clone())
+    }
+}
+
+impl PiaPeripheral for Apple1Keyboard {
+    fn poll(&mut self) -> Option<InputSignal> {
+        if self.signals.is_empty() && !self.awaiting_read {
+            if let Some(key) = self.keys.borrow_mut().pending.pop_front() {
+                self.signals.extend([
+                    InputSignal::CA1(Signal::Fall),
+                    InputSignal::IRA(key | 0x80),
+                    InputSignal::CA1(Signal::Rise),
+                    InputSignal::CA1(Signal::Fall),
+                ]);
+                self.awaiting_read = true;
+            }
+        }
+        self.signals.pop_front()
+    }
+
+    fn port_read(&mut self, _data: u8) {
+        if self.awaiting_read && self.signals.is_empty() {
+            self.awaiting_read = false;
+            self.keys.borrow_mut().read += 1;
+        }
+    }
+}
+
+/// Shared input of an `Apple1Keyboard`.
+#[derive(Clone)]
+pub struct KeyQueue(Rc<RefCell<Keys>>);
+
+impl KeyQueue {
+    /// Type text as on the Apple-1 keyboard: newlines (also CR LF) become CR, letters upper case.
+    pub fn type_text(&self, text: &[u8]) {
+        let mut keys = self.0.borrow_mut();
+        if keys.read == keys.total {
+            keys.read = 0;
+            keys.total = 0;
+        }
+        for b in text {
+            let key = match b & 0x7F {
+                b'\n' if keys.last_cr => {
+                    keys.last_cr = false;
+                    continue;
+                }
+                b'\n' => b'\r',
+                c => c.to_ascii_uppercase(),
+            };
+            keys.last_cr = *b == b'\r';
+            keys.pending.push_back(key);
+            keys.total += 1;
+        }
+    }
+
+    /// Keys read by the program and keys typed since the queue last ran empty.
+    pub fn progress(&self) -> (usize, usize) {
+        let keys = self.0.borrow();
+        (keys.read, keys.total)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let keys = self.0.borrow();
+        keys.read == keys.total
+    }
+}
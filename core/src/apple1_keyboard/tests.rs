@@ -0,0 +1,60 @@
+#[cfg(test)]
+use super::*;
+use crate::address_bus::InternalAddressing;
+use crate::mc6821::{Port, MC6821};
+
+const KBD: u16 = 0x0;
+const KBDCR: u16 = 0x1;
+
+// PIA as set up by the Woz monitor: CA1 positive edge, KBD selected
+fn pia_with_keyboard() -> (MC6821, KeyQueue) {
+    let keyboard = Apple1Keyboard::new();
+    let queue = keyboard.queue();
+    let mut pia = MC6821::new();
+    pia.attach_peripheral(Port::A, Box::new(keyboard));
+    pia.int_write(KBDCR, 0xA7);
+    (pia, queue)
+}
+
+#[test]
+fn next_key_only_after_kbd_read() {
+    // arrange
+    let (mut pia, keys) = pia_with_keyboard();
+    keys.type_text(b"AB");
+
+    // act
+    let first_strobe = pia.int_read(KBDCR) & 0x80;
+    let still_first = (pia.int_read(KBDCR) & 0x80, pia.int_peek(KBD));
+    let first = pia.int_read(KBD);
+    let after_read = keys.progress();
+    let second_strobe = pia.int_read(KBDCR) & 0x80;
+    let second = pia.int_read(KBD);
+    let none_left = pia.int_read(KBDCR) & 0x80;
+
+    // assert
+    assert_eq!(first_strobe, 0x80);
+    assert_eq!(still_first, (0x80, 0xC1));
+    assert_eq!(first, 0xC1);
+    assert_eq!(after_read, (1, 2));
+    assert_eq!(second_strobe, 0x80);
+    assert_eq!(second, 0xC2);
+    assert_eq!(none_left, 0);
+    assert!(keys.is_empty());
+}
+
+#[test]
+fn newlines_become_cr_and_letters_upper_case() {
+    // arrange
+    let (mut pia, keys) = pia_with_keyboard();
+
+    // act
+    keys.type_text(b"a\nb\r\nc\r");
+    let mut typed = vec![];
+    while pia.int_read(KBDCR) & 0x80 != 0 {
+        typed.push(pia.int_read(KBD) & 0x7F);
+    }
+
+    // assert
+    assert_eq!(typed, b"A\rB\rC\r".to_vec());
+    assert_eq!(keys.progress(), (6, 6));
+}
@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod tests;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// An open-collector interrupt line: active while any connected output is active.
+#[derive(Clone, Default)]
+pub struct InterruptLine {
+    active_outputs: Rc<Cell<usize>>,
+}
+
+impl InterruptLine {
+    pub fn new() -> InterruptLine {
+        InterruptLine::default()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active_outputs.get() > 0
+    }
+
+    pub fn output(&self) -> InterruptOutput {
+        let mut output = InterruptOutput::default();
+        output.connect(self);
+        output
+    }
+}
+
+/// Interrupt output of a device, e.g. IRQA of a PIA; keeps its level when not connected.
+#[derive(Default)]
+pub struct InterruptOutput {
+    line: Option<InterruptLine>,
+    active: bool,
+}
+
+impl InterruptOutput {
+    pub fn set(&mut self, active: bool) {
+        if self.active == active {
+            return;
+        }
+        self.active = active;
+        if let Some(line) = &self.line {
+            let count = line.active_outputs.get();
+            line.active_outputs
+                .set(if active { count + 1 } else { count - 1 });
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Connect to `line`, leaving the line it was connected to before.
+    pub fn connect(&mut self, line: &InterruptLine) {
+        let active = self.active;
+        self.set(false);
+        self.line = Some(line.clone());
+        self.set(active);
+    }
+}
+
+impl Drop for InterruptOutput {
+    fn drop(&mut self) {
+        self.set(false);
+    }
+}
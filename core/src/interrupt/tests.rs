@@ -0,0 +1,39 @@
+#[cfg(test)]
+use super::*;
+
+#[test]
+fn line_is_wired_or_of_outputs() {
+    // arrange
+    let line = InterruptLine::new();
+    let mut a = line.output();
+    let mut b = line.output();
+
+    // act
+    a.set(true);
+    b.set(true);
+    a.set(false);
+    let one_active = line.is_active();
+    b.set(false);
+    let none_active = line.is_active();
+
+    // assert
+    assert!(one_active);
+    assert!(!none_active);
+}
+
+#[test]
+fn output_keeps_level_when_connected_later_or_dropped() {
+    // arrange
+    let line = InterruptLine::new();
+    let mut output = InterruptOutput::default();
+    output.set(true);
+
+    // act
+    output.connect(&line);
+    let connected = line.is_active();
+    drop(output);
+
+    // assert
+    assert!(connected);
+    assert!(!line.is_active());
+}
@@ -1,4 +1,16 @@
+pub mod aci;
 pub mod address_bus;
+pub mod apple1_display;
+pub mod apple1_keyboard;
+pub mod interrupt;
+pub mod loader;
+pub mod machine;
 pub mod mc6821;
+pub mod mc6850;
 pub mod memory;
 pub mod mos6502;
+pub mod mos6522;
+pub mod mos6532;
+pub mod mos6551;
+pub mod rom_catalogue;
+pub mod serial;
@@ -0,0 +1,145 @@
+use std::fs;
+use std::path::Path;
+
+use crate::address_bus::ExternalAddressing;
+use crate::loader::LoadError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Binary,
+    IntelHex,
+    SRecord,
+    /// `0300  A9 01 ...  |..|`, 16 bytes per line; only printable ASCII in the text column
+    HexDump,
+    /// `0300: A9 01 ...`, 8 bytes per line, as printed and accepted by the Woz monitor
+    Woz,
+}
+
+impl ExportFormat {
+    pub fn from_path(path: &Path) -> Option<ExportFormat> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "bin" | "raw" => Some(ExportFormat::Binary),
+            "hex" | "ihx" | "ihex" => Some(ExportFormat::IntelHex),
+            "s19" | "srec" | "mot" => Some(ExportFormat::SRecord),
+            "txt" | "dump" => Some(ExportFormat::HexDump),
+            "woz" => Some(ExportFormat::Woz),
+            _ => None,
+        }
+    }
+}
+
+/// Export `from_addr..=to_addr` of any bus or `Memory`; uses `peek`, so devices are not disturbed.
+pub fn export(
+    source: &dyn ExternalAddressing,
+    from_addr: u16,
+    to_addr: u16,
+    format: ExportFormat,
+) -> Result<Vec<u8>, LoadError> {
+    if to_addr < from_addr {
+        return Err(LoadError::EmptyRange { from_addr, to_addr });
+    }
+    let data = (from_addr..=to_addr)
+        .map(|addr| source.peek(addr))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(LoadError::Addressing)?;
+
+    Ok(match format {
+        ExportFormat::Binary => data,
+        ExportFormat::IntelHex => intel_hex(from_addr, &data).into_bytes(),
+        ExportFormat::SRecord => s_record(from_addr, &data).into_bytes(),
+        ExportFormat::HexDump => hex_dump(from_addr, &data).into_bytes(),
+        ExportFormat::Woz => woz(from_addr, &data).into_bytes(),
+    })
+}
+
+/// Export into a file, with the format guessed from its extension when `format` is `None`.
+pub fn export_file(
+    source: &dyn ExternalAddressing,
+    from_addr: u16,
+    to_addr: u16,
+    path: impl AsRef<Path>,
+    format: Option<ExportFormat>,
+) -> Result<(), LoadError> {
+    let path = path.as_ref();
+    let format = match format.or_else(|| ExportFormat::from_path(path)) {
+        Some(format) => format,
+        None => return Err(LoadError::UnknownFormat(path.to_path_buf())),
+    };
+    let bytes = export(source, from_addr, to_addr, format)?;
+    fs::write(path, bytes).map_err(|e| LoadError::Io {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    })
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+fn lines(from_addr: u16, data: &[u8], per_line: usize) -> impl Iterator<Item = (u16, &[u8])> {
+    data.chunks(per_line)
+        .enumerate()
+        .map(move |(i, chunk)| (from_addr.wrapping_add((i * per_line) as u16), chunk))
+}
+
+fn intel_hex(from_addr: u16, data: &[u8]) -> String {
+    let mut text = String::new();
+    for (addr, chunk) in lines(from_addr, data, 16) {
+        let mut record = vec![chunk.len() as u8, (addr >> 8) as u8, addr as u8, 0x00];
+        record.extend_from_slice(chunk);
+        let checksum = record
+            .iter()
+            .fold(0u8, |sum, b| sum.wrapping_add(*b))
+            .wrapping_neg();
+        text.push_str(&format!(":{}{:02X}\n", hex(&record), checksum));
+    }
+    text.push_str(":00000001FF\n");
+    text
+}
+
+fn s_record(from_addr: u16, data: &[u8]) -> String {
+    let record = |record_type: u8, addr: u16, payload: &[u8]| {
+        let mut record = vec![payload.len() as u8 + 3, (addr >> 8) as u8, addr as u8];
+        record.extend_from_slice(payload);
+        let checksum = !record.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+        format!("S{}{}{:02X}\n", record_type, hex(&record), checksum)
+    };
+
+    let mut text = record(0, 0, b"rust6502");
+    for (addr, chunk) in lines(from_addr, data, 16) {
+        text.push_str(&record(1, addr, chunk));
+    }
+    text.push_str(&record(9, from_addr, &[]));
+    text
+}
+
+fn hex_dump(from_addr: u16, data: &[u8]) -> String {
+    let mut text = String::new();
+    for (addr, chunk) in lines(from_addr, data, 16) {
+        let bytes: Vec<String> = chunk.iter().map(|b| format!("{:02X}", b)).collect();
+        let ascii: String = chunk
+            .iter()
+            .map(|b| match b {
+                0x20..=0x7E => *b as char,
+                _ => '.', // also Apple-1 text with bit 7 set, it is not told apart from data
+            })
+            .collect();
+        text.push_str(&format!(
+            "{:04X}  {:<47}  |{}|\n",
+            addr,
+            bytes.join(" "),
+            ascii
+        ));
+    }
+    text
+}
+
+fn woz(from_addr: u16, data: &[u8]) -> String {
+    let mut text = String::new();
+    for (addr, chunk) in lines(from_addr, data, 8) {
+        let bytes: Vec<String> = chunk.iter().map(|b| format!("{:02X}", b)).collect();
+        text.push_str(&format!("{:04X}: {}\n", addr, bytes.join(" ")));
+    }
+    text
+}
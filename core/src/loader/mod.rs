@@ -0,0 +1,372 @@
+#[cfg(test)]
+mod tests;
+
+mod export;
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::address_bus::{AddressingError, ExternalAddressing};
+
+pub use export::{export, export_file, ExportFormat};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    IntelHex,
+    SRecord,
+    /// raw bytes placed at `load_addr`
+    Binary {
+        load_addr: u16,
+    },
+    /// raw bytes behind a 2 byte little endian load address
+    Prg,
+}
+
+impl Format {
+    /// Guess the format from the file extension; raw binaries need an explicit load address.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "hex" | "ihx" | "ihex" => Some(Format::IntelHex),
+            "s19" | "s28" | "s37" | "srec" | "mot" => Some(Format::SRecord),
+            "prg" => Some(Format::Prg),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io {
+        path: PathBuf,
+        message: String,
+    },
+    UnknownFormat(PathBuf),
+    Parse {
+        line: usize,
+        message: String,
+    },
+    Checksum {
+        line: usize,
+        expected: u8,
+        actual: u8,
+    },
+    OutOfRange {
+        addr: u32,
+        len: usize,
+    },
+    EmptyRange {
+        from_addr: u16,
+        to_addr: u16,
+    },
+    Addressing(AddressingError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io { path, message } => write!(f, "{}: {}", path.display(), message),
+            LoadError::UnknownFormat(path) => {
+                write!(f, "{}: unknown format, load address needed", path.display())
+            }
+            LoadError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            LoadError::Checksum {
+                line,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "line {}: checksum ${:02X} does not match ${:02X}",
+                line, actual, expected
+            ),
+            LoadError::OutOfRange { addr, len } => write!(
+                f,
+                "{} bytes at ${:X} exceed the 64kB address space",
+                len, addr
+            ),
+            LoadError::EmptyRange { from_addr, to_addr } => write!(
+                f,
+                "end ${:04X} of the range is before its start ${:04X}",
+                to_addr, from_addr
+            ),
+            LoadError::Addressing(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Inclusive address range written by a load.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadedRange {
+    pub from_addr: u16,
+    pub to_addr: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LoadedProgram {
+    pub ranges: Vec<LoadedRange>,
+    pub entry_point: Option<u16>,
+}
+
+/// Parsed file contents: data blocks by address and the start address, if the file has one.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Image {
+    pub blocks: Vec<(u16, Vec<u8>)>,
+    pub entry_point: Option<u16>,
+}
+
+impl Image {
+    pub fn parse(format: Format, data: &[u8]) -> Result<Image, LoadError> {
+        match format {
+            Format::IntelHex => parse_intel_hex(data),
+            Format::SRecord => parse_s_record(data),
+            Format::Binary { load_addr } => binary_image(load_addr as u32, data.to_vec()),
+            Format::Prg => {
+                if data.len() < 2 {
+                    return Err(LoadError::Parse {
+                        line: 1,
+                        message: "PRG without load address".to_string(),
+                    });
+                }
+                let load_addr = u16::from_le_bytes([data[0], data[1]]);
+                binary_image(load_addr as u32, data[2..].to_vec())
+            }
+        }
+    }
+
+    pub fn ranges(&self) -> Vec<LoadedRange> {
+        let mut ranges: Vec<LoadedRange> = vec![];
+        for (addr, bytes) in self.blocks.iter().filter(|(_, bytes)| !bytes.is_empty()) {
+            let to_addr = addr + (bytes.len() - 1) as u16;
+            match ranges.last_mut() {
+                Some(range) if range.to_addr as u32 + 1 == *addr as u32 => range.to_addr = to_addr,
+                _ => ranges.push(LoadedRange {
+                    from_addr: *addr,
+                    to_addr,
+                }),
+            }
+        }
+        ranges
+    }
+
+    /// Write the image with `poke`, so it also goes into ROM and does not trigger devices.
+    pub fn write_to(
+        &self,
+        target: &mut dyn ExternalAddressing,
+    ) -> Result<LoadedProgram, LoadError> {
+        for (addr, bytes) in &self.blocks {
+            for (i, b) in bytes.iter().enumerate() {
+                target
+                    .poke(addr + i as u16, *b)
+                    .map_err(LoadError::Addressing)?;
+            }
+        }
+        Ok(LoadedProgram {
+            ranges: self.ranges(),
+            entry_point: self.entry_point,
+        })
+    }
+}
+
+pub fn load(
+    target: &mut dyn ExternalAddressing,
+    format: Format,
+    data: &[u8],
+) -> Result<LoadedProgram, LoadError> {
+    Image::parse(format, data)?.write_to(target)
+}
+
+/// Load a file, with the format guessed from its extension when `format` is `None`.
+pub fn load_file(
+    target: &mut dyn ExternalAddressing,
+    path: impl AsRef<Path>,
+    format: Option<Format>,
+) -> Result<LoadedProgram, LoadError> {
+    let path = path.as_ref();
+    let format = match format.or_else(|| Format::from_path(path)) {
+        Some(format) => format,
+        None => return Err(LoadError::UnknownFormat(path.to_path_buf())),
+    };
+    let data = fs::read(path).map_err(|e| LoadError::Io {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    })?;
+    load(target, format, &data)
+}
+
+fn binary_image(addr: u32, bytes: Vec<u8>) -> Result<Image, LoadError> {
+    Ok(Image {
+        blocks: vec![(checked_addr(addr, bytes.len())?, bytes)],
+        entry_point: None,
+    })
+}
+
+fn checked_addr(addr: u32, len: usize) -> Result<u16, LoadError> {
+    if addr as usize + len > 0x10000 {
+        Err(LoadError::OutOfRange { addr, len })
+    } else {
+        Ok(addr as u16)
+    }
+}
+
+// hex digits of one record line, without the leading ':' or 'Sx'
+fn hex_bytes(line: usize, digits: &str) -> Result<Vec<u8>, LoadError> {
+    let parse_error = |message: String| LoadError::Parse { line, message };
+    if !digits.is_ascii() {
+        return Err(parse_error("non-ASCII characters in record".to_string()));
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err(parse_error("odd number of hex digits".to_string()));
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..i + 2], 16)
+                .map_err(|_| parse_error(format!("invalid hex digits '{}'", &digits[i..i + 2])))
+        })
+        .collect()
+}
+
+fn record_lines(data: &[u8]) -> Result<Vec<(usize, &str)>, LoadError> {
+    let text = std::str::from_utf8(data).map_err(|e| LoadError::Parse {
+        line: 1 + data[..e.valid_up_to()]
+            .iter()
+            .filter(|b| **b == b'\n')
+            .count(),
+        message: "not a text file".to_string(),
+    })?;
+    Ok(text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .collect())
+}
+
+fn parse_intel_hex(data: &[u8]) -> Result<Image, LoadError> {
+    let mut image = Image::default();
+    let mut base = 0u32;
+
+    for (line, record) in record_lines(data)? {
+        let parse_error = |message: &str| LoadError::Parse {
+            line,
+            message: message.to_string(),
+        };
+        let digits = record
+            .strip_prefix(':')
+            .ok_or_else(|| parse_error("record does not start with ':'"))?;
+        let bytes = hex_bytes(line, digits)?;
+        if bytes.len() < 5 || bytes.len() != bytes[0] as usize + 5 {
+            return Err(parse_error("record length does not match byte count"));
+        }
+
+        let (record, checksum) = bytes.split_at(bytes.len() - 1);
+        let expected = record
+            .iter()
+            .fold(0u8, |sum, b| sum.wrapping_add(*b))
+            .wrapping_neg();
+        if expected != checksum[0] {
+            return Err(LoadError::Checksum {
+                line,
+                expected,
+                actual: checksum[0],
+            });
+        }
+
+        let offset = u16::from_be_bytes([record[1], record[2]]) as u32;
+        let payload = &record[4..];
+        match record[3] {
+            0x00 => {
+                let addr = checked_addr(base + offset, payload.len())?;
+                image.blocks.push((addr, payload.to_vec()));
+            }
+            0x01 => break,
+            0x02 | 0x04 if payload.len() == 2 => {
+                let value = u16::from_be_bytes([payload[0], payload[1]]) as u32;
+                base = if record[3] == 0x02 {
+                    value << 4
+                } else {
+                    value << 16
+                };
+            }
+            0x03 | 0x05 if payload.len() == 4 => {
+                let value = u32::from_be_bytes([payload[0], payload[1], payload[2], payload[3]]);
+                let entry_point = if record[3] == 0x03 {
+                    ((value >> 16) << 4) + (value & 0xFFFF) // CS:IP
+                } else {
+                    value
+                };
+                image.entry_point = Some(checked_addr(entry_point, 1)?);
+            }
+            0x02..=0x05 => return Err(parse_error("invalid address record length")),
+            record_type => {
+                return Err(parse_error(&format!(
+                    "unknown record type {:02X}",
+                    record_type
+                )))
+            }
+        }
+    }
+    Ok(image)
+}
+
+fn parse_s_record(data: &[u8]) -> Result<Image, LoadError> {
+    let mut image = Image::default();
+
+    for (line, record) in record_lines(data)? {
+        let parse_error = |message: &str| LoadError::Parse {
+            line,
+            message: message.to_string(),
+        };
+        let mut chars = record.chars();
+        if chars.next() != Some('S') {
+            return Err(parse_error("record does not start with 'S'"));
+        }
+        let record_type = chars
+            .next()
+            .and_then(|c| c.to_digit(10))
+            .ok_or_else(|| parse_error("missing record type"))?;
+        let bytes = hex_bytes(line, chars.as_str())?;
+        if bytes.is_empty() || bytes.len() != bytes[0] as usize + 1 {
+            return Err(parse_error("record length does not match byte count"));
+        }
+
+        let (record, checksum) = bytes.split_at(bytes.len() - 1);
+        let expected = !record.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+        if expected != checksum[0] {
+            return Err(LoadError::Checksum {
+                line,
+                expected,
+                actual: checksum[0],
+            });
+        }
+
+        let address_len = match record_type {
+            0 | 1 | 5 | 9 => 2,
+            2 | 6 | 8 => 3,
+            3 | 7 => 4,
+            _ => {
+                return Err(parse_error(&format!(
+                    "unknown record type S{}",
+                    record_type
+                )))
+            }
+        };
+        if record.len() < 1 + address_len {
+            return Err(parse_error("record too short for its address"));
+        }
+        let addr = record[1..1 + address_len]
+            .iter()
+            .fold(0u32, |addr, b| (addr << 8) | *b as u32);
+        let payload = &record[1 + address_len..];
+
+        match record_type {
+            1..=3 => image
+                .blocks
+                .push((checked_addr(addr, payload.len())?, payload.to_vec())),
+            7..=9 => image.entry_point = Some(checked_addr(addr, 1)?),
+            _ => (), // header and record counts
+        }
+    }
+    Ok(image)
+}
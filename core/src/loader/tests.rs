@@ -0,0 +1,332 @@
+#[cfg(test)]
+use super::*;
+use crate::address_bus::{AddressBus, InternalAddressing};
+use crate::memory::*;
+
+const INTEL_HEX: &str = "\
+:0403000001020304EF
+:020304000506EC
+:04100A00A9018D1299
+:040000050000030BE9
+:00000001FF
+";
+
+const S_RECORD: &str = "\
+S00600004844521B
+S107030001020304EB
+S10503040506E8
+S9030300F9
+";
+
+#[test]
+fn loads_intel_hex() {
+    // arrange
+    let mut ram = Memory::new(0, 0x10000);
+
+    // act
+    let actual = load(&mut ram, Format::IntelHex, INTEL_HEX.as_bytes()).unwrap();
+
+    // assert
+    assert_eq!(
+        actual.ranges,
+        vec![
+            LoadedRange {
+                from_addr: 0x0300,
+                to_addr: 0x0305
+            },
+            LoadedRange {
+                from_addr: 0x100A,
+                to_addr: 0x100D
+            }
+        ]
+    );
+    assert_eq!(actual.entry_point, Some(0x030B));
+    assert_eq!(ram.int_read(0x0305), 0x06);
+    assert_eq!(ram.int_read(0x100B), 0x01);
+}
+
+#[test]
+fn intel_hex_checksum_error_has_line() {
+    // arrange
+    let mut ram = Memory::new(0, 0x10000);
+    let broken = INTEL_HEX.replace(":020304000506EC", ":020304000506ED");
+
+    // act
+    let actual = load(&mut ram, Format::IntelHex, broken.as_bytes());
+
+    // assert
+    assert!(matches!(
+        actual,
+        Err(LoadError::Checksum {
+            line: 2,
+            expected: 0xEC,
+            actual: 0xED
+        })
+    ));
+}
+
+#[test]
+fn intel_hex_rejects_invalid_records() {
+    // arrange
+    let mut ram = Memory::new(0, 0x10000);
+
+    // act
+    let no_colon = load(&mut ram, Format::IntelHex, b"0403000001020304F2\n");
+    let bad_digits = load(&mut ram, Format::IntelHex, b":04030000010203XXF2\n");
+    let beyond_64k = load(
+        &mut ram,
+        Format::IntelHex,
+        b":020000040001F9\n:01000000AA55\n",
+    );
+
+    // assert
+    assert!(matches!(no_colon, Err(LoadError::Parse { line: 1, .. })));
+    assert!(matches!(bad_digits, Err(LoadError::Parse { line: 1, .. })));
+    assert!(matches!(
+        beyond_64k,
+        Err(LoadError::OutOfRange {
+            addr: 0x10000,
+            len: 1
+        })
+    ));
+}
+
+#[test]
+fn loads_s_record() {
+    // arrange
+    let mut ram = Memory::new(0, 0x10000);
+
+    // act
+    let actual = load(&mut ram, Format::SRecord, S_RECORD.as_bytes()).unwrap();
+
+    // assert
+    assert_eq!(
+        actual.ranges,
+        vec![LoadedRange {
+            from_addr: 0x0300,
+            to_addr: 0x0305
+        }]
+    );
+    assert_eq!(actual.entry_point, Some(0x0300));
+    assert_eq!(ram.int_read(0x0304), 0x05);
+}
+
+#[test]
+fn s_record_checksum_error_has_line() {
+    // arrange
+    let mut ram = Memory::new(0, 0x10000);
+    let broken = S_RECORD.replace("S10503040506E8", "S10503040506E9");
+
+    // act
+    let actual = load(&mut ram, Format::SRecord, broken.as_bytes());
+
+    // assert
+    assert!(matches!(actual, Err(LoadError::Checksum { line: 3, .. })));
+}
+
+#[test]
+fn loads_binary_at_address() {
+    // arrange
+    let mut ram = Memory::new(0, 0x1000);
+
+    // act
+    let actual = load(
+        &mut ram,
+        Format::Binary { load_addr: 0x0280 },
+        &[0xA9, 0x01, 0x60],
+    )
+    .unwrap();
+
+    // assert
+    assert_eq!(
+        actual,
+        LoadedProgram {
+            ranges: vec![LoadedRange {
+                from_addr: 0x0280,
+                to_addr: 0x0282
+            }],
+            entry_point: None
+        }
+    );
+    assert_eq!(ram.int_read(0x0282), 0x60);
+}
+
+#[test]
+fn loads_prg_with_load_header() {
+    // arrange
+    let mut ram = Memory::new(0, 0x1000);
+
+    // act
+    let actual = load(&mut ram, Format::Prg, &[0x01, 0x08, 0xEA, 0xEA]).unwrap();
+    let too_short = load(&mut ram, Format::Prg, &[0x01]);
+
+    // assert
+    assert_eq!(
+        actual.ranges,
+        vec![LoadedRange {
+            from_addr: 0x0801,
+            to_addr: 0x0802
+        }]
+    );
+    assert!(matches!(too_short, Err(LoadError::Parse { .. })));
+}
+
+#[test]
+fn load_outside_target_reports_addressing_error() {
+    // arrange
+    let mut ram = Memory::new(0, 0x100);
+
+    // act
+    let actual = load(
+        &mut ram,
+        Format::Binary { load_addr: 0x00FF },
+        &[0x01, 0x02],
+    );
+
+    // assert
+    assert!(matches!(actual, Err(LoadError::Addressing(_))));
+}
+
+#[test]
+fn loads_into_rom_on_bus() {
+    // arrange
+    let mut rom = Memory::from_vec(0xE000, vec![0x00; 0x100]);
+    rom.set_write_policy(WritePolicy::Error);
+    let mut bus = AddressBus::new(0x100);
+    bus.add_component(0xE000, rom.len(), &mut rom).unwrap();
+
+    // act
+    let actual = load(&mut bus, Format::Binary { load_addr: 0xE010 }, &[0x4C]);
+
+    // assert
+    assert!(actual.is_ok());
+    assert_eq!(bus.peek(0xE010).unwrap(), 0x4C);
+}
+
+#[test]
+fn guesses_format_from_extension() {
+    // act & assert
+    assert_eq!(
+        Format::from_path(Path::new("prog.HEX")),
+        Some(Format::IntelHex)
+    );
+    assert_eq!(
+        Format::from_path(Path::new("prog.s19")),
+        Some(Format::SRecord)
+    );
+    assert_eq!(Format::from_path(Path::new("prog.prg")), Some(Format::Prg));
+    assert_eq!(Format::from_path(Path::new("prog.bin")), None);
+    assert!(matches!(
+        load_file(&mut Memory::new(0, 1), "prog.bin", None),
+        Err(LoadError::UnknownFormat(_))
+    ));
+}
+
+fn ram_with_program() -> Memory {
+    let mut ram = Memory::new(0, 0x1000);
+    for (i, b) in (0x0300u16..0x0315).zip(0x41u8..) {
+        ram.int_write(i, b);
+    }
+    ram
+}
+
+#[test]
+fn exports_intel_hex_and_s_record_round_trip() {
+    // arrange
+    let ram = ram_with_program();
+
+    for (export_format, format) in [
+        (ExportFormat::IntelHex, Format::IntelHex),
+        (ExportFormat::SRecord, Format::SRecord),
+    ] {
+        let mut copy = Memory::new(0, 0x1000);
+
+        // act
+        let exported = export(&ram, 0x0300, 0x0314, export_format).unwrap();
+        let loaded = load(&mut copy, format, &exported).unwrap();
+
+        // assert
+        assert_eq!(
+            loaded.ranges,
+            vec![LoadedRange {
+                from_addr: 0x0300,
+                to_addr: 0x0314
+            }]
+        );
+        assert_eq!(
+            export(&copy, 0x0300, 0x0314, ExportFormat::Binary).unwrap(),
+            export(&ram, 0x0300, 0x0314, ExportFormat::Binary).unwrap()
+        );
+    }
+}
+
+#[test]
+fn exports_hex_dump() {
+    // arrange
+    let ram = ram_with_program();
+
+    // act
+    let actual = String::from_utf8(export(&ram, 0x0300, 0x0311, ExportFormat::HexDump).unwrap());
+
+    // assert
+    assert_eq!(
+        actual.unwrap(),
+        "0300  41 42 43 44 45 46 47 48 49 4A 4B 4C 4D 4E 4F 50  |ABCDEFGHIJKLMNOP|\n\
+         0310  51 52                                            |QR|\n"
+    );
+}
+
+#[test]
+fn hex_dump_shows_only_ascii_as_text() {
+    // arrange
+    let ram = Memory::from_vec(0x0300, vec![0x41, 0xC1, 0x7F, 0xFF, 0x1F]);
+
+    // act
+    let actual = String::from_utf8(export(&ram, 0x0300, 0x0304, ExportFormat::HexDump).unwrap());
+
+    // assert
+    assert!(actual.unwrap().ends_with("|A....|\n"));
+}
+
+#[test]
+fn exports_woz_monitor_lines() {
+    // arrange
+    let ram = ram_with_program();
+
+    // act
+    let actual = String::from_utf8(export(&ram, 0x0300, 0x0309, ExportFormat::Woz).unwrap());
+
+    // assert
+    assert_eq!(
+        actual.unwrap(),
+        "0300: 41 42 43 44 45 46 47 48\n0308: 49 4A\n"
+    );
+}
+
+#[test]
+fn export_rejects_invalid_ranges() {
+    // arrange
+    let ram = ram_with_program();
+
+    // act & assert
+    let reversed = export(&ram, 0x0FFF, 0x0280, ExportFormat::Binary).unwrap_err();
+    assert!(matches!(
+        reversed,
+        LoadError::EmptyRange {
+            from_addr: 0x0FFF,
+            to_addr: 0x0280
+        }
+    ));
+    assert_eq!(
+        reversed.to_string(),
+        "end $0280 of the range is before its start $0FFF"
+    );
+    assert!(matches!(
+        export(&ram, 0x0FFF, 0x1000, ExportFormat::Binary),
+        Err(LoadError::Addressing(_))
+    ));
+    assert_eq!(
+        ExportFormat::from_path(Path::new("basic.woz")),
+        Some(ExportFormat::Woz)
+    );
+}
@@ -0,0 +1,282 @@
+use serde::Deserialize;
+
+use crate::aci::ROM_SIZE as ACI_ROM_SIZE;
+use crate::machine::MachineError;
+use crate::mos6532::RAM_SIZE as RIOT_RAM_SIZE;
+
+/// A machine as written down in a description file, e.g. `machines/apple1.toml`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MachineDescription {
+    pub name: String,
+    #[serde(default)]
+    pub cpu: CpuVariant,
+    #[serde(default = "default_clock_hz")]
+    pub clock_hz: u64,
+    #[serde(default = "default_block_size")]
+    pub block_size: usize,
+    /// how accesses to addresses without RAM, ROM or device are answered
+    #[serde(default)]
+    pub unmapped: Unmapped,
+    #[serde(default, rename = "ram")]
+    pub rams: Vec<RamRegion>,
+    #[serde(default, rename = "rom")]
+    pub roms: Vec<RomImage>,
+    #[serde(default, rename = "device")]
+    pub devices: Vec<DeviceDescription>,
+}
+
+fn default_clock_hz() -> u64 {
+    1_000_000
+}
+
+fn default_block_size() -> usize {
+    0x10
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum CpuVariant {
+    #[default]
+    #[serde(rename = "6502")]
+    Mos6502,
+}
+
+/// `UnmappedPolicy` of the bus; `log` is open bus with the accesses in `unmapped_log`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Unmapped {
+    #[default]
+    Error,
+    OpenBus,
+    Log,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RamRegion {
+    pub start: u16,
+    pub size: usize,
+    #[serde(default)]
+    pub wait_states: u8,
+    /// reads of bytes not written since power-on, see `Machine::uninitialized_logs`
+    #[serde(default)]
+    pub on_uninitialized: UninitializedRam,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UninitializedRam {
+    #[default]
+    Allow,
+    Log,
+    Stop,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RomImage {
+    pub start: u16,
+    /// path of the image, relative to the description file
+    pub image: String,
+    #[serde(default)]
+    pub on_write: RomWrites,
+    #[serde(default)]
+    pub wait_states: u8,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RomWrites {
+    #[default]
+    Ignore,
+    Error,
+    Log,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeviceDescription {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: DeviceKind,
+    pub base: u16,
+    /// address lines the device is decoded on; without a mask it only occupies its registers
+    pub decode_mask: Option<u16>,
+    #[serde(default)]
+    pub wait_states: u8,
+    /// connect the device's interrupt outputs to the CPU's IRQ input
+    #[serde(default)]
+    pub irq: bool,
+    /// host side of a serial device; without it the line is reached through `take_serial_channels`
+    pub serial: Option<SerialConnection>,
+    /// `mc6850` transmit/receive clock before the counter divide, 1.8432 MHz by default
+    pub serial_clock_hz: Option<u64>,
+    /// where the 128 bytes of `mos6532` RAM are mapped; `base` is the I/O and timer range
+    pub ram_base: Option<u16>,
+    /// `mc6821` port B drives an Apple-1 terminal section, busy on PB7 for a frame per character
+    #[serde(default)]
+    pub apple1_display: bool,
+    /// `mc6821` port A reads an Apple-1 keyboard, taking the next key once the port was read
+    #[serde(default)]
+    pub apple1_keyboard: bool,
+    /// PROM of an `aci`, relative to the description file like ROM images
+    pub image: Option<String>,
+}
+
+/// Host end of the line of an ACIA: `"stdio"`, `"pty"` or `"tcp:ADDRESS"`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum SerialConnection {
+    Stdio,
+    Pty,
+    Tcp(String),
+}
+
+impl TryFrom<String> for SerialConnection {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "stdio" => Ok(SerialConnection::Stdio),
+            "pty" => Ok(SerialConnection::Pty),
+            _ => match value.strip_prefix("tcp:") {
+                Some(addr) if !addr.is_empty() => Ok(SerialConnection::Tcp(addr.to_string())),
+                _ => Err(format!(
+                    "unknown serial connection '{}', expected stdio, pty or tcp:ADDRESS",
+                    value
+                )),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceKind {
+    Mc6821,
+    Mos6522,
+    Mos6551,
+    Mc6850,
+    Mos6532,
+    Aci,
+}
+
+impl DeviceKind {
+    pub fn register_count(&self) -> usize {
+        match self {
+            DeviceKind::Mc6821 => 4,
+            DeviceKind::Mos6522 => 16,
+            DeviceKind::Mos6551 => 4,
+            DeviceKind::Mc6850 => 2,
+            DeviceKind::Mos6532 => 32,
+            DeviceKind::Aci => 2 * ACI_ROM_SIZE,
+        }
+    }
+}
+
+impl MachineDescription {
+    pub fn parse(text: &str) -> Result<MachineDescription, MachineError> {
+        let description: MachineDescription =
+            toml::from_str(text).map_err(|e| MachineError::Parse(e.to_string()))?;
+        description.validate()?;
+        Ok(description)
+    }
+
+    /// Checks what can be checked without the ROM images; overlaps are found when mapping.
+    pub fn validate(&self) -> Result<(), MachineError> {
+        let invalid = |reason: String| Err(MachineError::Invalid(reason));
+
+        if self.clock_hz == 0 {
+            return invalid("clock_hz must not be 0".to_string());
+        }
+        if self.block_size == 0 || 0x10000 % self.block_size != 0 {
+            return invalid(format!(
+                "block_size {} does not divide 64kB",
+                self.block_size
+            ));
+        }
+        for ram in &self.rams {
+            if ram.size == 0 || ram.start as usize + ram.size > 0x10000 {
+                return invalid(format!(
+                    "RAM of {} bytes at ${:04X} does not fit the address space",
+                    ram.size, ram.start
+                ));
+            }
+        }
+        for (i, device) in self.devices.iter().enumerate() {
+            if self.devices[..i].iter().any(|d| d.name == device.name) {
+                return invalid(format!("device name '{}' used twice", device.name));
+            }
+            let serial = matches!(device.kind, DeviceKind::Mos6551 | DeviceKind::Mc6850);
+            if device.serial.is_some() && !serial {
+                return invalid(format!(
+                    "device '{}' has no serial line to connect",
+                    device.name
+                ));
+            }
+            match device.serial_clock_hz {
+                Some(_) if device.kind != DeviceKind::Mc6850 => {
+                    return invalid(format!(
+                        "device '{}' has no serial clock input",
+                        device.name
+                    ));
+                }
+                Some(0) => {
+                    return invalid(format!(
+                        "serial_clock_hz of device '{}' must not be 0",
+                        device.name
+                    ));
+                }
+                _ => (),
+            }
+            match device.ram_base {
+                Some(_) if device.kind != DeviceKind::Mos6532 => {
+                    return invalid(format!("device '{}' has no RAM", device.name));
+                }
+                Some(ram_base) if ram_base as usize + RIOT_RAM_SIZE > 0x10000 => {
+                    return invalid(format!(
+                        "RAM of device '{}' at ${:04X} does not fit the address space",
+                        device.name, ram_base
+                    ));
+                }
+                _ => (),
+            }
+            if device.apple1_display && device.kind != DeviceKind::Mc6821 {
+                return invalid(format!(
+                    "device '{}' cannot drive an Apple-1 display",
+                    device.name
+                ));
+            }
+            if device.apple1_keyboard && device.kind != DeviceKind::Mc6821 {
+                return invalid(format!(
+                    "device '{}' cannot read an Apple-1 keyboard",
+                    device.name
+                ));
+            }
+            match (&device.image, device.kind) {
+                (None, DeviceKind::Aci) => {
+                    return invalid(format!("device '{}' needs its PROM image", device.name));
+                }
+                (Some(_), kind) if kind != DeviceKind::Aci => {
+                    return invalid(format!("device '{}' has no PROM", device.name));
+                }
+                _ => (),
+            }
+            if device.kind == DeviceKind::Aci && device.base & 0x1FF != 0 {
+                return invalid(format!(
+                    "device '{}' base ${:04X} is not on a 512 byte boundary",
+                    device.name, device.base
+                ));
+            }
+            if let Some(mask) = device.decode_mask {
+                if device.base & !mask != 0 {
+                    return invalid(format!(
+                        "device '{}' base ${:04X} has bits outside decode_mask ${:04X}",
+                        device.name, device.base, mask
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
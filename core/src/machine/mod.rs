@@ -0,0 +1,426 @@
+#[cfg(test)]
+mod tests;
+
+mod description;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+
+use crate::aci::{Aci, Cassette, ROM_SIZE as ACI_ROM_SIZE};
+use crate::address_bus::{
+    AddressBus, Decoding, InternalAddressing, MapOptions, MappingError, Region, UnmappedPolicy,
+};
+use crate::apple1_display::{Apple1Display, FastMode};
+use crate::apple1_keyboard::{Apple1Keyboard, KeyQueue};
+use crate::interrupt::InterruptLine;
+use crate::mc6821::{InputSignal, Port, MC6821};
+use crate::mc6850::MC6850;
+use crate::memory::{Memory, UninitializedLog, UninitializedReads, WritePolicy};
+use crate::mos6502::{Cpu, CpuRegisters};
+use crate::mos6522::MOS6522;
+use crate::mos6532::{MOS6532, RAM_SIZE as RIOT_RAM_SIZE};
+use crate::mos6551::MOS6551;
+use crate::serial::{ChannelSerial, SerialBackend, StdioSerial, TcpSerial};
+
+pub use description::{
+    CpuVariant, DeviceDescription, DeviceKind, MachineDescription, RamRegion, RomImage, RomWrites,
+    SerialConnection, UninitializedRam, Unmapped,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MachineError {
+    Io { path: PathBuf, message: String },
+    Parse(String),
+    Invalid(String),
+    Mapping { what: String, error: MappingError },
+    Serial { device: String, message: String },
+}
+
+impl fmt::Display for MachineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MachineError::Io { path, message } => write!(f, "{}: {}", path.display(), message),
+            MachineError::Parse(message) => write!(f, "invalid machine description: {}", message),
+            MachineError::Invalid(reason) => write!(f, "invalid machine: {}", reason),
+            MachineError::Mapping { what, error } => write!(f, "mapping {}: {}", what, error),
+            MachineError::Serial { device, message } => {
+                write!(f, "serial line of '{}': {}", device, message)
+            }
+        }
+    }
+}
+
+/// Host side of a `MC6821`, `MOS6522` or `MOS6532` built from a description.
+pub struct PiaChannels {
+    pub input: Sender<InputSignal>,
+    pub output_a: Receiver<u8>,
+    pub output_b: Receiver<u8>,
+    /// display delay switch of a PIA with `apple1_display`
+    pub fast_display: Option<FastMode>,
+    /// keys for a PIA with `apple1_keyboard`, typed as the program reads them
+    pub keyboard: Option<KeyQueue>,
+}
+
+/// Host side of a `MOS6551` or `MC6850` built from a description without a `serial` connection.
+pub struct SerialChannels {
+    pub input: Sender<u8>,
+    pub output: Receiver<u8>,
+}
+
+/// A machine built from a `MachineDescription`; the bus owns all components, a `Cpu` is
+/// connected with `cpu()`.
+pub struct Machine {
+    pub description: MachineDescription,
+    pub bus: AddressBus<'static>,
+    /// IRQ input of the CPU, driven by devices with `irq = true`
+    pub irq: InterruptLine,
+    /// problems found while building that do not stop the machine, e.g. unknown ROM images
+    pub warnings: Vec<String>,
+    pias: HashMap<String, PiaChannels>,
+    serials: HashMap<String, SerialChannels>,
+    serial_endpoints: Vec<(String, String)>,
+    cassettes: HashMap<String, Cassette>,
+    uninitialized_logs: Vec<UninitializedLog>,
+}
+
+impl Machine {
+    /// Load a description file; ROM images are looked up relative to it.
+    pub fn load(path: impl AsRef<Path>) -> Result<Machine, MachineError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| io_error(path, e))?;
+        let description = MachineDescription::parse(&text)?;
+        let base_dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        Machine::build_with(description, |image| {
+            let image_path = base_dir.join(image);
+            fs::read(&image_path).map_err(|e| io_error(&image_path, e))
+        })
+    }
+
+    /// Build a machine, getting ROM images from `load_image`, e.g. where there is no file system.
+    pub fn build_with(
+        description: MachineDescription,
+        mut load_image: impl FnMut(&str) -> Result<Vec<u8>, MachineError>,
+    ) -> Result<Machine, MachineError> {
+        description.validate()?;
+
+        let mut bus = AddressBus::new(description.block_size);
+        bus.set_unmapped_policy(match description.unmapped {
+            Unmapped::Error => UnmappedPolicy::Error,
+            Unmapped::OpenBus => UnmappedPolicy::OpenBus,
+            Unmapped::Log => UnmappedPolicy::IgnoreWithLog,
+        });
+        let mut pias = HashMap::new();
+        let mut serials = HashMap::new();
+        let mut serial_endpoints = vec![];
+        let mut cassettes = HashMap::new();
+        let mut uninitialized_logs = vec![];
+        let mut warnings = vec![];
+        let irq = InterruptLine::new();
+
+        for ram in &description.rams {
+            let mut memory = Memory::new(ram.start, ram.size);
+            let policy = match ram.on_uninitialized {
+                UninitializedRam::Allow => UninitializedReads::Allow,
+                UninitializedRam::Log => UninitializedReads::Log,
+                UninitializedRam::Stop => UninitializedReads::Stop,
+            };
+            if policy != UninitializedReads::Allow {
+                memory.set_uninitialized_reads(policy);
+                uninitialized_logs.push(memory.uninitialized_log());
+            }
+            let id = bus.attach_owned_component(Box::new(memory));
+            bus.map_with(
+                id,
+                Region::Range {
+                    from_addr: ram.start,
+                    size: ram.size,
+                },
+                MapOptions::default().with_wait_states(ram.wait_states),
+            )
+            .map_err(|error| MachineError::Mapping {
+                what: format!("RAM at ${:04X}", ram.start),
+                error,
+            })?;
+        }
+
+        for rom in &description.roms {
+            let data = load_image(&rom.image)?;
+            if data.is_empty() || rom.start as usize + data.len() > 0x10000 {
+                return Err(MachineError::Invalid(format!(
+                    "ROM image {} of {} bytes does not fit at ${:04X}",
+                    rom.image,
+                    data.len(),
+                    rom.start
+                )));
+            }
+            let size = data.len();
+            let (mut memory, check) = Memory::from_rom_image(rom.start, data);
+            if !check.is_known() {
+                warnings.push(format!("ROM {}: {}", rom.image, check));
+            }
+            memory.set_write_policy(match rom.on_write {
+                RomWrites::Ignore => WritePolicy::Ignore,
+                RomWrites::Error => WritePolicy::Error,
+                RomWrites::Log => WritePolicy::Log,
+            });
+            let id = bus.attach_owned_component(Box::new(memory));
+            bus.map_with(
+                id,
+                Region::Range {
+                    from_addr: rom.start,
+                    size,
+                },
+                MapOptions::default().with_wait_states(rom.wait_states),
+            )
+            .map_err(|error| MachineError::Mapping {
+                what: format!("ROM {}", rom.image),
+                error,
+            })?;
+        }
+
+        for device in &description.devices {
+            let component: Box<dyn InternalAddressing> = match device.kind {
+                DeviceKind::Mc6821 => {
+                    let (rx_input, tx_output_a, tx_output_b) =
+                        pia_channels(&mut pias, &device.name);
+                    let mut pia = MC6821::new();
+                    pia.set_input_channel(rx_input);
+                    pia.set_output_channel_a(tx_output_a);
+                    if device.apple1_display {
+                        let display = Apple1Display::new(description.clock_hz, Some(tx_output_b));
+                        if let Some(channels) = pias.get_mut(&device.name) {
+                            channels.fast_display = Some(display.fast_mode());
+                        }
+                        pia.attach_peripheral(Port::B, Box::new(display));
+                    } else {
+                        pia.set_output_channel_b(tx_output_b);
+                    }
+                    if device.apple1_keyboard {
+                        let keyboard = Apple1Keyboard::new();
+                        if let Some(channels) = pias.get_mut(&device.name) {
+                            channels.keyboard = Some(keyboard.queue());
+                        }
+                        pia.attach_peripheral(Port::A, Box::new(keyboard));
+                    }
+                    if device.irq {
+                        pia.connect_irqa(&irq);
+                        pia.connect_irqb(&irq);
+                    }
+                    Box::new(pia)
+                }
+                DeviceKind::Mos6522 => {
+                    let (rx_input, tx_output_a, tx_output_b) =
+                        pia_channels(&mut pias, &device.name);
+                    let mut via = MOS6522::new();
+                    via.set_input_channel(rx_input);
+                    via.set_output_channel_a(tx_output_a);
+                    via.set_output_channel_b(tx_output_b);
+                    if device.irq {
+                        via.connect_irq(&irq);
+                    }
+                    Box::new(via)
+                }
+                DeviceKind::Mos6532 => {
+                    let (rx_input, tx_output_a, tx_output_b) =
+                        pia_channels(&mut pias, &device.name);
+                    let mut riot = MOS6532::new();
+                    riot.set_input_channel(rx_input);
+                    riot.set_output_channel_a(tx_output_a);
+                    riot.set_output_channel_b(tx_output_b);
+                    if device.irq {
+                        riot.connect_irq(&irq);
+                    }
+                    if let Some(ram_base) = device.ram_base {
+                        let id = bus.attach_owned_component(Box::new(riot.ram()));
+                        bus.map_with(
+                            id,
+                            Region::Range {
+                                from_addr: ram_base,
+                                size: RIOT_RAM_SIZE,
+                            },
+                            MapOptions::default().with_wait_states(device.wait_states),
+                        )
+                        .map_err(|error| MachineError::Mapping {
+                            what: format!("RAM of device '{}'", device.name),
+                            error,
+                        })?;
+                    }
+                    Box::new(riot)
+                }
+                DeviceKind::Mos6551 => {
+                    let backend = serial_backend(device, &mut serials, &mut serial_endpoints)?;
+                    let mut acia = MOS6551::new(description.clock_hz, backend);
+                    if device.irq {
+                        acia.connect_irq(&irq);
+                    }
+                    Box::new(acia)
+                }
+                DeviceKind::Mc6850 => {
+                    let backend = serial_backend(device, &mut serials, &mut serial_endpoints)?;
+                    let mut acia = MC6850::new(description.clock_hz, backend);
+                    if let Some(hz) = device.serial_clock_hz {
+                        acia.set_serial_clock_hz(hz);
+                    }
+                    if device.irq {
+                        acia.connect_irq(&irq);
+                    }
+                    Box::new(acia)
+                }
+                DeviceKind::Aci => {
+                    let image = device.image.as_deref().unwrap_or_default();
+                    let rom = load_image(image)?;
+                    if rom.len() != ACI_ROM_SIZE {
+                        return Err(MachineError::Invalid(format!(
+                            "PROM image {} of device '{}' has {} bytes, expected {}",
+                            image,
+                            device.name,
+                            rom.len(),
+                            ACI_ROM_SIZE
+                        )));
+                    }
+                    let aci = Aci::new(description.clock_hz, rom);
+                    cassettes.insert(device.name.clone(), aci.cassette());
+                    Box::new(aci)
+                }
+            };
+
+            let id = bus.attach_owned_component(component);
+            let region = match device.decode_mask {
+                Some(mask) => Region::Decoded(Decoding::new(mask, device.base)),
+                None => Region::Range {
+                    from_addr: device.base,
+                    size: device.kind.register_count(),
+                },
+            };
+            bus.map_with(
+                id,
+                region,
+                MapOptions::default().with_wait_states(device.wait_states),
+            )
+            .map_err(|error| MachineError::Mapping {
+                what: format!("device '{}'", device.name),
+                error,
+            })?;
+        }
+
+        Ok(Machine {
+            description,
+            bus,
+            irq,
+            warnings,
+            pias,
+            serials,
+            serial_endpoints,
+            cassettes,
+            uninitialized_logs,
+        })
+    }
+
+    /// Channels of the `MC6821`, `MOS6522` or `MOS6532` with the given name; they can only be
+    /// taken once.
+    pub fn take_pia_channels(&mut self, name: &str) -> Option<PiaChannels> {
+        self.pias.remove(name)
+    }
+
+    /// Channels of a `MOS6551` or `MC6850` without a `serial` connection; they can only be taken once.
+    pub fn take_serial_channels(&mut self, name: &str) -> Option<SerialChannels> {
+        self.serials.remove(name)
+    }
+
+    /// Device names with the address a `tcp` serial line listens on or the path of a `pty`,
+    /// for the user to connect to.
+    pub fn serial_endpoints(&self) -> &[(String, String)] {
+        &self.serial_endpoints
+    }
+
+    /// Tape deck of the `aci` with the given name; it can only be taken once.
+    pub fn take_cassette(&mut self, name: &str) -> Option<Cassette> {
+        self.cassettes.remove(name)
+    }
+
+    /// Uninitialized read logs of the RAM regions with `on_uninitialized` `log` or `stop`.
+    pub fn uninitialized_logs(&self) -> Vec<UninitializedLog> {
+        self.uninitialized_logs.clone()
+    }
+
+    pub fn cpu(&mut self) -> Cpu<'_> {
+        let mut cpu = Cpu::new(CpuRegisters::default(), &mut self.bus);
+        cpu.set_irq_line(self.irq.clone());
+        cpu
+    }
+}
+
+fn pia_channels(
+    pias: &mut HashMap<String, PiaChannels>,
+    name: &str,
+) -> (Receiver<InputSignal>, Sender<u8>, Sender<u8>) {
+    let (tx_input, rx_input) = unbounded();
+    let (tx_output_a, rx_output_a) = unbounded();
+    let (tx_output_b, rx_output_b) = unbounded();
+    pias.insert(
+        name.to_string(),
+        PiaChannels {
+            input: tx_input,
+            output_a: rx_output_a,
+            output_b: rx_output_b,
+            fast_display: None,
+            keyboard: None,
+        },
+    );
+    (rx_input, tx_output_a, tx_output_b)
+}
+
+fn serial_backend(
+    device: &DeviceDescription,
+    serials: &mut HashMap<String, SerialChannels>,
+    endpoints: &mut Vec<(String, String)>,
+) -> Result<Box<dyn SerialBackend>, MachineError> {
+    let serial_error = |e: std::io::Error| MachineError::Serial {
+        device: device.name.clone(),
+        message: e.to_string(),
+    };
+
+    match &device.serial {
+        None => {
+            let (tx_input, rx_input) = unbounded();
+            let (tx_output, rx_output) = unbounded();
+            serials.insert(
+                device.name.clone(),
+                SerialChannels {
+                    input: tx_input,
+                    output: rx_output,
+                },
+            );
+            Ok(Box::new(ChannelSerial::new(rx_input, tx_output)))
+        }
+        Some(SerialConnection::Stdio) => Ok(Box::new(StdioSerial::new())),
+        Some(SerialConnection::Tcp(addr)) => {
+            let tcp = TcpSerial::listen(addr.as_str()).map_err(serial_error)?;
+            let local_addr = tcp.local_addr().map_err(serial_error)?;
+            endpoints.push((device.name.clone(), local_addr.to_string()));
+            Ok(Box::new(tcp))
+        }
+        #[cfg(unix)]
+        Some(SerialConnection::Pty) => {
+            let pty = crate::serial::PtySerial::open().map_err(serial_error)?;
+            endpoints.push((device.name.clone(), pty.path().display().to_string()));
+            Ok(Box::new(pty))
+        }
+        #[cfg(not(unix))]
+        Some(SerialConnection::Pty) => Err(MachineError::Serial {
+            device: device.name.clone(),
+            message: "pseudo-terminals need a Unix host".to_string(),
+        }),
+    }
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> MachineError {
+    MachineError::Io {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    }
+}
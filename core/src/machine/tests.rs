@@ -0,0 +1,465 @@
+#[cfg(test)]
+use super::*;
+use crate::address_bus::ExternalAddressing;
+use crate::mc6821::Signal;
+
+fn machines_path(file: &str) -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.pop();
+    path.push("machines");
+    path.push(file);
+    path
+}
+
+const SMALL_MACHINE: &str = r#"
+name = "small"
+
+[[ram]]
+start = 0x0000
+size = 0x0200
+
+[[rom]]
+start = 0xFF00
+image = "monitor"
+on_write = "error"
+wait_states = 1
+
+[[device]]
+name = "pia"
+type = "mc6821"
+base = 0xD010
+"#;
+
+fn monitor_image(_image: &str) -> Result<Vec<u8>, MachineError> {
+    let mut rom = vec![0xEA; 0x100];
+    rom[0xFC] = 0x00; // reset vector $FF00
+    rom[0xFD] = 0xFF;
+    Ok(rom)
+}
+
+#[test]
+fn parses_description() {
+    // act
+    let actual = MachineDescription::parse(SMALL_MACHINE).unwrap();
+
+    // assert
+    assert_eq!(actual.name, "small");
+    assert_eq!(actual.cpu, CpuVariant::Mos6502);
+    assert_eq!(actual.clock_hz, 1_000_000);
+    assert_eq!(
+        actual.rams,
+        vec![RamRegion {
+            start: 0,
+            size: 0x200,
+            wait_states: 0,
+            on_uninitialized: UninitializedRam::Allow
+        }]
+    );
+    assert_eq!(actual.roms[0].on_write, RomWrites::Error);
+    assert_eq!(actual.devices[0].kind, DeviceKind::Mc6821);
+    assert_eq!(actual.devices[0].decode_mask, None);
+}
+
+#[test]
+fn builds_machine_from_description() {
+    // arrange
+    let description = MachineDescription::parse(SMALL_MACHINE).unwrap();
+
+    // act
+    let mut machine = Machine::build_with(description, monitor_image).unwrap();
+
+    // assert
+    assert_eq!(machine.bus.peek(0xFF00).unwrap(), 0xEA);
+    assert_eq!(machine.bus.read(0xFF00).unwrap(), 0xEA);
+    assert_eq!(machine.bus.take_wait_cycles(), 1);
+    assert!(machine.bus.write(0xFF00, 0x00).is_err());
+    assert!(machine.bus.write(0x01FF, 0x00).is_ok());
+    assert!(machine.bus.read(0x0200).is_err());
+    assert!(machine.bus.read(0xD013).is_ok());
+    assert!(machine.bus.read(0xD014).is_err());
+    assert!(machine.take_pia_channels("pia").is_some());
+    assert!(machine.take_pia_channels("pia").is_none());
+}
+
+#[test]
+fn unmapped_policy_from_description() {
+    // arrange
+    let open_bus = format!("unmapped = \"open_bus\"\n{}", SMALL_MACHINE);
+    let description = MachineDescription::parse(&open_bus).unwrap();
+
+    // act
+    let mut machine = Machine::build_with(description, monitor_image).unwrap();
+    let mut strict = Machine::build_with(
+        MachineDescription::parse(SMALL_MACHINE).unwrap(),
+        monitor_image,
+    )
+    .unwrap();
+
+    // assert
+    assert_eq!(machine.description.unmapped, Unmapped::OpenBus);
+    assert!(machine.bus.read(0x0200).is_ok());
+    assert!(machine.bus.write(0x0200, 0x01).is_ok());
+    assert!(strict.bus.read(0x0200).is_err());
+}
+
+#[test]
+fn warns_about_roms_not_in_catalogue() {
+    // arrange
+    let description = MachineDescription::parse(SMALL_MACHINE).unwrap();
+
+    // act
+    let machine = Machine::build_with(description, monitor_image).unwrap();
+    let apple1 = Machine::load(machines_path("apple1.toml")).unwrap();
+
+    // assert: same size as the Woz monitor at $FF00, but different contents
+    assert_eq!(machine.warnings.len(), 1);
+    assert!(machine.warnings[0].starts_with("ROM monitor: corrupt image of Woz Monitor"));
+    assert!(apple1.warnings.is_empty());
+}
+
+#[test]
+fn loads_apple1_description_and_runs_monitor() {
+    // arrange
+    let mut machine = Machine::load(machines_path("apple1.toml")).unwrap();
+    let pia = machine.take_pia_channels("pia").unwrap();
+
+    // act
+    let mut cpu = machine.cpu();
+    cpu.reset();
+    for _ in 0..10_000 {
+        cpu.cycle(false);
+    }
+
+    // assert: the monitor printed its prompt
+    assert_eq!(pia.output_b.try_iter().next(), Some(b'\\'));
+}
+
+#[test]
+fn apple1_keyboard_types_into_monitor() {
+    // arrange
+    let mut machine = Machine::load(machines_path("apple1.toml")).unwrap();
+    let pia = machine.take_pia_channels("pia").unwrap();
+    let keys = pia.keyboard.unwrap();
+    pia.fast_display.unwrap().set(true);
+
+    // act
+    keys.type_text(b"0:aa bb\n");
+    let mut cpu = machine.cpu();
+    cpu.reset();
+    for _ in 0..50_000 {
+        cpu.cycle(false);
+    }
+
+    // assert
+    assert_eq!(
+        (cpu.peek(0x0000).unwrap(), cpu.peek(0x0001).unwrap()),
+        (0xAA, 0xBB)
+    );
+    assert_eq!(keys.progress(), (8, 8));
+}
+
+#[test]
+fn rejects_unknown_fields_and_cpus() {
+    // act
+    let unknown_field = MachineDescription::parse("name = \"x\"\nspeed = 1\n");
+    let unknown_cpu = MachineDescription::parse("name = \"x\"\ncpu = \"65816\"\n");
+
+    // assert
+    assert!(matches!(unknown_field, Err(MachineError::Parse(_))));
+    assert!(matches!(unknown_cpu, Err(MachineError::Parse(_))));
+}
+
+#[test]
+fn rejects_invalid_descriptions() {
+    // arrange
+    let duplicate_devices = "name = \"x\"\n\
+        [[device]]\nname = \"pia\"\ntype = \"mc6821\"\nbase = 0xD010\n\
+        [[device]]\nname = \"pia\"\ntype = \"mc6821\"\nbase = 0xD020\n";
+    let ram_too_large = "name = \"x\"\n[[ram]]\nstart = 0xF000\nsize = 0x2000\n";
+
+    // act & assert
+    assert!(matches!(
+        MachineDescription::parse(duplicate_devices),
+        Err(MachineError::Invalid(_))
+    ));
+    assert!(matches!(
+        MachineDescription::parse(ram_too_large),
+        Err(MachineError::Invalid(_))
+    ));
+}
+
+#[test]
+fn reports_overlapping_components() {
+    // arrange
+    let description = MachineDescription::parse(
+        "name = \"x\"\n[[ram]]\nstart = 0x0000\nsize = 0x10000\n\
+         [[rom]]\nstart = 0xFF00\nimage = \"monitor\"\n",
+    )
+    .unwrap();
+
+    // act
+    let actual = Machine::build_with(description, monitor_image);
+
+    // assert
+    assert!(matches!(
+        actual,
+        Err(MachineError::Mapping {
+            error: MappingError::Overlap { .. },
+            ..
+        })
+    ));
+}
+
+#[test]
+fn reports_missing_rom_image() {
+    // arrange
+    let path = std::env::temp_dir().join("rust6502-missing-rom.toml");
+    fs::write(
+        &path,
+        "name = \"x\"\n[[rom]]\nstart = 0xFF00\nimage = \"does-not-exist.bin\"\n",
+    )
+    .unwrap();
+
+    // act
+    let actual = Machine::load(&path);
+
+    // assert
+    assert!(matches!(actual, Err(MachineError::Io { .. })));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn connects_device_interrupts_to_cpu() {
+    // arrange
+    let description = MachineDescription::parse(&format!("{}irq = true\n", SMALL_MACHINE)).unwrap();
+    let mut machine = Machine::build_with(description, monitor_image).unwrap();
+    let pia = machine.take_pia_channels("pia").unwrap();
+    machine.bus.write(0xD011, 0x05).unwrap(); // IRQ on negative CA1 transition
+
+    // act
+    pia.input.send(InputSignal::CA1(Signal::Fall)).unwrap();
+    machine.bus.read(0xD011).unwrap();
+    let active = machine.irq.is_active();
+    machine.bus.read(0xD010).unwrap();
+
+    // assert
+    assert!(active);
+    assert!(!machine.irq.is_active());
+}
+
+#[test]
+fn builds_via_clocked_by_cpu() {
+    // arrange
+    let description = MachineDescription::parse(
+        "name = \"x\"\n[[ram]]\nstart = 0x0000\nsize = 0x1000\n\
+         [[rom]]\nstart = 0xFF00\nimage = \"monitor\"\n\
+         [[device]]\nname = \"via\"\ntype = \"mos6522\"\nbase = 0x6000\nirq = true\n",
+    )
+    .unwrap();
+    let mut machine = Machine::build_with(description, monitor_image).unwrap();
+    machine.bus.write(0x600E, 0xC0).unwrap(); // enable T1 interrupt
+    machine.bus.write(0x6004, 0x20).unwrap();
+    machine.bus.write(0x6005, 0x00).unwrap(); // start T1
+
+    // act
+    let irq = machine.irq.clone();
+    let mut cpu = machine.cpu();
+    cpu.reset(); // masks the interrupt
+    for _ in 0..0x21 {
+        cpu.cycle(false);
+    }
+
+    // assert
+    assert!(irq.is_active());
+    assert!(machine.take_pia_channels("via").is_some());
+}
+
+#[test]
+fn builds_acia_with_serial_channels() {
+    // arrange
+    let description = MachineDescription::parse(
+        "name = \"x\"\n[[ram]]\nstart = 0x0000\nsize = 0x1000\n\
+         [[rom]]\nstart = 0xFF00\nimage = \"monitor\"\n\
+         [[device]]\nname = \"acia\"\ntype = \"mos6551\"\nbase = 0x8000\n",
+    )
+    .unwrap();
+    let mut machine = Machine::build_with(description, monitor_image).unwrap();
+    let serial = machine.take_serial_channels("acia").unwrap();
+    machine.bus.write(0x8003, 0x1F).unwrap(); // 19200 baud
+    machine.bus.write(0x8002, 0x0B).unwrap(); // DTR, transmitter on
+
+    // act
+    machine.bus.write(0x8000, b'!').unwrap();
+    serial.input.send(b'?').unwrap();
+    for _ in 0..600 {
+        machine.bus.tick();
+    }
+
+    // assert
+    assert_eq!(serial.output.try_recv(), Ok(b'!'));
+    assert_eq!(machine.bus.read(0x8000).unwrap(), b'?');
+    assert!(machine.take_pia_channels("acia").is_none());
+}
+
+#[test]
+fn reports_where_tcp_serial_listens() {
+    // arrange
+    let description = MachineDescription::parse(
+        "name = \"x\"\n[[device]]\nname = \"acia\"\ntype = \"mos6551\"\nbase = 0x8000\n\
+         serial = \"tcp:127.0.0.1:0\"\n",
+    )
+    .unwrap();
+
+    // act
+    let machine = Machine::build_with(description, monitor_image).unwrap();
+
+    // assert: the port the system picked
+    let endpoints = machine.serial_endpoints();
+    assert_eq!(endpoints.len(), 1);
+    assert_eq!(endpoints[0].0, "acia");
+    assert!(endpoints[0].1.starts_with("127.0.0.1:"));
+    assert!(!endpoints[0].1.ends_with(":0"));
+}
+
+#[test]
+fn parses_serial_connections() {
+    // arrange
+    let acia = "name = \"x\"\n[[device]]\nname = \"acia\"\ntype = \"mos6551\"\nbase = 0x8000\n";
+    let pia = "name = \"x\"\n[[device]]\nname = \"pia\"\ntype = \"mc6821\"\nbase = 0xD010\n";
+
+    // act
+    let tcp = MachineDescription::parse(&format!("{}serial = \"tcp:127.0.0.1:6551\"\n", acia));
+    let unknown = MachineDescription::parse(&format!("{}serial = \"modem\"\n", acia));
+    let not_serial = MachineDescription::parse(&format!("{}serial = \"stdio\"\n", pia));
+
+    // assert
+    assert_eq!(
+        tcp.unwrap().devices[0].serial,
+        Some(SerialConnection::Tcp("127.0.0.1:6551".to_string()))
+    );
+    assert!(matches!(unknown, Err(MachineError::Parse(_))));
+    assert!(matches!(not_serial, Err(MachineError::Invalid(_))));
+}
+
+#[test]
+fn composes_mc6850_alongside_mc6821() {
+    // arrange
+    let description = MachineDescription::parse(&format!(
+        "{}\n[[device]]\nname = \"acia\"\ntype = \"mc6850\"\nbase = 0xF000\n\
+         serial_clock_hz = 153600\nirq = true\n",
+        SMALL_MACHINE
+    ))
+    .unwrap();
+    let mut machine = Machine::build_with(description, monitor_image).unwrap();
+    let serial = machine.take_serial_channels("acia").unwrap();
+    machine.bus.write(0xF000, 0x03).unwrap(); // master reset
+    machine.bus.write(0xF000, 0x95).unwrap(); // receive interrupt, 9600 baud 8N1
+
+    // act
+    serial.input.send(b'R').unwrap();
+    machine.bus.tick();
+    let irq = machine.irq.is_active();
+    let data = machine.bus.read(0xF001).unwrap();
+
+    // assert
+    assert!(irq);
+    assert_eq!(data, b'R');
+    assert!(!machine.irq.is_active());
+    assert!(machine.bus.read(0xF002).is_err());
+    assert!(machine.take_pia_channels("pia").is_some());
+}
+
+#[test]
+fn maps_riot_ram_and_io_separately() {
+    // arrange
+    let description = MachineDescription::parse(
+        "name = \"x\"\n[[rom]]\nstart = 0xFF00\nimage = \"monitor\"\n\
+         [[device]]\nname = \"riot\"\ntype = \"mos6532\"\nbase = 0x0280\nram_base = 0x0080\n\
+         irq = true\n",
+    )
+    .unwrap();
+    let mut machine = Machine::build_with(description, monitor_image).unwrap();
+
+    // act
+    machine.bus.write(0x00FF, 0x42).unwrap();
+    machine.bus.write(0x029C, 0x00).unwrap(); // timer, divide by 1, interrupt enabled
+    for _ in 0..2 {
+        machine.bus.tick();
+    }
+
+    // assert
+    assert_eq!(machine.bus.read(0x00FF).unwrap(), 0x42);
+    assert!(machine.bus.read(0x0100).is_err());
+    assert!(machine.irq.is_active());
+    assert!(machine.take_pia_channels("riot").is_some());
+    assert!(matches!(
+        MachineDescription::parse(
+            "name = \"x\"\n[[device]]\nname = \"pia\"\ntype = \"mc6821\"\nbase = 0xD010\n\
+             ram_base = 0x0080\n"
+        ),
+        Err(MachineError::Invalid(_))
+    ));
+}
+
+#[test]
+fn maps_aci_with_prom_and_cassette() {
+    // arrange
+    let description = MachineDescription::parse(
+        "name = \"x\"\n[[rom]]\nstart = 0xFF00\nimage = \"monitor\"\n\
+         [[device]]\nname = \"aci\"\ntype = \"aci\"\nbase = 0xC000\nimage = \"aci\"\n",
+    )
+    .unwrap();
+    let mut machine = Machine::build_with(description, |image| match image {
+        "aci" => Ok((0..=0xFF).collect()),
+        _ => monitor_image(image),
+    })
+    .unwrap();
+    let cassette = machine.take_cassette("aci").unwrap();
+    cassette.record(1000);
+
+    // act
+    let tape_in = machine.bus.read(0xC081).unwrap();
+    machine.bus.read(0xC000).unwrap(); // TAPEOUT
+    for _ in 0..1000 {
+        machine.bus.tick();
+    }
+    let recording = cassette.take_recording(0);
+
+    // assert
+    assert_eq!(machine.bus.read(0xC1A5).unwrap(), 0xA5);
+    assert_eq!(tape_in, 0x80); // no tape: the input stays low
+    assert_eq!(recording.map(|wav| wav.samples.len()), Some(1));
+    assert!(machine.bus.read(0xC200).is_err());
+    assert!(machine.take_cassette("aci").is_none());
+}
+
+#[test]
+fn rejects_aci_without_prom_or_aligned_base() {
+    // arrange
+    let aci = "name = \"x\"\n[[device]]\nname = \"aci\"\ntype = \"aci\"\n";
+
+    // act & assert
+    assert!(matches!(
+        MachineDescription::parse(&format!("{}base = 0xC000\n", aci)),
+        Err(MachineError::Invalid(_))
+    ));
+    assert!(matches!(
+        MachineDescription::parse(&format!("{}base = 0xC100\nimage = \"aci\"\n", aci)),
+        Err(MachineError::Invalid(_))
+    ));
+    assert!(matches!(
+        MachineDescription::parse(
+            "name = \"x\"\n[[device]]\nname = \"pia\"\ntype = \"mc6821\"\nbase = 0xD010\n\
+             image = \"aci\"\n"
+        ),
+        Err(MachineError::Invalid(_))
+    ));
+    assert!(matches!(
+        Machine::build_with(
+            MachineDescription::parse(&format!("{}base = 0xC000\nimage = \"aci\"\n", aci)).unwrap(),
+            |_| Ok(vec![0; 0x800])
+        )
+        .map(|_| ()),
+        Err(MachineError::Invalid(_))
+    ));
+}
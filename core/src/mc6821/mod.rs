@@ -1,7 +1,8 @@
 #[cfg(test)]
 mod tests;
 
-use crate::address_bus::InternalAddressing;
+use crate::address_bus::{AddressingError, InternalAddressing};
+use crate::interrupt::{InterruptLine, InterruptOutput};
 use crossbeam_channel::*;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -27,6 +28,80 @@ pub enum InputSignal {
     CB2(Signal),
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Port {
+    A,
+    B,
+}
+
+/// A device on a PIA port, called synchronously by the PIA.
+pub trait PiaPeripheral {
+    /// Next change of the input lines, called until `None` before every register read;
+    /// usually the port's data lines (`IRA`/`IRB`) and its control lines.
+    fn poll(&mut self) -> Option<InputSignal> {
+        None
+    }
+
+    /// The CPU read the port's data register.
+    fn port_read(&mut self, _data: u8) {}
+
+    /// The CPU wrote the port's output register, or its output changed otherwise (PB7 of a
+    /// 6522); `data` mixes output and input bits.
+    fn port_written(&mut self, _data: u8) {}
+
+    /// CA2/CB2 changed while configured as output.
+    fn control_output(&mut self, _signal: Signal) {}
+
+    /// CB1 changed as shift clock output of a 6522.
+    fn clock_output(&mut self, _signal: Signal) {}
+
+    /// One clock cycle passed; for devices with timing of their own.
+    fn tick(&mut self) {}
+}
+
+/// Input signals from a channel and port output into a channel, e.g. for a terminal thread.
+pub struct ChannelPeripheral {
+    input: Option<Receiver<InputSignal>>,
+    output: Option<Sender<u8>>,
+}
+
+impl ChannelPeripheral {
+    pub fn new(input: Option<Receiver<InputSignal>>, output: Option<Sender<u8>>) -> Self {
+        ChannelPeripheral { input, output }
+    }
+}
+
+impl PiaPeripheral for ChannelPeripheral {
+    fn poll(&mut self) -> Option<InputSignal> {
+        self.input.as_ref()?.try_recv().ok()
+    }
+
+    fn port_written(&mut self, data: u8) {
+        if let Some(tx) = &self.output {
+            tx.send(data).unwrap();
+        }
+    }
+}
+
+// CA2/CB2 in pulse or handshake mode, advanced by `tick`
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum Strobe {
+    Idle,
+    FallNext, // port accessed, goes low on the next cycle
+    RiseNext, // pulse mode, back high on the next cycle
+}
+
+impl Strobe {
+    // inverse of `as u8` for `load_state`
+    pub(crate) fn from_state(b: u8) -> Strobe {
+        match b {
+            1 => Strobe::FallNext,
+            2 => Strobe::RiseNext,
+            _ => Strobe::Idle,
+        }
+    }
+}
+
 pub struct MC6821 {
     ora: u8,      // Output register A
     ira: u8,      // Input register A
@@ -34,6 +109,7 @@ pub struct MC6821 {
     ddra_neg: u8, // negative data direction register A    (Output=0, Input=1)
     ca1: Signal,  // control line A1
     ca2: Signal,  // control line A2
+    ca2_strobe: Strobe,
 
     cra: u8, // control register A
     cra_bit_0_enable_irq_a1: bool,
@@ -52,6 +128,7 @@ pub struct MC6821 {
     ddrb_neg: u8, // negative data direction register B    (Output=0, Input=1)
     cb1: Signal,  // control line B1
     cb2: Signal,  // control line B2
+    cb2_strobe: Strobe,
 
     crb: u8, // control register B
     crb_bit_0_enable_irq_b1: bool,
@@ -64,12 +141,18 @@ pub struct MC6821 {
     crb_bit_4_manual_output: bool,
     crb_bit_5_output_mode: bool,
 
-    input_channel: Option<Receiver<InputSignal>>,
-    output_channel_a: Option<Sender<u8>>,
-    output_channel_b: Option<Sender<u8>>,
+    peripherals: Vec<(Port, Box<dyn PiaPeripheral>)>,
+    irqa: InterruptOutput,
+    irqb: InterruptOutput,
     interrupt_channel: Option<Sender<InterruptSignal>>,
 }
 
+impl Default for MC6821 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl MC6821 {
     pub fn new() -> MC6821 {
         MC6821 {
@@ -80,6 +163,7 @@ impl MC6821 {
             ddra_neg: 0xFF,
             ca1: Signal::Rise,
             ca2: Signal::Rise,
+            ca2_strobe: Strobe::Idle,
 
             cra: 0,
             cra_bit_0_enable_irq_a1: false,
@@ -97,6 +181,7 @@ impl MC6821 {
             ddrb_neg: 0xFF,
             cb1: Signal::Fall,
             cb2: Signal::Fall,
+            cb2_strobe: Strobe::Idle,
 
             crb: 0,
             crb_bit_0_enable_irq_b1: false,
@@ -109,9 +194,9 @@ impl MC6821 {
             crb_bit_4_manual_output: false,
             crb_bit_5_output_mode: false,
 
-            input_channel: None,
-            output_channel_a: None,
-            output_channel_b: None,
+            peripherals: vec![],
+            irqa: InterruptOutput::default(),
+            irqb: InterruptOutput::default(),
             interrupt_channel: None,
         }
     }
@@ -132,6 +217,7 @@ impl MC6821 {
         if self.cra_bit_5_output_mode {
             self.cra_bit_4_manual_output = (self.cra & 0x10) == 0x10;
             if self.cra_bit_4_manual_output {
+                self.ca2_strobe = Strobe::Idle;
                 self.cra_bit_3_ca2_set_high = (self.cra & 0x08) == 0x08;
                 self.ca2 = if self.cra_bit_3_ca2_set_high {
                     Signal::Rise
@@ -142,6 +228,7 @@ impl MC6821 {
                 self.cra_bit_3_pulse_output = (self.cra & 0x08) == 0x08;
             }
         } else {
+            self.ca2_strobe = Strobe::Idle;
             self.cra_bit_3_enable_irq_a2 = (self.cra & 0x08) == 0x08;
             self.cra_bit_4_ca2_positive_trans = (self.cra & 0x10) == 0x10;
         }
@@ -161,6 +248,7 @@ impl MC6821 {
         if self.crb_bit_5_output_mode {
             self.crb_bit_4_manual_output = (self.crb & 0x10) == 0x10;
             if self.crb_bit_4_manual_output {
+                self.cb2_strobe = Strobe::Idle;
                 self.crb_bit_3_cb2_set_high = (self.crb & 0x08) == 0x08;
                 self.cb2 = if self.crb_bit_3_cb2_set_high {
                     Signal::Rise
@@ -171,55 +259,136 @@ impl MC6821 {
                 self.crb_bit_3_pulse_output = (self.crb & 0x08) == 0x08;
             }
         } else {
+            self.cb2_strobe = Strobe::Idle;
             self.crb_bit_3_enable_irq_b2 = (self.crb & 0x08) == 0x08;
             self.crb_bit_4_cb2_positive_trans = (self.crb & 0x10) == 0x10;
         }
     }
 
-    fn update_irq(&self) {
-        if (self.cra_bit_0_enable_irq_a1 && (self.cra & 0x80) == 0x80)
-            || (self.cra_bit_3_enable_irq_a2 && (self.cra & 0x40) == 0x40)
-            || (self.crb_bit_0_enable_irq_b1 && (self.crb & 0x80) == 0x80)
-            || (self.crb_bit_3_enable_irq_b2 && (self.crb & 0x40) == 0x40)
-        {
-            match &self.interrupt_channel {
-                Some(tx) => tx.send(InterruptSignal::IRQ).unwrap(),
-                None => (),
+    // drive IRQA/IRQB from the flags and their enables
+    fn update_irq(&mut self) {
+        let was_active = self.irqa.is_active() || self.irqb.is_active();
+
+        self.irqa.set(
+            (self.cra_bit_0_enable_irq_a1 && (self.cra & 0x80) == 0x80)
+                || (self.cra_bit_3_enable_irq_a2 && (self.cra & 0x40) == 0x40),
+        );
+        self.irqb.set(
+            (self.crb_bit_0_enable_irq_b1 && (self.crb & 0x80) == 0x80)
+                || (self.crb_bit_3_enable_irq_b2 && (self.crb & 0x40) == 0x40),
+        );
+
+        let active = self.irqa.is_active() || self.irqb.is_active();
+        if active != was_active {
+            if let Some(tx) = &self.interrupt_channel {
+                tx.send(if active {
+                    InterruptSignal::IRQ
+                } else {
+                    InterruptSignal::NoSignal
+                })
+                .unwrap()
             }
         }
     }
 
+    /// Level of the IRQA output.
+    pub fn irqa(&self) -> bool {
+        self.irqa.is_active()
+    }
+
+    /// Level of the IRQB output.
+    pub fn irqb(&self) -> bool {
+        self.irqb.is_active()
+    }
+
+    pub fn connect_irqa(&mut self, line: &InterruptLine) {
+        self.irqa.connect(line);
+    }
+
+    pub fn connect_irqb(&mut self, line: &InterruptLine) {
+        self.irqb.connect(line);
+    }
+
+    /// Attach a device to a port; several devices can share a port.
+    pub fn attach_peripheral(&mut self, port: Port, peripheral: Box<dyn PiaPeripheral>) {
+        self.peripherals.push((port, peripheral));
+    }
+
+    pub fn detach_peripherals(&mut self) -> Vec<(Port, Box<dyn PiaPeripheral>)> {
+        std::mem::take(&mut self.peripherals)
+    }
+
     pub fn set_input_channel(&mut self, rx: Receiver<InputSignal>) {
-        self.input_channel = Some(rx);
+        self.attach_peripheral(Port::A, Box::new(ChannelPeripheral::new(Some(rx), None)));
     }
 
+    /// Take pending input line changes from all peripherals.
     pub fn process_input(&mut self) {
-        loop {
-            match &self.input_channel {
-                Some(rx) => match rx.try_recv() {
-                    Ok(input) => match input {
-                        InputSignal::IRA(b) => self.ira = b,
-                        InputSignal::IRB(b) => self.irb = b,
-                        InputSignal::CA1(s) => self.set_ca1(s),
-                        InputSignal::CA2(s) => self.set_ca2(s),
-                        InputSignal::CB1(s) => self.set_cb1(s),
-                        InputSignal::CB2(s) => self.set_cb2(s),
-                    },
-                    Err(_) => break,
-                },
-                None => (),
+        for i in 0..self.peripherals.len() {
+            while let Some(input) = self.peripherals[i].1.poll() {
+                match input {
+                    InputSignal::IRA(b) => self.ira = b,
+                    InputSignal::IRB(b) => self.irb = b,
+                    InputSignal::CA1(s) => self.set_ca1(s),
+                    InputSignal::CA2(s) => self.set_ca2(s),
+                    InputSignal::CB1(s) => self.set_cb1(s),
+                    InputSignal::CB2(s) => self.set_cb2(s),
+                }
+            }
+        }
+    }
+
+    fn notify(&mut self, port: Port, mut f: impl FnMut(&mut dyn PiaPeripheral)) {
+        for (_, peripheral) in self.peripherals.iter_mut().filter(|(p, _)| *p == port) {
+            f(peripheral.as_mut());
+        }
+    }
+
+    fn notify_c2(&mut self, port: Port, before: Signal) {
+        let after = match port {
+            Port::A => self.ca2,
+            Port::B => self.cb2,
+        };
+        if after != before {
+            self.notify(port, |peripheral| peripheral.control_output(after));
+        }
+    }
+
+    // one step of the CA2/CB2 strobe: low after the access, high again one cycle later in
+    // pulse mode or on the next active C1 transition in handshake mode
+    fn clock_strobe(&mut self, port: Port) {
+        let (strobe, pulse, line) = match port {
+            Port::A => (self.ca2_strobe, self.cra_bit_3_pulse_output, self.ca2),
+            Port::B => (self.cb2_strobe, self.crb_bit_3_pulse_output, self.cb2),
+        };
+        let (next, signal) = match strobe {
+            Strobe::Idle => return,
+            Strobe::FallNext if pulse => (Strobe::RiseNext, Signal::Fall),
+            Strobe::FallNext => (Strobe::Idle, Signal::Fall),
+            Strobe::RiseNext => (Strobe::Idle, Signal::Rise),
+        };
+        match port {
+            Port::A => {
+                self.ca2_strobe = next;
+                self.ca2 = signal;
+            }
+            Port::B => {
+                self.cb2_strobe = next;
+                self.cb2 = signal;
             }
         }
+        self.notify_c2(port, line);
     }
 
     pub fn set_output_channel_a(&mut self, tx: Sender<u8>) {
-        self.output_channel_a = Some(tx);
+        self.attach_peripheral(Port::A, Box::new(ChannelPeripheral::new(None, Some(tx))));
     }
 
     pub fn set_output_channel_b(&mut self, tx: Sender<u8>) {
-        self.output_channel_b = Some(tx);
+        self.attach_peripheral(Port::B, Box::new(ChannelPeripheral::new(None, Some(tx))));
     }
 
+    /// Receives `IRQ` when IRQA or IRQB becomes active and `NoSignal` when both are released.
     pub fn set_interrupt_channel(&mut self, tx: Sender<InterruptSignal>) {
         self.interrupt_channel = Some(tx);
     }
@@ -239,8 +408,11 @@ impl MC6821 {
                 && !self.cra_bit_4_manual_output
                 && !self.cra_bit_3_pulse_output
             {
-                // handshake mode
+                // handshake mode: the peripheral answered
+                let before = self.ca2;
                 self.ca2 = Signal::Rise;
+                self.ca2_strobe = Strobe::Idle;
+                self.notify_c2(Port::A, before);
             }
         }
         self.ca1 = s;
@@ -284,8 +456,11 @@ impl MC6821 {
                 && !self.crb_bit_4_manual_output
                 && !self.crb_bit_3_pulse_output
             {
-                // handshake mode
+                // handshake mode: the peripheral answered
+                let before = self.cb2;
                 self.cb2 = Signal::Rise;
+                self.cb2_strobe = Strobe::Idle;
+                self.notify_c2(Port::B, before);
             }
         }
         self.cb1 = s;
@@ -319,14 +494,39 @@ impl InternalAddressing for MC6821 {
     fn int_read(&mut self, addr: u16) -> u8 {
         self.process_input();
 
+        let data = self.int_peek(addr);
+
+        match addr & 0x03 {
+            0 => {
+                self.cra &= 0x3F; // IRQ flags implicitly cleared by a read of PA
+                if self.cra_bit_2_write_port {
+                    if self.cra_bit_5_output_mode && !self.cra_bit_4_manual_output {
+                        self.ca2_strobe = Strobe::FallNext; // read strobe
+                    }
+                    self.notify(Port::A, |peripheral| peripheral.port_read(data));
+                }
+            }
+            2 => {
+                self.crb &= 0x3F; // IRQ flags implicitly cleared by a read of PB
+                if self.crb_bit_2_write_port {
+                    self.notify(Port::B, |peripheral| peripheral.port_read(data));
+                }
+            }
+            _ => (),
+        }
+        self.update_irq();
+
+        data
+    }
+
+    // register contents as a read would return them, without consuming pending input
+    fn int_peek(&self, addr: u16) -> u8 {
         let reg = (addr & 0x03) as u8;
         let mut data = 0u8;
 
         match reg {
             // PA
             0 => {
-                self.cra &= 0x3F; // IRQ flags implicitly cleared by a read
-
                 // mix input and output
                 data |= self.ora & self.ddra;
                 data |= self.ira & self.ddra_neg;
@@ -339,8 +539,6 @@ impl InternalAddressing for MC6821 {
 
             // PB
             2 => {
-                self.crb &= 0x3F; // IRQ flags implicitly cleared by a read
-
                 // mix input and output
                 data |= self.orb & self.ddrb;
                 data |= self.irb & self.ddrb_neg;
@@ -357,6 +555,30 @@ impl InternalAddressing for MC6821 {
         data
     }
 
+    // set registers without output, handshake or interrupt signalling
+    fn int_poke(&mut self, addr: u16, data: u8) {
+        match addr & 0x03 {
+            0 if self.cra_bit_2_write_port => self.ora = data,
+            0 => {
+                self.ddra = data;
+                self.ddra_neg = !data;
+            }
+            1 => {
+                self.cra = (self.cra & 0xC0) | (data & 0x3F);
+                self.update_control_registers();
+            }
+            2 if self.crb_bit_2_write_port => self.orb = data,
+            2 => {
+                self.ddrb = data;
+                self.ddrb_neg = !data;
+            }
+            _ => {
+                self.crb = (self.crb & 0xC0) | (data & 0x3F);
+                self.update_control_registers();
+            }
+        }
+    }
+
     fn int_write(&mut self, addr: u16, data: u8) {
         let reg = (addr & 0x03) as u8;
 
@@ -365,16 +587,12 @@ impl InternalAddressing for MC6821 {
             0 => {
                 if self.cra_bit_2_write_port {
                     self.ora = data; // into output register A
-                    match &self.output_channel_a {
-                        Some(tx) => {
-                            // mix input and output
-                            let mut out = 0u8;
-                            out |= self.ora & self.ddra;
-                            out |= self.ira & self.ddra_neg;
-                            tx.send(out).unwrap();
-                        }
-                        None => (),
-                    }
+
+                    // mix input and output
+                    let mut out = 0u8;
+                    out |= self.ora & self.ddra;
+                    out |= self.ira & self.ddra_neg;
+                    self.notify(Port::A, |peripheral| peripheral.port_written(out));
                 } else {
                     self.ddra = data; // into data direction register A
                     self.ddra_neg = !data;
@@ -383,8 +601,10 @@ impl InternalAddressing for MC6821 {
 
             // CRA
             1 => {
+                let before = self.ca2;
                 self.cra = (self.cra & 0xC0) | (data & 0x3F); // do not change IRQ flags
                 self.update_control_registers();
+                self.notify_c2(Port::A, before);
                 self.update_irq();
             }
 
@@ -392,24 +612,15 @@ impl InternalAddressing for MC6821 {
             2 => {
                 if self.crb_bit_2_write_port {
                     self.orb = data; // into output register B
-                    match &self.output_channel_b {
-                        Some(tx) => {
-                            // mix input and output
-                            let mut out = 0u8;
-                            out |= self.orb & self.ddrb;
-                            out |= self.irb & self.ddrb_neg;
-                            tx.send(out).unwrap();
-
-                            if self.crb_bit_5_output_mode && !self.crb_bit_4_manual_output
-                            // handshake on write mode
-                            {
-                                self.cb2 = Signal::Fall;
-                                if self.crb_bit_3_pulse_output {
-                                    self.cb2 = Signal::Rise
-                                };
-                            }
-                        }
-                        None => (),
+
+                    // mix input and output
+                    let mut out = 0u8;
+                    out |= self.orb & self.ddrb;
+                    out |= self.irb & self.ddrb_neg;
+                    self.notify(Port::B, |peripheral| peripheral.port_written(out));
+
+                    if self.crb_bit_5_output_mode && !self.crb_bit_4_manual_output {
+                        self.cb2_strobe = Strobe::FallNext; // write strobe
                     }
                 } else {
                     self.ddrb = data; // into data direction register B
@@ -419,8 +630,10 @@ impl InternalAddressing for MC6821 {
 
             // CRB
             3 => {
+                let before = self.cb2;
                 self.crb = (self.crb & 0xC0) | (data & 0x3F); // do not change IRQ flags
                 self.update_control_registers();
+                self.notify_c2(Port::B, before);
                 self.update_irq();
             }
 
@@ -431,4 +644,62 @@ impl InternalAddressing for MC6821 {
     fn len(&self) -> usize {
         0
     }
+
+    fn tick(&mut self) {
+        for (_, peripheral) in self.peripherals.iter_mut() {
+            peripheral.tick();
+        }
+        self.clock_strobe(Port::A);
+        self.clock_strobe(Port::B);
+    }
+
+    fn describe(&self) -> String {
+        "MC6821 PIA".to_string()
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        vec![
+            self.ora,
+            self.ira,
+            self.ddra,
+            self.ca1 as u8,
+            self.ca2 as u8,
+            self.cra,
+            self.orb,
+            self.irb,
+            self.ddrb,
+            self.cb1 as u8,
+            self.cb2 as u8,
+            self.crb,
+            self.ca2_strobe as u8,
+            self.cb2_strobe as u8,
+        ]
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), AddressingError> {
+        if state.len() != 14 {
+            return Err(AddressingError::new("load_state", 0));
+        }
+
+        let signal = |b: u8| if b == 0 { Signal::Fall } else { Signal::Rise };
+        self.ora = state[0];
+        self.ira = state[1];
+        self.ddra = state[2];
+        self.ddra_neg = !state[2];
+        self.ca1 = signal(state[3]);
+        self.ca2 = signal(state[4]);
+        self.cra = state[5];
+        self.orb = state[6];
+        self.irb = state[7];
+        self.ddrb = state[8];
+        self.ddrb_neg = !state[8];
+        self.cb1 = signal(state[9]);
+        self.cb2 = signal(state[10]);
+        self.crb = state[11];
+        self.update_control_registers();
+        self.ca2_strobe = Strobe::from_state(state[12]);
+        self.cb2_strobe = Strobe::from_state(state[13]);
+        self.update_irq();
+        Ok(())
+    }
 }
@@ -0,0 +1,301 @@
+#[cfg(test)]
+mod tests;
+
+use crate::address_bus::{AddressingError, InternalAddressing};
+use crate::interrupt::{InterruptLine, InterruptOutput};
+use crate::serial::SerialBackend;
+
+// status register bits
+pub const STATUS_RDRF: u8 = 0x01; // receive data register full
+pub const STATUS_TDRE: u8 = 0x02; // transmit data register empty
+pub const STATUS_DCD: u8 = 0x04; // data carrier lost
+pub const STATUS_CTS: u8 = 0x08; // clear to send not asserted
+pub const STATUS_FRAMING_ERROR: u8 = 0x10;
+pub const STATUS_OVERRUN: u8 = 0x20;
+pub const STATUS_PARITY_ERROR: u8 = 0x40;
+pub const STATUS_IRQ: u8 = 0x80;
+
+/// 1.8432 MHz, giving 115200 baud with the clock divided by 16.
+pub const DEFAULT_SERIAL_CLOCK_HZ: u64 = 1_843_200;
+
+pub struct MC6850 {
+    rdr: u8,     // receive data register
+    tdr: u8,     // transmit data register
+    tsr: u8,     // transmit shift register
+    status: u8,  // status register without the IRQ bit and the CTS input
+    control: u8, // control register
+    master_reset: bool,
+
+    tsr_full: bool,
+    tx_countdown: u64, // cycles until the character in the shift register is sent
+    rx_countdown: u64, // cycles until the receiver takes the next character
+
+    cts: bool, // clear to send input asserted
+    dcd: bool, // data carrier detect input asserted
+
+    clock_hz: u64,
+    serial_clock_hz: u64,
+    backend: Box<dyn SerialBackend>,
+    irq: InterruptOutput,
+}
+
+impl MC6850 {
+    /// An ACIA driven by a CPU clock of `clock_hz`, transmitting to and receiving from `backend`;
+    /// like after power-on it does nothing until the first master reset.
+    pub fn new(clock_hz: u64, backend: Box<dyn SerialBackend>) -> MC6850 {
+        MC6850 {
+            rdr: 0,
+            tdr: 0,
+            tsr: 0,
+            status: 0,
+            control: 0,
+            master_reset: true,
+
+            tsr_full: false,
+            tx_countdown: 0,
+            rx_countdown: 0,
+
+            cts: true,
+            dcd: true,
+
+            clock_hz,
+            serial_clock_hz: DEFAULT_SERIAL_CLOCK_HZ,
+            backend,
+            irq: InterruptOutput::default(),
+        }
+    }
+
+    /// Clock on the transmit and receive clock inputs, before the counter divide.
+    pub fn set_serial_clock_hz(&mut self, hz: u64) {
+        self.serial_clock_hz = hz.max(1);
+    }
+
+    pub fn connect_irq(&mut self, line: &InterruptLine) {
+        self.irq.connect(line);
+    }
+
+    /// Level of the IRQ output.
+    pub fn irq(&self) -> bool {
+        self.irq.is_active()
+    }
+
+    /// RTS output asserted (low).
+    pub fn rts(&self) -> bool {
+        self.control & 0x60 != 0x40
+    }
+
+    /// CTS input; while it is not asserted TDRE reads 0 and the transmitter holds.
+    pub fn set_cts(&mut self, asserted: bool) {
+        self.cts = asserted;
+        self.update_irq();
+    }
+
+    /// DCD input; losing the carrier is latched in the status register and stops the receiver.
+    pub fn set_dcd(&mut self, asserted: bool) {
+        if self.dcd && !asserted {
+            self.status |= STATUS_DCD;
+        }
+        self.dcd = asserted;
+        self.update_irq();
+    }
+
+    /// CPU cycles one character takes on the line, start and stop bits included.
+    pub fn character_cycles(&self) -> u64 {
+        let divide = match self.control & 0x03 {
+            0x00 => 1,
+            0x01 => 16,
+            _ => 64,
+        };
+        let (data_bits, parity_bits, stop_bits) = match self.control >> 2 & 0x07 {
+            0 | 1 => (7, 1, 2),
+            2 | 3 => (7, 1, 1),
+            4 => (8, 0, 2),
+            5 => (8, 0, 1),
+            _ => (8, 1, 1),
+        };
+        let bits = 1 + data_bits + parity_bits + stop_bits;
+        let cycles = self.clock_hz as f64 * (bits * divide) as f64 / self.serial_clock_hz as f64;
+        cycles.round().max(1.0) as u64
+    }
+
+    fn data_mask(&self) -> u8 {
+        if self.control & 0x10 != 0 {
+            0xFF
+        } else {
+            0x7F
+        }
+    }
+
+    fn tx_irq_enabled(&self) -> bool {
+        self.control & 0x60 == 0x20
+    }
+
+    fn rx_irq_enabled(&self) -> bool {
+        self.control & 0x80 != 0
+    }
+
+    fn transmitting_break(&self) -> bool {
+        self.control & 0x60 == 0x60
+    }
+
+    fn status_register(&self) -> u8 {
+        let mut status = self.status;
+        if !self.cts {
+            status = (status | STATUS_CTS) & !STATUS_TDRE;
+        }
+        if self.irq.is_active() {
+            status |= STATUS_IRQ;
+        }
+        status
+    }
+
+    fn update_irq(&mut self) {
+        let rx =
+            self.rx_irq_enabled() && self.status & (STATUS_RDRF | STATUS_OVERRUN | STATUS_DCD) != 0;
+        let tx = self.tx_irq_enabled() && self.cts && self.status & STATUS_TDRE != 0;
+        self.irq.set(!self.master_reset && (rx || tx));
+    }
+
+    fn clock_transmitter(&mut self) {
+        if self.tsr_full {
+            self.tx_countdown = self.tx_countdown.saturating_sub(1);
+            if self.tx_countdown == 0 {
+                let data = self.tsr & self.data_mask();
+                self.backend.transmit(data);
+                self.tsr_full = false;
+            }
+        }
+
+        if !self.tsr_full
+            && self.status & STATUS_TDRE == 0
+            && self.cts
+            && !self.transmitting_break()
+        {
+            self.tsr = self.tdr;
+            self.tsr_full = true;
+            self.tx_countdown = self.character_cycles();
+            self.status |= STATUS_TDRE;
+            self.update_irq();
+        }
+    }
+
+    // the backend is asked once per character time, so it is not polled on every cycle
+    fn clock_receiver(&mut self) {
+        self.rx_countdown = self.rx_countdown.saturating_sub(1);
+        if self.rx_countdown > 0 || !self.dcd {
+            return;
+        }
+        self.rx_countdown = self.character_cycles();
+
+        if let Some(data) = self.backend.receive() {
+            if self.status & STATUS_RDRF != 0 {
+                self.status |= STATUS_OVERRUN; // the character in the RDR is kept
+            } else {
+                self.rdr = data & self.data_mask();
+                self.status |= STATUS_RDRF;
+            }
+            self.update_irq();
+        }
+    }
+}
+
+impl InternalAddressing for MC6850 {
+    fn int_read(&mut self, addr: u16) -> u8 {
+        let data = self.int_peek(addr);
+
+        if addr & 0x01 == 1 {
+            self.status &=
+                !(STATUS_RDRF | STATUS_OVERRUN | STATUS_FRAMING_ERROR | STATUS_PARITY_ERROR);
+            if self.dcd {
+                self.status &= !STATUS_DCD;
+            }
+            self.update_irq();
+        }
+
+        data
+    }
+
+    fn int_peek(&self, addr: u16) -> u8 {
+        if addr & 0x01 == 0 {
+            self.status_register()
+        } else {
+            self.rdr
+        }
+    }
+
+    fn int_poke(&mut self, addr: u16, data: u8) {
+        if addr & 0x01 == 0 {
+            self.control = data;
+        } else {
+            self.rdr = data;
+        }
+    }
+
+    fn int_write(&mut self, addr: u16, data: u8) {
+        if addr & 0x01 == 0 {
+            self.control = data;
+            if data & 0x03 == 0x03 {
+                self.master_reset = true;
+                self.status = 0;
+                self.tsr_full = false;
+            } else if self.master_reset {
+                self.master_reset = false;
+                self.status = STATUS_TDRE;
+                self.rx_countdown = 0;
+            }
+        } else {
+            self.tdr = data;
+            self.status &= !STATUS_TDRE;
+        }
+        self.update_irq();
+    }
+
+    fn len(&self) -> usize {
+        2
+    }
+
+    fn tick(&mut self) {
+        if !self.master_reset {
+            self.clock_transmitter();
+            self.clock_receiver();
+        }
+    }
+
+    fn describe(&self) -> String {
+        "MC6850 ACIA".to_string()
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        vec![
+            self.rdr,
+            self.tdr,
+            self.tsr,
+            self.status,
+            self.control,
+            self.master_reset as u8,
+            self.tsr_full as u8,
+        ]
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), AddressingError> {
+        if state.len() != 7 {
+            return Err(AddressingError::new("load_state", 0));
+        }
+
+        self.rdr = state[0];
+        self.tdr = state[1];
+        self.tsr = state[2];
+        self.status = state[3];
+        self.control = state[4];
+        self.master_reset = state[5] != 0;
+        self.tsr_full = state[6] != 0;
+        self.tx_countdown = if self.tsr_full {
+            self.character_cycles()
+        } else {
+            0
+        };
+        self.rx_countdown = 0;
+        self.update_irq();
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+#[cfg(test)]
+use super::*;
+use crate::serial::ChannelSerial;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+
+const CONTROL: u16 = 0x0;
+const STATUS: u16 = 0x0;
+const DATA: u16 = 0x1;
+
+fn acia() -> (MC6850, Sender<u8>, Receiver<u8>) {
+    let (tx_input, rx_input) = unbounded();
+    let (tx_output, rx_output) = unbounded();
+    let acia = MC6850::new(1_000_000, Box::new(ChannelSerial::new(rx_input, tx_output)));
+    (acia, tx_input, rx_output)
+}
+
+fn ticks(acia: &mut MC6850, n: u64) {
+    for _ in 0..n {
+        acia.tick();
+    }
+}
+
+#[test]
+fn idle_until_master_reset() {
+    // arrange
+    let (mut acia, input, output) = acia();
+    input.send(b'a').unwrap();
+
+    // act
+    acia.int_write(DATA, b'b');
+    ticks(&mut acia, 1000);
+    let status_before_reset = acia.int_peek(STATUS);
+    acia.int_write(CONTROL, 0x03); // master reset
+    acia.int_write(CONTROL, 0x15); // divide by 16, 8N1
+    let status_after_reset = acia.int_peek(STATUS);
+
+    // assert
+    assert_eq!(status_before_reset, 0);
+    assert!(output.try_recv().is_err());
+    assert_eq!(status_after_reset, STATUS_TDRE);
+}
+
+#[test]
+fn character_time_follows_divide_and_word_select() {
+    // arrange
+    let (mut acia, _, _) = acia();
+
+    // act
+    acia.int_write(CONTROL, 0x15); // divide by 16, 8N1
+    let divide_16 = acia.character_cycles();
+    acia.int_write(CONTROL, 0x0A); // divide by 64, 7E1
+    let divide_64 = acia.character_cycles();
+    acia.set_serial_clock_hz(9600);
+    acia.int_write(CONTROL, 0x10); // divide by 1, 8N2
+    let divide_1 = acia.character_cycles();
+
+    // assert
+    assert_eq!(divide_16, 87);
+    assert_eq!(divide_64, 347);
+    assert_eq!(divide_1, 1146);
+}
+
+#[test]
+fn transmits_after_character_time() {
+    // arrange
+    let (mut acia, _, output) = acia();
+    acia.int_write(CONTROL, 0x03);
+    acia.int_write(CONTROL, 0x05); // divide by 16, 7O2
+
+    // act
+    acia.int_write(DATA, 0xC1);
+    let status_after_write = acia.int_peek(STATUS);
+    ticks(&mut acia, 1);
+    let status_after_load = acia.int_peek(STATUS);
+    ticks(&mut acia, 94);
+    let too_early = output.try_recv();
+    ticks(&mut acia, 1);
+
+    // assert
+    assert_eq!(status_after_write & STATUS_TDRE, 0);
+    assert_eq!(status_after_load & STATUS_TDRE, STATUS_TDRE);
+    assert!(too_early.is_err());
+    assert_eq!(output.try_recv(), Ok(0x41)); // 7 bits
+}
+
+#[test]
+fn receive_interrupt_and_overrun() {
+    // arrange
+    let line = InterruptLine::new();
+    let (mut acia, input, _) = acia();
+    acia.connect_irq(&line);
+    acia.int_write(CONTROL, 0x03);
+    acia.int_write(CONTROL, 0x95); // receive interrupt, divide by 16, 8N1
+
+    // act
+    input.send(b'x').unwrap();
+    input.send(b'y').unwrap();
+    acia.tick();
+    let status = acia.int_read(STATUS);
+    let after_status_read = line.is_active();
+    ticks(&mut acia, 87);
+    let overrun = acia.int_read(STATUS);
+    let data = acia.int_read(DATA);
+
+    // assert
+    assert_eq!(status, STATUS_IRQ | STATUS_TDRE | STATUS_RDRF);
+    assert!(after_status_read); // a level, only reading the data clears it
+    assert_eq!(overrun & STATUS_OVERRUN, STATUS_OVERRUN);
+    assert_eq!(data, b'x');
+    assert_eq!(acia.int_peek(STATUS), STATUS_TDRE);
+    assert!(!line.is_active());
+}
+
+#[test]
+fn transmit_interrupt_and_rts() {
+    // arrange
+    let (mut acia, _, _) = acia();
+    acia.int_write(CONTROL, 0x03);
+
+    // act
+    acia.int_write(CONTROL, 0x35); // RTS low, transmit interrupt
+    let enabled = (acia.irq(), acia.rts());
+    acia.int_write(DATA, 0x55);
+    let written = acia.irq();
+    acia.tick();
+    let loaded = acia.irq();
+    acia.int_write(CONTROL, 0x55); // RTS high, no transmit interrupt
+    let disabled = (acia.irq(), acia.rts());
+
+    // assert
+    assert_eq!(enabled, (true, true));
+    assert!(!written);
+    assert!(loaded);
+    assert_eq!(disabled, (false, false));
+}
+
+#[test]
+fn cts_holds_transmitter_and_dcd_loss_interrupts() {
+    // arrange
+    let (mut acia, _, output) = acia();
+    acia.int_write(CONTROL, 0x03);
+    acia.int_write(CONTROL, 0x95);
+
+    // act
+    acia.set_cts(false);
+    acia.int_write(DATA, b'c');
+    ticks(&mut acia, 200);
+    let held = (acia.int_peek(STATUS), output.try_recv().is_err());
+    acia.set_cts(true);
+    ticks(&mut acia, 88);
+    acia.set_dcd(false);
+    let carrier_lost = (acia.int_read(STATUS), acia.irq());
+    acia.set_dcd(true);
+    acia.int_read(DATA);
+
+    // assert
+    assert_eq!(held, (STATUS_CTS, true));
+    assert_eq!(output.try_recv(), Ok(b'c'));
+    assert_eq!(carrier_lost, (STATUS_IRQ | STATUS_DCD | STATUS_TDRE, true));
+    assert!(!acia.irq());
+}
+
+#[test]
+fn save_and_load_state() {
+    // arrange
+    let (mut acia, input, _) = acia();
+    acia.int_write(CONTROL, 0x03);
+    acia.int_write(CONTROL, 0x96);
+    input.send(0x42).unwrap();
+    acia.tick();
+    let state = acia.save_state();
+
+    // act
+    let (mut restored, _, _) = self::acia();
+    let actual = restored.load_state(&state);
+
+    // assert
+    assert!(actual.is_ok());
+    assert!(restored.irq());
+    assert_eq!(restored.int_peek(DATA), 0x42);
+    assert_eq!(restored.save_state(), state);
+}
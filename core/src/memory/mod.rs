@@ -1,39 +1,237 @@
 #[cfg(test)]
 mod tests;
 
+use std::cell::{Cell, RefCell};
 use std::fs;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
 
-use crate::address_bus::{AddressingError, ExternalAddressing, InternalAddressing};
+use crate::address_bus::{AccessContext, AddressingError, ExternalAddressing, InternalAddressing};
+use crate::rom_catalogue::{self, RomCheck};
+
+/// What happens to writes into a `Memory`; everything but `Writable` makes it a ROM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WritePolicy {
+    Writable,
+    Ignore,
+    Error,
+    Log,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RomWrite {
+    pub addr: u16,
+    pub data: u8,
+    pub pc: u16,
+}
+
+const ROM_WRITE_LOG_LIMIT: usize = 1024; // keep the first writes, like the unmapped access log
+
+/// What happens on reads of bytes not written since tracking started; `Stop` logs them and
+/// leaves a stop for the run loop in `UninitializedLog::take_stop`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UninitializedReads {
+    Allow,
+    Log,
+    Stop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UninitializedRead {
+    pub addr: u16,
+    pub pc: u16,
+}
+
+/// Shared log of uninitialized reads, still readable after the `Memory` went onto a bus.
+#[derive(Debug, Clone, Default)]
+pub struct UninitializedLog {
+    reads: Rc<RefCell<Vec<UninitializedRead>>>,
+    stop: Rc<Cell<Option<UninitializedRead>>>,
+}
+
+impl UninitializedLog {
+    pub fn entries(&self) -> Vec<UninitializedRead> {
+        self.reads.borrow().clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.reads.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reads.borrow().is_empty()
+    }
+
+    pub fn last(&self) -> Option<UninitializedRead> {
+        self.reads.borrow().last().copied()
+    }
+
+    pub fn clear(&self) {
+        self.reads.borrow_mut().clear();
+    }
+
+    /// The read that asked to stop under `UninitializedReads::Stop`, once; a run loop checks
+    /// it after every cycle, so it stops before the next instruction.
+    pub fn take_stop(&self) -> Option<UninitializedRead> {
+        self.stop.take()
+    }
+
+    fn push(&self, read: UninitializedRead) {
+        self.reads.borrow_mut().push(read);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shadow {
+    Uninitialized,
+    Reported, // uninitialized, but already logged
+    Written,
+}
 
 pub struct Memory {
     offset: u16,
     mem: Vec<u8>,
+    write_policy: WritePolicy,
+    rom_writes: Vec<RomWrite>,
+    dropped_rom_writes: u64,
+    uninitialized_reads: UninitializedReads,
+    shadow: Vec<Shadow>, // empty unless uninitialized reads are tracked
+    uninitialized_log: UninitializedLog,
+    context: AccessContext,
 }
 
 #[allow(dead_code)]
 impl Memory {
     pub fn new(offset: u16, size: usize) -> Memory {
-        Memory {
-            offset: offset,
-            mem: vec![0u8; size],
-        }
+        Memory::from_vec(offset, vec![0u8; size])
     }
 
     pub fn from_vec(offset: u16, v: Vec<u8>) -> Memory {
         Memory {
-            offset: offset,
+            offset,
             mem: v,
+            write_policy: WritePolicy::Writable,
+            rom_writes: vec![],
+            dropped_rom_writes: 0,
+            uninitialized_reads: UninitializedReads::Allow,
+            shadow: vec![],
+            uninitialized_log: UninitializedLog::default(),
+            context: AccessContext::default(),
         }
     }
 
-    pub fn load_rom(offset: u16, filename: String) -> Memory {
-        let data = fs::read(filename).expect("could not read file");
-        Memory {
-            offset: offset,
-            mem: data,
+    /// Read an image with `from_rom_image`.
+    pub fn load_rom(offset: u16, filename: impl AsRef<Path>) -> io::Result<(Memory, RomCheck)> {
+        Ok(Memory::from_rom_image(offset, fs::read(filename)?))
+    }
+
+    /// Place an image at `offset`, with its check against the ROM catalogue; anything but
+    /// `RomCheck::Known` is worth a warning.
+    pub fn from_rom_image(offset: u16, data: Vec<u8>) -> (Memory, RomCheck) {
+        let memory = Memory::from_vec(offset, data);
+        let check = memory.rom_check();
+        (memory, check)
+    }
+
+    /// The contents checked against the ROM catalogue as a dump loaded at this offset.
+    pub fn rom_check(&self) -> RomCheck {
+        rom_catalogue::check(&self.mem, self.offset)
+    }
+
+    pub fn set_write_policy(&mut self, policy: WritePolicy) {
+        self.write_policy = policy;
+    }
+
+    pub fn write_policy(&self) -> WritePolicy {
+        self.write_policy
+    }
+
+    /// First writes refused while `WritePolicy::Log` was active.
+    pub fn rom_writes(&self) -> &[RomWrite] {
+        &self.rom_writes
+    }
+
+    /// Refused writes not kept in `rom_writes` because it was full.
+    pub fn dropped_rom_writes(&self) -> u64 {
+        self.dropped_rom_writes
+    }
+
+    pub fn clear_rom_writes(&mut self) {
+        self.rom_writes.clear();
+        self.dropped_rom_writes = 0;
+    }
+
+    /// Track written bytes from now on, as after power-on, and log reads of all others;
+    /// `Allow` stops tracking.
+    pub fn set_uninitialized_reads(&mut self, policy: UninitializedReads) {
+        self.uninitialized_reads = policy;
+        self.shadow = match policy {
+            UninitializedReads::Allow => vec![],
+            UninitializedReads::Log | UninitializedReads::Stop => {
+                vec![Shadow::Uninitialized; self.mem.len()]
+            }
+        };
+    }
+
+    /// First read of every uninitialized byte while `Log` or `Stop` was active.
+    pub fn uninitialized_log(&self) -> UninitializedLog {
+        self.uninitialized_log.clone()
+    }
+
+    fn tracked_read(&mut self, addr: u16, context: AccessContext) -> u8 {
+        let i = (addr - self.offset) as usize;
+        if let Some(shadow) = self.shadow.get_mut(i) {
+            match (*shadow, self.uninitialized_reads) {
+                (Shadow::Written, _) | (_, UninitializedReads::Allow) => (),
+                (Shadow::Uninitialized, policy) => {
+                    *shadow = Shadow::Reported;
+                    let read = UninitializedRead {
+                        addr,
+                        pc: context.pc,
+                    };
+                    self.uninitialized_log.push(read);
+                    if policy == UninitializedReads::Stop {
+                        self.uninitialized_log.stop.set(Some(read));
+                    }
+                }
+                (Shadow::Reported, _) => (),
+            }
+        }
+        self.mem[i]
+    }
+
+    fn mark_written(&mut self, addr: u16) {
+        if let Some(shadow) = self.shadow.get_mut((addr - self.offset) as usize) {
+            *shadow = Shadow::Written;
         }
     }
 
+    fn checked_write(
+        &mut self,
+        addr: u16,
+        data: u8,
+        context: AccessContext,
+    ) -> Result<(), AddressingError> {
+        match self.write_policy {
+            WritePolicy::Writable => {
+                self.mem[(addr - self.offset) as usize] = data;
+                self.mark_written(addr);
+            }
+            WritePolicy::Ignore => (),
+            WritePolicy::Error => return Err(AddressingError::new("write to ROM", addr)),
+            WritePolicy::Log if self.rom_writes.len() < ROM_WRITE_LOG_LIMIT => {
+                self.rom_writes.push(RomWrite {
+                    addr,
+                    data,
+                    pc: context.pc,
+                })
+            }
+            WritePolicy::Log => self.dropped_rom_writes += 1,
+        }
+        Ok(())
+    }
+
     pub fn fill(&mut self, size: usize, value: u8) {
         while self.mem.len() < size {
             self.mem.push(value);
@@ -43,15 +241,66 @@ impl Memory {
 
 impl InternalAddressing for Memory {
     fn int_read(&mut self, addr: u16) -> u8 {
-        self.mem[(addr - self.offset) as usize]
+        self.tracked_read(addr, self.context)
+    }
+
+    fn int_read_checked(
+        &mut self,
+        addr: u16,
+        context: AccessContext,
+    ) -> Result<u8, AddressingError> {
+        Ok(self.tracked_read(addr, context))
     }
 
     fn int_write(&mut self, addr: u16, data: u8) {
+        let _ = self.checked_write(addr, data, self.context);
+    }
+
+    fn int_peek(&self, addr: u16) -> u8 {
+        self.mem[(addr - self.offset) as usize]
+    }
+
+    fn int_poke(&mut self, addr: u16, data: u8) {
         self.mem[(addr - self.offset) as usize] = data;
+        self.mark_written(addr);
+    }
+
+    fn int_write_checked(
+        &mut self,
+        addr: u16,
+        data: u8,
+        context: AccessContext,
+    ) -> Result<(), AddressingError> {
+        self.checked_write(addr, data, context)
     }
 
     fn len(&self) -> usize {
-        self.mem.len() as usize
+        self.mem.len()
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        self.mem.clone()
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), AddressingError> {
+        if state.len() != self.mem.len() {
+            return Err(AddressingError::new("load_state", self.offset));
+        }
+        self.mem.copy_from_slice(state);
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{} ${:04X}-${:04X}",
+            if self.write_policy == WritePolicy::Writable {
+                "memory"
+            } else {
+                "ROM"
+            },
+            self.offset,
+            (self.offset as usize + self.mem.len()).saturating_sub(1)
+        )
     }
 }
 
@@ -60,7 +309,7 @@ impl ExternalAddressing for Memory {
         if addr < self.offset || (addr - self.offset) as usize >= self.mem.len() {
             Err(AddressingError::new("read", addr))
         } else {
-            Ok(self.mem[(addr - self.offset) as usize])
+            Ok(self.tracked_read(addr, self.context))
         }
     }
 
@@ -68,8 +317,28 @@ impl ExternalAddressing for Memory {
         if addr < self.offset || (addr - self.offset) as usize >= self.mem.len() {
             Err(AddressingError::new("write", addr))
         } else {
-            self.mem[(addr - self.offset) as usize] = data;
+            self.checked_write(addr, data, self.context)
+        }
+    }
+
+    fn peek(&self, addr: u16) -> Result<u8, AddressingError> {
+        if addr < self.offset || (addr - self.offset) as usize >= self.mem.len() {
+            Err(AddressingError::new("peek", addr))
+        } else {
+            Ok(self.int_peek(addr))
+        }
+    }
+
+    fn poke(&mut self, addr: u16, data: u8) -> Result<(), AddressingError> {
+        if addr < self.offset || (addr - self.offset) as usize >= self.mem.len() {
+            Err(AddressingError::new("poke", addr))
+        } else {
+            self.int_poke(addr, data);
             Ok(())
         }
     }
+
+    fn set_context(&mut self, context: AccessContext) {
+        self.context = context;
+    }
 }
@@ -1,5 +1,7 @@
 #[cfg(test)]
 use super::*;
+use crate::address_bus::{AccessContext, AddressBus};
+use crate::mos6502::{Cpu, CpuRegisters};
 
 #[test]
 fn writes_and_reads_memory() {
@@ -32,7 +34,8 @@ fn read_vec_memory() {
 #[test]
 fn load_rom() {
     // arrange
-    let mut rom_monitor = Memory::load_rom(0xFF00, "../roms/Apple1_HexMonitor.bin".to_string());
+    let (mut rom_monitor, check) =
+        Memory::load_rom(0xFF00, "../roms/Apple1_HexMonitor.bin").unwrap();
     let expected = 216u8;
 
     let addr = 0xFF00;
@@ -42,6 +45,28 @@ fn load_rom() {
 
     // assert
     assert_eq!(expected, actual);
+    assert!(check.is_known());
+}
+
+#[test]
+fn load_rom_reports_modified_image() {
+    // arrange
+    let mut data = std::fs::read("../roms/Apple1_HexMonitor.bin").unwrap();
+    data[0x10] ^= 0xFF;
+    let path = std::env::temp_dir().join(format!("modified-monitor-{}.bin", std::process::id()));
+    std::fs::write(&path, &data).unwrap();
+
+    // act
+    let (_, check) = Memory::load_rom(0xFF00, &path).unwrap();
+    let missing = Memory::load_rom(0xFF00, "../roms/no-such-rom.bin");
+    std::fs::remove_file(&path).unwrap();
+
+    // assert
+    assert!(matches!(check, RomCheck::Corrupt { .. }));
+    assert!(check
+        .to_string()
+        .starts_with("corrupt image of Woz Monitor"));
+    assert!(missing.is_err());
 }
 
 #[test]
@@ -57,8 +82,7 @@ fn writes_and_reads_external() {
     let actual = mem.read(addr);
 
     // assert
-    assert_eq!(actual.is_ok(), true);
-    assert_eq!(actual.is_err(), false);
+    assert!(actual.is_ok());
     assert_eq!(expected, actual.unwrap());
 }
 
@@ -75,8 +99,7 @@ fn writes_and_reads_external_with_offset() {
     let actual = mem.read(addr);
 
     // assert
-    assert_eq!(actual.is_ok(), true);
-    assert_eq!(actual.is_err(), false);
+    assert!(actual.is_ok());
     assert_eq!(expected, actual.unwrap());
 }
 
@@ -92,10 +115,8 @@ fn writes_and_reads_external_wrong_lower_addr() {
     let actual_read = mem.read(addr);
 
     // assert
-    assert_eq!(actual_write.is_ok(), false);
-    assert_eq!(actual_write.is_err(), true);
-    assert_eq!(actual_read.is_ok(), false);
-    assert_eq!(actual_read.is_err(), true);
+    assert!(actual_write.is_err());
+    assert!(actual_read.is_err());
 }
 
 #[test]
@@ -110,8 +131,215 @@ fn writes_and_reads_external_wrong_higher_addr() {
     let actual_read = mem.read(addr);
 
     // assert
-    assert_eq!(actual_write.is_ok(), false);
-    assert_eq!(actual_write.is_err(), true);
-    assert_eq!(actual_read.is_ok(), false);
-    assert_eq!(actual_read.is_err(), true);
+    assert!(actual_write.is_err());
+    assert!(actual_read.is_err());
+}
+
+#[test]
+fn rom_ignores_writes() {
+    // arrange
+    let mut rom = Memory::from_vec(0xE000, vec![0xEA; 0x10]);
+    rom.set_write_policy(WritePolicy::Ignore);
+
+    // act
+    rom.int_write(0xE001, 0x00);
+    let actual = rom.write(0xE002, 0x00);
+
+    // assert
+    assert!(actual.is_ok());
+    assert_eq!(rom.int_read(0xE001), 0xEA);
+    assert_eq!(rom.int_read(0xE002), 0xEA);
+    assert!(rom.rom_writes().is_empty());
+}
+
+#[test]
+fn rom_reports_writes_as_error() {
+    // arrange
+    let mut rom = Memory::from_vec(0xE000, vec![0xEA; 0x10]);
+    rom.set_write_policy(WritePolicy::Error);
+
+    // act
+    let actual = rom.write(0xE001, 0x00);
+
+    // assert
+    assert!(actual.is_err());
+    assert_eq!(rom.int_read(0xE001), 0xEA);
+}
+
+#[test]
+fn rom_logs_writes_with_pc() {
+    // arrange
+    let mut rom = Memory::from_vec(0xE000, vec![0xEA; 0x10]);
+    rom.set_write_policy(WritePolicy::Log);
+    rom.set_context(AccessContext {
+        pc: 0x0300,
+        ..Default::default()
+    });
+
+    // act
+    let actual = rom.write(0xE00F, 0x42);
+
+    // assert
+    assert!(actual.is_ok());
+    assert_eq!(rom.int_read(0xE00F), 0xEA);
+    assert_eq!(
+        rom.rom_writes(),
+        &[RomWrite {
+            addr: 0xE00F,
+            data: 0x42,
+            pc: 0x0300
+        }]
+    );
+}
+
+#[test]
+fn rom_write_log_keeps_first_writes() {
+    // arrange
+    let mut rom = Memory::from_vec(0xE000, vec![0xEA; 0x10]);
+    rom.set_write_policy(WritePolicy::Log);
+
+    // act
+    for i in 0..1500u16 {
+        rom.write(0xE000 + i % 0x10, i as u8).unwrap();
+    }
+
+    // assert
+    assert_eq!(rom.rom_writes().len(), 1024);
+    assert_eq!(rom.rom_writes()[0].data, 0x00);
+    assert_eq!(rom.dropped_rom_writes(), 476);
+    rom.clear_rom_writes();
+    assert_eq!((rom.rom_writes().len(), rom.dropped_rom_writes()), (0, 0));
+}
+
+#[test]
+fn logs_first_read_of_uninitialized_bytes() {
+    // arrange
+    let mut ram = Memory::new(0, 0x100);
+    ram.set_uninitialized_reads(UninitializedReads::Log);
+    ram.set_context(AccessContext {
+        pc: 0x0280,
+        ..Default::default()
+    });
+
+    // act
+    ram.int_write(0x10, 0x42);
+    ram.read(0x10).unwrap();
+    ram.read(0x11).unwrap();
+    ram.read(0x11).unwrap();
+    ram.int_poke(0x12, 0x00);
+    ram.int_read(0x12);
+
+    // assert
+    assert_eq!(
+        ram.uninitialized_log().entries(),
+        &[UninitializedRead {
+            addr: 0x11,
+            pc: 0x0280
+        }]
+    );
+}
+
+#[test]
+fn uninitialized_read_log_has_cpu_pc() {
+    // arrange: LDA $0010 at $FF00 without writing $0010 first
+    let mut program = vec![0xAD, 0x10, 0x00];
+    program.resize(0x100, 0xEA);
+    program[0xFD] = 0xFF; // reset vector $FF00
+    program[0xFC] = 0x00;
+    let mut rom = Memory::from_vec(0xFF00, program);
+    let mut ram = Memory::new(0, 0x100);
+    ram.set_uninitialized_reads(UninitializedReads::Log);
+    {
+        let mut bus = AddressBus::new(0x100);
+        bus.add_component(0, ram.len(), &mut ram).unwrap();
+        bus.add_component(0xFF00, rom.len(), &mut rom).unwrap();
+        let mut cpu = Cpu::new(CpuRegisters::default(), &mut bus);
+
+        // act
+        cpu.reset();
+        cpu.wait_for_system_reset_cycles();
+        cpu.cycle(false);
+    }
+
+    // assert
+    assert_eq!(
+        ram.uninitialized_log().entries(),
+        &[UninitializedRead {
+            addr: 0x0010,
+            pc: 0xFF00
+        }]
+    );
+}
+
+#[test]
+fn uninitialized_reads_through_bus_are_logged_not_failed() {
+    // arrange
+    let mut ram = Memory::new(0, 0x100);
+    ram.set_uninitialized_reads(UninitializedReads::Log);
+    let log = ram.uninitialized_log();
+    let mut bus = AddressBus::new(0x100);
+    bus.add_component(0, ram.len(), &mut ram).unwrap();
+
+    // act
+    let actual = bus.read(0x0010);
+    bus.write(0x0010, 0x01).unwrap();
+
+    // assert
+    assert_eq!(actual.unwrap(), 0x00);
+    assert_eq!(bus.read(0x0010).unwrap(), 0x01);
+    assert_eq!(bus.peek(0x0011).unwrap(), 0x00); // peeks are not checked
+    assert_eq!(log.entries(), &[UninitializedRead { addr: 0x10, pc: 0 }]);
+}
+
+#[test]
+fn uninitialized_reads_are_not_tracked_by_default() {
+    // arrange
+    let mut ram = Memory::new(0, 0x100);
+
+    // act
+    ram.int_read(0x20);
+    ram.set_uninitialized_reads(UninitializedReads::Log);
+    ram.set_uninitialized_reads(UninitializedReads::Allow);
+    ram.int_read(0x20);
+
+    // assert
+    assert!(ram.uninitialized_log().is_empty());
+}
+
+#[test]
+fn stop_on_uninitialized_read_halts_before_next_instruction() {
+    // arrange: LDA $0010, LDA $0011 at $FF00 without writing either
+    let mut program = vec![0xAD, 0x10, 0x00, 0xAD, 0x11, 0x00];
+    program.resize(0x100, 0xEA);
+    program[0xFD] = 0xFF; // reset vector $FF00
+    program[0xFC] = 0x00;
+    let mut rom = Memory::from_vec(0xFF00, program);
+    let mut ram = Memory::new(0, 0x100);
+    ram.set_uninitialized_reads(UninitializedReads::Stop);
+    let log = ram.uninitialized_log();
+    let mut bus = AddressBus::new(0x100);
+    bus.add_component(0, ram.len(), &mut ram).unwrap();
+    bus.add_component(0xFF00, rom.len(), &mut rom).unwrap();
+    let mut cpu = Cpu::new(CpuRegisters::default(), &mut bus);
+    cpu.reset();
+    cpu.wait_for_system_reset_cycles();
+
+    // act
+    let mut stop = None;
+    while stop.is_none() && cpu.cycles() < 100 {
+        cpu.cycle(false);
+        stop = log.take_stop();
+    }
+
+    // assert
+    assert_eq!(
+        stop,
+        Some(UninitializedRead {
+            addr: 0x0010,
+            pc: 0xFF00
+        })
+    );
+    assert_eq!(cpu.pc(), 0xFF03);
+    assert_eq!(log.len(), 1);
+    assert_eq!(log.take_stop(), None);
 }
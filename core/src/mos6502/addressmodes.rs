@@ -21,7 +21,7 @@ fn from_pc_word(cpu: &mut Cpu, operation: &str, index: u8) -> Result<AddressMode
                         absolute_address: abs_addr,
                         relative_address: 0,
                         fetched_value: 0,
-                        add_cycles: add_cycles,
+                        add_cycles,
                     })
                 }
                 Err(_e) => Err(cpu_error),
@@ -69,7 +69,7 @@ pub fn ind(cpu: &mut Cpu) -> Result<AddressModeValues, CpuError> {
             match cpu.address_bus.read(abs_addr) {
                 Ok(lo) => {
                     if abs_addr & 0x00FF == 0x00FF {
-                        abs_addr = abs_addr & 0xFF00
+                        abs_addr &= 0xFF00
                     } else {
                         abs_addr += 1
                     }
@@ -118,7 +118,7 @@ pub fn izx(cpu: &mut Cpu) -> Result<AddressModeValues, CpuError> {
 
     match from_pc_byte(cpu, "IZX") {
         Ok(result) => {
-            let indexed_address = result.absolute_address as u16 + cpu.r.x as u16;
+            let indexed_address = result.absolute_address + cpu.r.x as u16;
             match cpu.address_bus.read(indexed_address & 0x00FF) {
                 Ok(lo) => match cpu.address_bus.read((indexed_address + 1) & 0x00FF) {
                     Ok(hi) => Ok(AddressModeValues {
@@ -142,7 +142,7 @@ pub fn izy(cpu: &mut Cpu) -> Result<AddressModeValues, CpuError> {
 
     match from_pc_byte(cpu, "IZY") {
         Ok(result) => {
-            let indexed_address = result.absolute_address as u16;
+            let indexed_address = result.absolute_address;
             match cpu.address_bus.read(indexed_address & 0x00FF) {
                 Ok(lo) => match cpu.address_bus.read((indexed_address + 1) & 0x00FF) {
                     Ok(hi) => {
@@ -157,7 +157,7 @@ pub fn izy(cpu: &mut Cpu) -> Result<AddressModeValues, CpuError> {
                             absolute_address: abs_addr,
                             relative_address: 0,
                             fetched_value: 0,
-                            add_cycles: add_cycles,
+                            add_cycles,
                         })
                     }
                     Err(_e) => Err(cpu_error),
@@ -173,7 +173,7 @@ pub fn rel(cpu: &mut Cpu) -> Result<AddressModeValues, CpuError> {
     let cpu_error = CpuError::new("REL", cpu.r.pc);
     match from_pc_byte(cpu, "REL") {
         Ok(result) => {
-            let mut rel_address = result.absolute_address as u16;
+            let mut rel_address = result.absolute_address;
             if rel_address & 0x80 != 0 {
                 rel_address |= 0xFF00
             }
@@ -194,7 +194,7 @@ pub fn zp0(cpu: &mut Cpu) -> Result<AddressModeValues, CpuError> {
     match from_pc_byte(cpu, "ZP0") {
         Ok(result) => Ok(AddressModeValues {
             result: AddressModeResult::Absolute,
-            absolute_address: result.absolute_address as u16 & 0x00FF,
+            absolute_address: result.absolute_address & 0x00FF,
             relative_address: 0,
             fetched_value: 0,
             add_cycles: 0,
@@ -208,7 +208,7 @@ pub fn zpx(cpu: &mut Cpu) -> Result<AddressModeValues, CpuError> {
     match from_pc_byte(cpu, "ZPX") {
         Ok(result) => Ok(AddressModeValues {
             result: AddressModeResult::Absolute,
-            absolute_address: (result.absolute_address as u16 + cpu.r.x as u16) & 0x00FF,
+            absolute_address: (result.absolute_address + cpu.r.x as u16) & 0x00FF,
             relative_address: 0,
             fetched_value: 0,
             add_cycles: 0,
@@ -222,7 +222,7 @@ pub fn zpy(cpu: &mut Cpu) -> Result<AddressModeValues, CpuError> {
     match from_pc_byte(cpu, "ZPY") {
         Ok(result) => Ok(AddressModeValues {
             result: AddressModeResult::Absolute,
-            absolute_address: (result.absolute_address as u16 + cpu.r.y as u16) & 0x00FF,
+            absolute_address: (result.absolute_address + cpu.r.y as u16) & 0x00FF,
             relative_address: 0,
             fetched_value: 0,
             add_cycles: 0,
@@ -6,7 +6,8 @@ mod tests;
 use std::fs::File;
 use std::io::Write;
 
-use crate::address_bus::ExternalAddressing;
+use crate::address_bus::{AccessContext, AddressingError, ExternalAddressing};
+use crate::interrupt::InterruptLine;
 use addressmodes::*;
 use operations::*;
 
@@ -50,7 +51,9 @@ pub struct CpuRegisters {
 pub struct Cpu<'a> {
     r: CpuRegisters,
     remaining_cycles: u8,
+    cycles: u64,
     address_bus: &'a mut dyn ExternalAddressing,
+    irq: Option<InterruptLine>,
     // DEBUG INFORMATION
     current_pc: u16,
 }
@@ -66,7 +69,7 @@ impl CpuError {
     fn new(operation: &str, pc: u16) -> CpuError {
         CpuError {
             operation: operation.to_string(),
-            pc: pc,
+            pc,
         }
     }
 }
@@ -354,10 +357,12 @@ pub enum StatusFlag {
 impl<'a> Cpu<'a> {
     pub fn new(r: CpuRegisters, address_bus: &'a mut dyn ExternalAddressing) -> Cpu<'a> {
         Cpu {
-            r: r,
+            r,
             remaining_cycles: 0,
+            cycles: 0,
             current_pc: 0,
-            address_bus: address_bus,
+            address_bus,
+            irq: None,
         }
     }
 
@@ -369,6 +374,35 @@ impl<'a> Cpu<'a> {
         self.address_bus.read(addr).unwrap()
     }
 
+    /// The connected bus, e.g. to inspect or export memory without side effects.
+    pub fn address_bus(&self) -> &dyn ExternalAddressing {
+        &*self.address_bus
+    }
+
+    /// Debugger access to the bus, failing where nothing is mapped.
+    pub fn peek(&self, addr: u16) -> Result<u8, AddressingError> {
+        self.address_bus.peek(addr)
+    }
+
+    pub fn poke(&mut self, addr: u16, b: u8) -> Result<(), AddressingError> {
+        self.address_bus.poke(addr, b)
+    }
+
+    /// Address of the next instruction once the current one has completed its cycles.
+    pub fn pc(&self) -> u16 {
+        self.r.pc
+    }
+
+    /// Continue execution at `addr`, e.g. to start a program after `reset`.
+    pub fn set_pc(&mut self, addr: u16) {
+        self.r.pc = addr;
+    }
+
+    /// Connect the IRQ input; devices drive the line through their `InterruptOutput`s.
+    pub fn set_irq_line(&mut self, line: InterruptLine) {
+        self.irq = Some(line);
+    }
+
     // ##### FLAGS ####
     pub fn set_flag(&mut self, flag: StatusFlag, value: bool) {
         if value {
@@ -383,7 +417,7 @@ impl<'a> Cpu<'a> {
 
     // ##### CYCLES ####
     pub fn reset(&mut self) {
-        self.r.status = 0 | StatusFlag::U as u8;
+        self.r.status = StatusFlag::U as u8 | StatusFlag::I as u8;
         self.r.a = 0;
         self.r.x = 0;
         self.r.y = 0;
@@ -403,10 +437,50 @@ impl<'a> Cpu<'a> {
         }
 
         self.remaining_cycles = 7;
+        self.add_wait_cycles();
+    }
+
+    // wait states of the components accessed are paid as additional instruction cycles
+    fn add_wait_cycles(&mut self) {
+        let wait_cycles = self.address_bus.take_wait_cycles();
+        self.remaining_cycles = self
+            .remaining_cycles
+            .saturating_add(u8::try_from(wait_cycles).unwrap_or(u8::MAX));
+    }
+
+    // IRQ is taken between instructions while the line is active and not masked
+    fn handle_irq(&mut self) {
+        if self.remaining_cycles != 0
+            || self.get_flag(StatusFlag::I)
+            || !self.irq.as_ref().is_some_and(|line| line.is_active())
+        {
+            return;
+        }
+
+        self.push((self.r.pc >> 8) as u8);
+        self.push(self.r.pc as u8);
+        self.push((self.r.status & !(StatusFlag::B as u8)) | StatusFlag::U as u8);
+        self.set_flag(StatusFlag::I, true);
+        let lo = self.read(0xFFFE);
+        let hi = self.read(0xFFFF);
+        self.r.pc = (hi as u16) << 8 | lo as u16;
+
+        self.remaining_cycles = 7;
+        self.add_wait_cycles();
+    }
+
+    fn push(&mut self, b: u8) {
+        self.write(0x0100 | self.r.sp as u16, b);
+        self.r.sp = self.r.sp.wrapping_sub(1);
     }
 
     pub fn cycle(&mut self, debug: bool) {
+        self.handle_irq();
         if self.remaining_cycles == 0 {
+            self.address_bus.set_context(AccessContext {
+                pc: self.r.pc,
+                cycle: self.cycles,
+            });
             match self.address_bus.read(self.r.pc) {
                 Ok(opcode) => {
                     self.current_pc = self.r.pc;
@@ -425,6 +499,7 @@ impl<'a> Cpu<'a> {
                             self.remaining_cycles += address_mode_values.add_cycles;
                             self.remaining_cycles +=
                                 (operation.operation)(self, address_mode_values, opcode);
+                            self.add_wait_cycles();
 
                             if debug {
                                 println!(
@@ -448,10 +523,17 @@ impl<'a> Cpu<'a> {
         }
 
         self.remaining_cycles -= 1;
+        self.cycles += 1;
+        self.address_bus.tick();
     }
 
     pub fn cycle_file(&mut self, w: &mut File) {
+        self.handle_irq();
         if self.remaining_cycles == 0 {
+            self.address_bus.set_context(AccessContext {
+                pc: self.r.pc,
+                cycle: self.cycles,
+            });
             match self.address_bus.read(self.r.pc) {
                 Ok(opcode) => {
                     self.current_pc = self.r.pc;
@@ -470,6 +552,7 @@ impl<'a> Cpu<'a> {
                             self.remaining_cycles += address_mode_values.add_cycles;
                             self.remaining_cycles +=
                                 (operation.operation)(self, address_mode_values, opcode);
+                            self.add_wait_cycles();
 
                             writeln!(
                                 w,
@@ -492,6 +575,10 @@ impl<'a> Cpu<'a> {
             }
         }
 
+        self.cycles += self.remaining_cycles as u64;
+        for _ in 0..self.remaining_cycles {
+            self.address_bus.tick();
+        }
         self.remaining_cycles = 0; // skip cycles
     }
 
@@ -501,6 +588,11 @@ impl<'a> Cpu<'a> {
         }
     }
 
+    /// Cycles executed since the CPU was created.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
     pub fn completed_operation_cycles(&mut self) -> bool {
         self.remaining_cycles == 0
     }
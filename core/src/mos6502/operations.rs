@@ -164,7 +164,7 @@ pub fn bit(cpu: &mut Cpu, address_mode_values: AddressModeValues, _opcode: u8) -
     let fetched = fetch(cpu, address_mode_values);
     let temp = cpu.r.a & fetched;
 
-    cpu.set_flag(StatusFlag::Z, temp & 0xFF == 0);
+    cpu.set_flag(StatusFlag::Z, temp == 0);
     cpu.set_flag(StatusFlag::N, fetched & (1 << 7) != 0);
     cpu.set_flag(StatusFlag::V, fetched & (1 << 6) != 0);
 
@@ -317,7 +317,7 @@ fn compare_with_register(
     let fetched = fetch(cpu, address_mode_values);
     let temp = register.wrapping_sub(fetched);
     cpu.set_flag(StatusFlag::C, register >= fetched);
-    cpu.set_flag(StatusFlag::Z, temp & 0xFF == 0);
+    cpu.set_flag(StatusFlag::Z, temp == 0);
     cpu.set_flag(StatusFlag::N, temp & 0x80 != 0);
     1
 }
@@ -339,10 +339,10 @@ pub fn dec(cpu: &mut Cpu, address_mode_values: AddressModeValues, _opcode: u8) -
     let temp = fetched.wrapping_sub(1);
     match cpu
         .address_bus
-        .write(address_mode_values.absolute_address, (temp & 0xFF) as u8)
+        .write(address_mode_values.absolute_address, temp)
     {
         Ok(_) => {
-            cpu.set_flag(StatusFlag::Z, temp & 0xFF == 0);
+            cpu.set_flag(StatusFlag::Z, temp == 0);
             cpu.set_flag(StatusFlag::N, temp & 0x80 != 0);
         }
         Err(e) => panic!("addressing error {}", e),
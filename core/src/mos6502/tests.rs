@@ -24,8 +24,7 @@ fn test_address_mode_abs() {
     let actual = abs(&mut cpu);
 
     // assert
-    assert_eq!(actual.is_ok(), true);
-    assert_eq!(actual.is_err(), false);
+    assert!(actual.is_ok());
     let a = actual.unwrap();
     assert_eq!(expected, a.absolute_address);
     assert_eq!(AddressModeResult::Absolute, a.result);
@@ -52,8 +51,7 @@ fn test_address_mode_abx() {
     let actual = abx(&mut cpu);
 
     // assert
-    assert_eq!(actual.is_ok(), true);
-    assert_eq!(actual.is_err(), false);
+    assert!(actual.is_ok());
     let a = actual.unwrap();
     assert_eq!(expected, a.absolute_address);
     assert_eq!(AddressModeResult::Absolute, a.result);
@@ -80,8 +78,7 @@ fn test_address_mode_abx_cross_page() {
     let actual = abx(&mut cpu);
 
     // assert
-    assert_eq!(actual.is_ok(), true);
-    assert_eq!(actual.is_err(), false);
+    assert!(actual.is_ok());
     let a = actual.unwrap();
     assert_eq!(expected, a.absolute_address);
     assert_eq!(AddressModeResult::Absolute, a.result);
@@ -109,8 +106,7 @@ fn test_address_mode_aby() {
     let actual = aby(&mut cpu);
 
     // assert
-    assert_eq!(actual.is_ok(), true);
-    assert_eq!(actual.is_err(), false);
+    assert!(actual.is_ok());
     let a = actual.unwrap();
     assert_eq!(expected, a.absolute_address);
     assert_eq!(AddressModeResult::Absolute, a.result);
@@ -133,8 +129,7 @@ fn test_address_mode_abs_addr_error() {
     let actual = abs(&mut cpu);
 
     // assert
-    assert_eq!(actual.is_ok(), false);
-    assert_eq!(actual.is_err(), true);
+    assert!(actual.is_err());
 }
 
 #[test]
@@ -156,8 +151,7 @@ fn test_address_mode_ind() {
     let actual = ind(&mut cpu);
 
     // assert
-    assert_eq!(actual.is_ok(), true);
-    assert_eq!(actual.is_err(), false);
+    assert!(actual.is_ok());
     let a = actual.unwrap();
     assert_eq!(expected, a.absolute_address);
     assert_eq!(AddressModeResult::Absolute, a.result);
@@ -176,8 +170,7 @@ fn test_address_mode_imm() {
     let actual = imm(&mut cpu);
 
     // assert
-    assert_eq!(actual.is_ok(), true);
-    assert_eq!(actual.is_err(), false);
+    assert!(actual.is_ok());
     let a = actual.unwrap();
     assert_eq!(expected, a.absolute_address);
     assert_eq!(AddressModeResult::Absolute, a.result);
@@ -196,8 +189,7 @@ fn test_address_mode_imp() {
     let actual = imp(&mut cpu);
 
     // assert
-    assert_eq!(actual.is_ok(), true);
-    assert_eq!(actual.is_err(), false);
+    assert!(actual.is_ok());
     let a = actual.unwrap();
     assert_eq!(expected, a.fetched_value);
     assert_eq!(AddressModeResult::Fetched, a.result);
@@ -223,8 +215,7 @@ fn test_address_mode_izx() {
     let actual = izx(&mut cpu);
 
     // assert
-    assert_eq!(actual.is_ok(), true);
-    assert_eq!(actual.is_err(), false);
+    assert!(actual.is_ok());
     let a = actual.unwrap();
     assert_eq!(expected, a.absolute_address);
     assert_eq!(AddressModeResult::Absolute, a.result);
@@ -251,8 +242,7 @@ fn test_address_mode_izy() {
     let actual = izy(&mut cpu);
 
     // assert
-    assert_eq!(actual.is_ok(), true);
-    assert_eq!(actual.is_err(), false);
+    assert!(actual.is_ok());
     let a = actual.unwrap();
     assert_eq!(expected, a.absolute_address);
     assert_eq!(AddressModeResult::Absolute, a.result);
@@ -278,8 +268,7 @@ fn test_address_mode_rel() {
     let actual = rel(&mut cpu);
 
     // assert
-    assert_eq!(actual.is_ok(), true);
-    assert_eq!(actual.is_err(), false);
+    assert!(actual.is_ok());
     let a = actual.unwrap();
     assert_eq!(expected, a.relative_address);
     assert_eq!(AddressModeResult::Relative, a.result);
@@ -305,8 +294,7 @@ fn test_address_mode_zp0() {
     let actual = zp0(&mut cpu);
 
     // assert
-    assert_eq!(actual.is_ok(), true);
-    assert_eq!(actual.is_err(), false);
+    assert!(actual.is_ok());
     let a = actual.unwrap();
     assert_eq!(expected, a.absolute_address);
     assert_eq!(AddressModeResult::Absolute, a.result);
@@ -333,8 +321,7 @@ fn test_address_mode_zpx() {
     let actual = zpx(&mut cpu);
 
     // assert
-    assert_eq!(actual.is_ok(), true);
-    assert_eq!(actual.is_err(), false);
+    assert!(actual.is_ok());
     let a = actual.unwrap();
     assert_eq!(expected, a.absolute_address);
     assert_eq!(AddressModeResult::Absolute, a.result);
@@ -361,8 +348,7 @@ fn test_address_mode_zpy() {
     let actual = zpy(&mut cpu);
 
     // assert
-    assert_eq!(actual.is_ok(), true);
-    assert_eq!(actual.is_err(), false);
+    assert!(actual.is_ok());
     let a = actual.unwrap();
     assert_eq!(expected, a.absolute_address);
     assert_eq!(AddressModeResult::Absolute, a.result);
@@ -389,15 +375,15 @@ fn test_set_flags() {
 
     // assert
     assert_eq!(expected, cpu.r.status);
-    assert_eq!(true, cpu.get_flag(StatusFlag::D));
-    assert_eq!(true, cpu.get_flag(StatusFlag::C));
-    assert_eq!(false, cpu.get_flag(StatusFlag::Z));
+    assert!(cpu.get_flag(StatusFlag::D));
+    assert!(cpu.get_flag(StatusFlag::C));
+    assert!(!cpu.get_flag(StatusFlag::Z));
 }
 
 #[test]
 fn test_clear_flags() {
     // arrange
-    let expected: u8 = 0xFF & !(StatusFlag::D as u8 | StatusFlag::C as u8);
+    let expected: u8 = !(StatusFlag::D as u8 | StatusFlag::C as u8);
     let mut mem = Memory::from_vec(0, vec![0]);
     let mut address_bus = AddressBus::new(mem.len());
     if address_bus.add_component(0, mem.len(), &mut (mem)).is_err() {
@@ -413,9 +399,9 @@ fn test_clear_flags() {
 
     // assert
     assert_eq!(expected, cpu.r.status);
-    assert_eq!(false, cpu.get_flag(StatusFlag::D));
-    assert_eq!(false, cpu.get_flag(StatusFlag::C));
-    assert_eq!(true, cpu.get_flag(StatusFlag::Z));
+    assert!(!cpu.get_flag(StatusFlag::D));
+    assert!(!cpu.get_flag(StatusFlag::C));
+    assert!(cpu.get_flag(StatusFlag::Z));
 }
 
 // ##### OPERATIONS ####
@@ -562,7 +548,7 @@ fn functional_test() {
     // let mut w = File::create("func-rust.txt").unwrap();
 
     const END_OF_FUNCTIONAL_TEST: u16 = 0x3469;
-    let mut mem = Memory::load_rom(0, "../roms/6502_functional_test.bin".to_string());
+    let (mut mem, _) = Memory::load_rom(0, "../roms/6502_functional_test.bin").unwrap();
 
     let mut cpu = Cpu::new(CpuRegisters::default(), &mut mem);
     cpu.reset();
@@ -617,7 +603,7 @@ fn decimal_test() {
         panic!("add_component failed");
     }
 
-    let mut rom = Memory::load_rom(0x200, "../roms/6502_decimal_test.bin".to_string());
+    let (mut rom, _) = Memory::load_rom(0x200, "../roms/6502_decimal_test.bin").unwrap();
     rom.fill(0x200, 0);
     if address_bus
         .add_component(0x0200, rom.len(), &mut (rom))
@@ -648,3 +634,84 @@ fn decimal_test() {
         RESULT_ADDR_DECIMAL_TEST, RESULT_DECIMAL_TEST, actual
     );
 }
+
+#[test]
+fn irq_is_taken_when_line_active_and_not_masked() {
+    // arrange
+    let mut program = vec![0xEA; 0x10000];
+    program[0x0200] = 0x58; // CLI
+    program[0x0300] = 0x40; // RTI
+    program[0xFFFC] = 0x00; // reset $0200
+    program[0xFFFD] = 0x02;
+    program[0xFFFE] = 0x00; // IRQ $0300
+    program[0xFFFF] = 0x03;
+    let mut mem = Memory::from_vec(0, program);
+    let line = crate::interrupt::InterruptLine::new();
+    let mut device = line.output();
+    device.set(true);
+
+    let mut cpu = Cpu::new(CpuRegisters::default(), &mut mem);
+    cpu.set_irq_line(line.clone());
+    cpu.reset();
+    cpu.wait_for_system_reset_cycles();
+
+    // act
+    for _ in 0..2 {
+        cpu.cycle(false); // CLI; I is still set after reset
+    }
+    let masked_pc = cpu.r.pc;
+    cpu.cycle(false); // interrupt sequence
+    let handler_pc = cpu.r.pc;
+    let stacked_status = cpu.peek(0x01FB).unwrap();
+    cpu.wait_for_system_reset_cycles();
+    device.set(false);
+    for _ in 0..6 {
+        cpu.cycle(false); // RTI
+    }
+
+    // assert
+    assert_eq!(masked_pc, 0x0201);
+    assert_eq!(handler_pc, 0x0300);
+    assert_eq!(cpu.peek(0x01FD).unwrap(), 0x02);
+    assert_eq!(cpu.peek(0x01FC).unwrap(), 0x01);
+    assert_eq!(stacked_status & StatusFlag::B as u8, 0);
+    assert_eq!(cpu.r.pc, 0x0201);
+}
+
+#[test]
+fn peek_and_poke_of_unmapped_address_fail() {
+    // arrange
+    let mut mem = Memory::new(0, 0x100);
+    let mut address_bus = AddressBus::new(0x100);
+    address_bus.add_component(0, mem.len(), &mut mem).unwrap();
+    let mut cpu = Cpu::new(CpuRegisters::default(), &mut address_bus);
+
+    // act
+    let peeked = cpu.peek(0x1000);
+    let poked = cpu.poke(0x1000, 0x42);
+
+    // assert
+    assert!(peeked.is_err());
+    assert!(poked.is_err());
+    assert!(cpu.poke(0x0010, 0x42).is_ok());
+    assert_eq!(cpu.peek(0x0010).unwrap(), 0x42);
+}
+
+#[test]
+fn reset_sets_interrupt_disable_and_unused_flags() {
+    // arrange
+    let mut program = vec![0xEA; 0x10000];
+    program[0xFFFC] = 0x00; // reset $0200
+    program[0xFFFD] = 0x02;
+    let mut mem = Memory::from_vec(0, program);
+    let mut cpu = Cpu::new(CpuRegisters::default(), &mut mem);
+    cpu.r.status = 0xFF;
+    cpu.r.a = 0x42;
+
+    // act
+    cpu.reset();
+
+    // assert
+    assert_eq!(cpu.r.status, StatusFlag::I as u8 | StatusFlag::U as u8);
+    assert_eq!((cpu.r.a, cpu.r.sp, cpu.r.pc), (0x00, 0xFD, 0x0200));
+}
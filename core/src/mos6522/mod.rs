@@ -0,0 +1,742 @@
+#[cfg(test)]
+mod tests;
+
+use crossbeam_channel::{Receiver, Sender};
+
+use crate::address_bus::{AddressingError, InternalAddressing};
+use crate::interrupt::{InterruptLine, InterruptOutput};
+use crate::mc6821::{ChannelPeripheral, InputSignal, PiaPeripheral, Port, Signal, Strobe};
+
+// IFR / IER bits
+pub const IRQ_CA2: u8 = 0x01;
+pub const IRQ_CA1: u8 = 0x02;
+pub const IRQ_SR: u8 = 0x04;
+pub const IRQ_CB2: u8 = 0x08;
+pub const IRQ_CB1: u8 = 0x10;
+pub const IRQ_T2: u8 = 0x20;
+pub const IRQ_T1: u8 = 0x40;
+
+pub struct MOS6522 {
+    ora: u8,       // output register A
+    ira: u8,       // input pins A
+    ira_latch: u8, // pins A at the last active CA1 transition
+    ddra: u8,      // data direction register A (Output=1, Input=0)
+    ca1: Signal,
+    ca2: Signal,
+    ca2_strobe: Strobe,
+
+    orb: u8,       // output register B
+    irb: u8,       // input pins B
+    irb_latch: u8, // pins B at the last active CB1 transition
+    ddrb: u8,      // data direction register B (Output=1, Input=0)
+    cb1: Signal,
+    cb2: Signal,
+    cb2_strobe: Strobe,
+
+    t1_counter: u16,
+    t1_latch: u16,
+    t1_armed: bool,  // one-shot interrupt not yet flagged
+    t1_reload: bool, // free-run: counter is reloaded on the next cycle
+    pb7: bool,       // timer 1 output on PB7
+
+    t2_counter: u16,
+    t2_latch_low: u8,
+    t2_armed: bool,
+
+    sr: u8,
+    sr_bits: u8,       // bits shifted since the shift register was accessed
+    sr_running: bool,  // shifting with internal or external clock
+    sr_countdown: u16, // cycles to the next edge of the internal shift clock
+
+    acr: u8, // auxiliary control register
+    pcr: u8, // peripheral control register
+    ifr: u8, // interrupt flags, bit 7 computed on read
+    ier: u8, // interrupt enable, bit 7 reads as 1
+
+    peripherals: Vec<(Port, Box<dyn PiaPeripheral>)>,
+    irq: InterruptOutput,
+}
+
+impl Default for MOS6522 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MOS6522 {
+    pub fn new() -> MOS6522 {
+        MOS6522 {
+            ora: 0,
+            ira: 0xFF,
+            ira_latch: 0xFF,
+            ddra: 0,
+            ca1: Signal::Rise,
+            ca2: Signal::Rise,
+            ca2_strobe: Strobe::Idle,
+
+            orb: 0,
+            irb: 0xFF,
+            irb_latch: 0xFF,
+            ddrb: 0,
+            cb1: Signal::Rise,
+            cb2: Signal::Rise,
+            cb2_strobe: Strobe::Idle,
+
+            t1_counter: 0xFFFF,
+            t1_latch: 0xFFFF,
+            t1_armed: false,
+            t1_reload: false,
+            pb7: true,
+
+            t2_counter: 0xFFFF,
+            t2_latch_low: 0xFF,
+            t2_armed: false,
+
+            sr: 0,
+            sr_bits: 0,
+            sr_running: false,
+            sr_countdown: 0,
+
+            acr: 0,
+            pcr: 0,
+            ifr: 0,
+            ier: 0,
+
+            peripherals: vec![],
+            irq: InterruptOutput::default(),
+        }
+    }
+
+    /// Attach a device to a port; several devices can share a port.
+    pub fn attach_peripheral(&mut self, port: Port, peripheral: Box<dyn PiaPeripheral>) {
+        self.peripherals.push((port, peripheral));
+    }
+
+    pub fn set_input_channel(&mut self, rx: Receiver<InputSignal>) {
+        self.attach_peripheral(Port::A, Box::new(ChannelPeripheral::new(Some(rx), None)));
+    }
+
+    pub fn set_output_channel_a(&mut self, tx: Sender<u8>) {
+        self.attach_peripheral(Port::A, Box::new(ChannelPeripheral::new(None, Some(tx))));
+    }
+
+    pub fn set_output_channel_b(&mut self, tx: Sender<u8>) {
+        self.attach_peripheral(Port::B, Box::new(ChannelPeripheral::new(None, Some(tx))));
+    }
+
+    pub fn connect_irq(&mut self, line: &InterruptLine) {
+        self.irq.connect(line);
+    }
+
+    /// Level of the IRQ output.
+    pub fn irq(&self) -> bool {
+        self.irq.is_active()
+    }
+
+    pub fn get_ca2(&self) -> Signal {
+        self.ca2
+    }
+
+    pub fn get_cb1(&self) -> Signal {
+        self.cb1
+    }
+
+    pub fn get_cb2(&self) -> Signal {
+        self.cb2
+    }
+
+    /// Take pending input line changes from all peripherals; also done on every `tick`.
+    pub fn process_input(&mut self) {
+        for i in 0..self.peripherals.len() {
+            while let Some(input) = self.peripherals[i].1.poll() {
+                match input {
+                    InputSignal::IRA(b) => self.ira = b,
+                    InputSignal::IRB(b) => self.set_irb(b),
+                    InputSignal::CA1(s) => self.set_ca1(s),
+                    InputSignal::CA2(s) => self.set_ca2(s),
+                    InputSignal::CB1(s) => self.set_cb1(s),
+                    InputSignal::CB2(s) => self.set_cb2(s),
+                }
+            }
+        }
+    }
+
+    fn notify(&mut self, port: Port, mut f: impl FnMut(&mut dyn PiaPeripheral)) {
+        for (_, peripheral) in self.peripherals.iter_mut().filter(|(p, _)| *p == port) {
+            f(peripheral.as_mut());
+        }
+    }
+
+    // ##### INTERRUPTS ####
+
+    fn set_flags(&mut self, flags: u8) {
+        self.ifr |= flags;
+        self.update_irq();
+    }
+
+    fn clear_flags(&mut self, flags: u8) {
+        self.ifr &= !flags;
+        self.update_irq();
+    }
+
+    fn update_irq(&mut self) {
+        self.irq.set(self.ifr & self.ier & 0x7F != 0);
+    }
+
+    fn ifr_with_irq_bit(&self) -> u8 {
+        if self.irq.is_active() {
+            self.ifr | 0x80
+        } else {
+            self.ifr
+        }
+    }
+
+    // ##### PORTS ####
+
+    fn port_a(&self) -> u8 {
+        let pins = if self.acr & 0x01 != 0 {
+            self.ira_latch
+        } else {
+            self.ira
+        };
+        (self.ora & self.ddra) | (pins & !self.ddra)
+    }
+
+    fn port_b(&self) -> u8 {
+        let pins = if self.acr & 0x02 != 0 {
+            self.irb_latch
+        } else {
+            self.irb
+        };
+        let data = (self.orb & self.ddrb) | (pins & !self.ddrb);
+        if self.acr & 0x80 != 0 {
+            (data & 0x7F) | if self.pb7 { 0x80 } else { 0x00 }
+        } else {
+            data
+        }
+    }
+
+    fn port_b_output_changed(&mut self) {
+        let data = self.port_b();
+        self.notify(Port::B, |peripheral| peripheral.port_written(data));
+    }
+
+    // CA2/CB2 control from PCR bits 1-3 / 5-7
+    fn c2_control(&self, port: Port) -> u8 {
+        match port {
+            Port::A => (self.pcr >> 1) & 0x07,
+            Port::B => (self.pcr >> 5) & 0x07,
+        }
+    }
+
+    // reading or writing ORA/ORB clears the C1 flag and, unless independent, the C2 flag
+    fn port_access(&mut self, port: Port) {
+        let independent = matches!(self.c2_control(port), 0b001 | 0b011);
+        let (c1, c2) = match port {
+            Port::A => (IRQ_CA1, IRQ_CA2),
+            Port::B => (IRQ_CB1, IRQ_CB2),
+        };
+        self.clear_flags(if independent { c1 } else { c1 | c2 });
+    }
+
+    fn start_strobe(&mut self, port: Port) {
+        if matches!(self.c2_control(port), 0b100 | 0b101) {
+            match port {
+                Port::A => self.ca2_strobe = Strobe::FallNext,
+                Port::B => self.cb2_strobe = Strobe::FallNext,
+            }
+        }
+    }
+
+    // low after the port access, high one cycle later (pulse) or on the next C1 edge
+    fn clock_strobe(&mut self, port: Port) {
+        let strobe = match port {
+            Port::A => self.ca2_strobe,
+            Port::B => self.cb2_strobe,
+        };
+        let pulse = self.c2_control(port) == 0b101;
+        let (next, signal) = match strobe {
+            Strobe::Idle => return,
+            Strobe::FallNext if pulse => (Strobe::RiseNext, Signal::Fall),
+            Strobe::FallNext => (Strobe::Idle, Signal::Fall),
+            Strobe::RiseNext => (Strobe::Idle, Signal::Rise),
+        };
+        match port {
+            Port::A => self.ca2_strobe = next,
+            Port::B => self.cb2_strobe = next,
+        }
+        self.drive_c2(port, signal);
+    }
+
+    fn drive_c2(&mut self, port: Port, signal: Signal) {
+        let line = match port {
+            Port::A => &mut self.ca2,
+            Port::B => &mut self.cb2,
+        };
+        if *line != signal {
+            *line = signal;
+            self.notify(port, |peripheral| peripheral.control_output(signal));
+        }
+    }
+
+    fn update_pcr(&mut self) {
+        for port in [Port::A, Port::B] {
+            match self.c2_control(port) {
+                0b110 => self.drive_c2(port, Signal::Fall),
+                0b111 => self.drive_c2(port, Signal::Rise),
+                0b100 | 0b101 => (),
+                _ => match port {
+                    Port::A => self.ca2_strobe = Strobe::Idle,
+                    Port::B => self.cb2_strobe = Strobe::Idle,
+                },
+            }
+        }
+    }
+
+    fn active_edge(previous: Signal, s: Signal, positive: bool) -> bool {
+        previous != s && (s == Signal::Rise) == positive
+    }
+
+    fn set_ca1(&mut self, s: Signal) {
+        if MOS6522::active_edge(self.ca1, s, self.pcr & 0x01 != 0) {
+            self.ira_latch = self.ira;
+            if self.c2_control(Port::A) == 0b100 {
+                // handshake mode: the peripheral answered
+                self.ca2_strobe = Strobe::Idle;
+                self.drive_c2(Port::A, Signal::Rise);
+            }
+            self.set_flags(IRQ_CA1);
+        }
+        self.ca1 = s;
+    }
+
+    fn set_ca2(&mut self, s: Signal) {
+        let control = self.c2_control(Port::A);
+        if control & 0b100 == 0 {
+            if MOS6522::active_edge(self.ca2, s, control & 0b010 != 0) {
+                self.set_flags(IRQ_CA2);
+            }
+            self.ca2 = s;
+        }
+    }
+
+    fn set_cb1(&mut self, s: Signal) {
+        if MOS6522::active_edge(self.cb1, s, self.pcr & 0x10 != 0) {
+            self.irb_latch = self.irb;
+            if self.c2_control(Port::B) == 0b100 {
+                // handshake mode: the peripheral answered
+                self.cb2_strobe = Strobe::Idle;
+                self.drive_c2(Port::B, Signal::Rise);
+            }
+            self.set_flags(IRQ_CB1);
+        }
+        if self.cb1 != s && matches!(self.sr_mode(), 0b011 | 0b111) {
+            self.shift_clock(s); // external shift clock
+        }
+        self.cb1 = s;
+    }
+
+    fn set_cb2(&mut self, s: Signal) {
+        let control = self.c2_control(Port::B);
+        if self.sr_mode() != 0 {
+            if self.sr_mode() & 0b100 == 0 {
+                self.cb2 = s; // shift register data input
+            }
+        } else if control & 0b100 == 0 {
+            if MOS6522::active_edge(self.cb2, s, control & 0b010 != 0) {
+                self.set_flags(IRQ_CB2);
+            }
+            self.cb2 = s;
+        }
+    }
+
+    fn set_irb(&mut self, b: u8) {
+        // timer 2 counts negative transitions on PB6
+        if self.acr & 0x20 != 0 && self.irb & 0x40 != 0 && b & 0x40 == 0 {
+            self.t2_counter = self.t2_counter.wrapping_sub(1);
+            if self.t2_counter == 0 && self.t2_armed {
+                self.t2_armed = false;
+                self.set_flags(IRQ_T2);
+            }
+        }
+        self.irb = b;
+    }
+
+    // ##### TIMERS ####
+
+    fn clock_t1(&mut self) {
+        if self.t1_reload {
+            self.t1_reload = false;
+            self.t1_counter = self.t1_latch;
+            return;
+        }
+        if self.t1_counter != 0 {
+            self.t1_counter -= 1;
+            return;
+        }
+
+        self.t1_counter = 0xFFFF;
+        if self.acr & 0x40 != 0 {
+            // free-run: period of latch + 2 cycles, PB7 toggles
+            self.t1_reload = true;
+            self.set_flags(IRQ_T1);
+            if self.acr & 0x80 != 0 {
+                self.pb7 = !self.pb7;
+                self.port_b_output_changed();
+            }
+        } else if self.t1_armed {
+            self.t1_armed = false;
+            self.set_flags(IRQ_T1);
+            if self.acr & 0x80 != 0 {
+                self.pb7 = true;
+                self.port_b_output_changed();
+            }
+        }
+    }
+
+    fn clock_t2(&mut self) {
+        if self.acr & 0x20 != 0 {
+            return; // counting PB6 pulses
+        }
+        if self.t2_counter == 0 && self.t2_armed {
+            self.t2_armed = false;
+            self.set_flags(IRQ_T2);
+        }
+        self.t2_counter = self.t2_counter.wrapping_sub(1);
+    }
+
+    // ##### SHIFT REGISTER ####
+
+    fn sr_mode(&self) -> u8 {
+        (self.acr >> 2) & 0x07
+    }
+
+    // cycles between edges of the shift clock on CB1 under timer 2 control
+    fn sr_half_period(&self) -> u16 {
+        self.t2_latch_low as u16 + 2
+    }
+
+    fn start_shift(&mut self) {
+        self.sr_bits = 0;
+        self.sr_running = self.sr_mode() != 0;
+        self.sr_countdown = self.sr_half_period();
+        self.clear_flags(IRQ_SR);
+    }
+
+    fn clock_sr(&mut self) {
+        if !self.sr_running || matches!(self.sr_mode(), 0b000 | 0b011 | 0b111) {
+            return;
+        }
+        if matches!(self.sr_mode(), 0b010 | 0b110) {
+            // phi2: a whole clock period, so one bit, in every cycle
+            self.drive_shift_clock(Signal::Fall);
+            self.drive_shift_clock(Signal::Rise);
+            return;
+        }
+        self.sr_countdown -= 1;
+        if self.sr_countdown == 0 {
+            self.sr_countdown = self.sr_half_period();
+            self.drive_shift_clock(match self.cb1 {
+                Signal::Rise => Signal::Fall,
+                Signal::Fall => Signal::Rise,
+            });
+        }
+    }
+
+    fn drive_shift_clock(&mut self, edge: Signal) {
+        self.cb1 = edge;
+        self.notify(Port::B, |peripheral| peripheral.clock_output(edge));
+        self.shift_clock(edge);
+    }
+
+    // shift out changes CB2 on falling edges, bits are taken on rising edges
+    fn shift_clock(&mut self, edge: Signal) {
+        if !self.sr_running {
+            return;
+        }
+        let shift_out = self.sr_mode() & 0b100 != 0;
+        match edge {
+            Signal::Fall if shift_out => {
+                let bit = self.sr & 0x80 != 0;
+                self.sr = self.sr.rotate_left(1);
+                self.drive_c2(Port::B, if bit { Signal::Rise } else { Signal::Fall });
+            }
+            Signal::Fall => (),
+            Signal::Rise => {
+                if !shift_out {
+                    self.sr = (self.sr << 1) | (self.cb2 == Signal::Rise) as u8;
+                }
+                self.sr_bits += 1;
+                if self.sr_bits == 8 {
+                    if self.sr_mode() == 0b100 {
+                        self.sr_bits = 0; // free-running, no interrupt
+                    } else {
+                        self.sr_running = false;
+                        self.set_flags(IRQ_SR);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl InternalAddressing for MOS6522 {
+    fn int_read(&mut self, addr: u16) -> u8 {
+        self.process_input();
+
+        let data = self.int_peek(addr);
+
+        match addr & 0x0F {
+            0x0 => {
+                self.port_access(Port::B);
+                self.notify(Port::B, |peripheral| peripheral.port_read(data));
+            }
+            0x1 => {
+                self.port_access(Port::A);
+                self.start_strobe(Port::A);
+                self.notify(Port::A, |peripheral| peripheral.port_read(data));
+            }
+            0x4 => self.clear_flags(IRQ_T1),
+            0x8 => self.clear_flags(IRQ_T2),
+            0xA => self.start_shift(),
+            0xF => self.notify(Port::A, |peripheral| peripheral.port_read(data)),
+            _ => (),
+        }
+
+        data
+    }
+
+    // register contents as a read would return them, without clearing flags or starting strobes
+    fn int_peek(&self, addr: u16) -> u8 {
+        match addr & 0x0F {
+            0x0 => self.port_b(),
+            0x1 | 0xF => self.port_a(),
+            0x2 => self.ddrb,
+            0x3 => self.ddra,
+            0x4 => self.t1_counter as u8,
+            0x5 => (self.t1_counter >> 8) as u8,
+            0x6 => self.t1_latch as u8,
+            0x7 => (self.t1_latch >> 8) as u8,
+            0x8 => self.t2_counter as u8,
+            0x9 => (self.t2_counter >> 8) as u8,
+            0xA => self.sr,
+            0xB => self.acr,
+            0xC => self.pcr,
+            0xD => self.ifr_with_irq_bit(),
+            _ => self.ier | 0x80,
+        }
+    }
+
+    // set registers without output, strobes, starting timers or interrupt signalling
+    fn int_poke(&mut self, addr: u16, data: u8) {
+        match addr & 0x0F {
+            0x0 => self.orb = data,
+            0x1 | 0xF => self.ora = data,
+            0x2 => self.ddrb = data,
+            0x3 => self.ddra = data,
+            0x4 | 0x6 => self.t1_latch = (self.t1_latch & 0xFF00) | data as u16,
+            0x5 | 0x7 => self.t1_latch = (self.t1_latch & 0x00FF) | (data as u16) << 8,
+            0x8 => self.t2_latch_low = data,
+            0x9 => self.t2_counter = (self.t2_counter & 0x00FF) | (data as u16) << 8,
+            0xA => self.sr = data,
+            0xB => self.acr = data,
+            0xC => self.pcr = data,
+            0xD => self.ifr = data & 0x7F,
+            _ => self.ier = data & 0x7F,
+        }
+    }
+
+    fn int_write(&mut self, addr: u16, data: u8) {
+        match addr & 0x0F {
+            // ORB
+            0x0 => {
+                self.orb = data;
+                self.port_access(Port::B);
+                self.start_strobe(Port::B);
+                self.port_b_output_changed();
+            }
+
+            // ORA, with and without handshake
+            0x1 | 0xF => {
+                self.ora = data;
+                if addr & 0x0F == 0x1 {
+                    self.port_access(Port::A);
+                    self.start_strobe(Port::A);
+                }
+                let out = self.port_a();
+                self.notify(Port::A, |peripheral| peripheral.port_written(out));
+            }
+
+            0x2 => self.ddrb = data,
+            0x3 => self.ddra = data,
+
+            // T1 latch low
+            0x4 | 0x6 => self.t1_latch = (self.t1_latch & 0xFF00) | data as u16,
+
+            // T1 counter high: load and start
+            0x5 => {
+                self.t1_latch = (self.t1_latch & 0x00FF) | (data as u16) << 8;
+                self.t1_counter = self.t1_latch;
+                self.t1_armed = true;
+                self.t1_reload = false;
+                self.clear_flags(IRQ_T1);
+                if self.acr & 0x80 != 0 {
+                    self.pb7 = false;
+                    self.port_b_output_changed();
+                }
+            }
+
+            // T1 latch high
+            0x7 => {
+                self.t1_latch = (self.t1_latch & 0x00FF) | (data as u16) << 8;
+                self.clear_flags(IRQ_T1);
+            }
+
+            // T2 latch low
+            0x8 => self.t2_latch_low = data,
+
+            // T2 counter high: load and start
+            0x9 => {
+                self.t2_counter = (data as u16) << 8 | self.t2_latch_low as u16;
+                self.t2_armed = true;
+                self.clear_flags(IRQ_T2);
+            }
+
+            0xA => {
+                self.sr = data;
+                self.start_shift();
+            }
+
+            0xB => {
+                let pb7_output = self.acr & 0x80;
+                self.acr = data;
+                if self.acr & 0x80 != pb7_output {
+                    self.port_b_output_changed();
+                }
+            }
+
+            0xC => {
+                self.pcr = data;
+                self.update_pcr();
+            }
+
+            // IFR: ones clear flags
+            0xD => self.clear_flags(data & 0x7F),
+
+            // IER: bit 7 selects set or clear
+            _ => {
+                if data & 0x80 != 0 {
+                    self.ier |= data & 0x7F;
+                } else {
+                    self.ier &= !data;
+                }
+                self.update_irq();
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        16
+    }
+
+    fn tick(&mut self) {
+        for (_, peripheral) in self.peripherals.iter_mut() {
+            peripheral.tick();
+        }
+        self.process_input();
+        self.clock_strobe(Port::A);
+        self.clock_strobe(Port::B);
+        self.clock_t1();
+        self.clock_t2();
+        self.clock_sr();
+    }
+
+    fn describe(&self) -> String {
+        "MOS6522 VIA".to_string()
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        vec![
+            self.ora,
+            self.ira,
+            self.ddra,
+            self.orb,
+            self.irb,
+            self.ddrb,
+            self.t1_counter as u8,
+            (self.t1_counter >> 8) as u8,
+            self.t1_latch as u8,
+            (self.t1_latch >> 8) as u8,
+            self.t2_counter as u8,
+            (self.t2_counter >> 8) as u8,
+            self.t2_latch_low,
+            self.sr,
+            self.acr,
+            self.pcr,
+            self.ifr,
+            self.ier,
+            self.pb7 as u8,
+            self.ira_latch,
+            self.irb_latch,
+            self.ca1 as u8,
+            self.ca2 as u8,
+            self.cb1 as u8,
+            self.cb2 as u8,
+            self.ca2_strobe as u8,
+            self.cb2_strobe as u8,
+            self.t1_armed as u8,
+            self.t1_reload as u8,
+            self.t2_armed as u8,
+            self.sr_bits,
+            self.sr_running as u8,
+            self.sr_countdown as u8,
+            (self.sr_countdown >> 8) as u8,
+        ]
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), AddressingError> {
+        if state.len() != 34 {
+            return Err(AddressingError::new("load_state", 0));
+        }
+
+        self.ora = state[0];
+        self.ira = state[1];
+        self.ddra = state[2];
+        self.orb = state[3];
+        self.irb = state[4];
+        self.ddrb = state[5];
+        self.t1_counter = u16::from_le_bytes([state[6], state[7]]);
+        self.t1_latch = u16::from_le_bytes([state[8], state[9]]);
+        self.t2_counter = u16::from_le_bytes([state[10], state[11]]);
+        self.t2_latch_low = state[12];
+        self.sr = state[13];
+        self.acr = state[14];
+        self.pcr = state[15];
+        self.ifr = state[16];
+        self.ier = state[17];
+        self.pb7 = state[18] != 0;
+        let signal = |b: u8| if b == 0 { Signal::Fall } else { Signal::Rise };
+        self.ira_latch = state[19];
+        self.irb_latch = state[20];
+        self.ca1 = signal(state[21]);
+        self.ca2 = signal(state[22]);
+        self.cb1 = signal(state[23]);
+        self.cb2 = signal(state[24]);
+        self.ca2_strobe = Strobe::from_state(state[25]);
+        self.cb2_strobe = Strobe::from_state(state[26]);
+        self.t1_armed = state[27] != 0;
+        self.t1_reload = state[28] != 0;
+        self.t2_armed = state[29] != 0;
+        self.sr_bits = state[30];
+        self.sr_running = state[31] != 0;
+        // a running shift is always between 1 and a half period away from its next edge
+        let sr_countdown = u16::from_le_bytes([state[32], state[33]]);
+        self.sr_countdown = if self.sr_running {
+            sr_countdown.clamp(1, self.sr_half_period())
+        } else {
+            sr_countdown
+        };
+        self.update_irq();
+        Ok(())
+    }
+}
@@ -0,0 +1,341 @@
+#[cfg(test)]
+use super::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+const ORB: u16 = 0x0;
+const ORA: u16 = 0x1;
+const DDRB: u16 = 0x2;
+const T1CL: u16 = 0x4;
+const T1CH: u16 = 0x5;
+const T2CL: u16 = 0x8;
+const T2CH: u16 = 0x9;
+const SR: u16 = 0xA;
+const ACR: u16 = 0xB;
+const PCR: u16 = 0xC;
+const IFR: u16 = 0xD;
+const IER: u16 = 0xE;
+
+fn ticks(via: &mut MOS6522, n: usize) {
+    for _ in 0..n {
+        via.tick();
+    }
+}
+
+#[derive(Default)]
+struct Lines {
+    port_b: Vec<u8>,
+    c2: Vec<Signal>,
+    clock: Vec<Signal>,
+}
+
+impl PiaPeripheral for Rc<RefCell<Lines>> {
+    fn port_written(&mut self, data: u8) {
+        self.borrow_mut().port_b.push(data);
+    }
+
+    fn control_output(&mut self, signal: Signal) {
+        self.borrow_mut().c2.push(signal);
+    }
+
+    fn clock_output(&mut self, signal: Signal) {
+        self.borrow_mut().clock.push(signal);
+    }
+}
+
+#[test]
+fn timer1_one_shot_interrupts_once() {
+    // arrange
+    let line = InterruptLine::new();
+    let mut via = MOS6522::new();
+    via.connect_irq(&line);
+    via.int_write(IER, 0x80 | IRQ_T1);
+    via.int_write(T1CL, 10);
+
+    // act
+    via.int_write(T1CH, 0);
+    ticks(&mut via, 10);
+    let before_timeout = line.is_active();
+    via.tick();
+    let at_timeout = via.int_peek(IFR);
+    via.int_read(T1CL);
+    let after_read = line.is_active();
+    ticks(&mut via, 0x10010);
+
+    // assert
+    assert!(!before_timeout);
+    assert_eq!(at_timeout, 0x80 | IRQ_T1);
+    assert!(!after_read);
+    assert_eq!(via.int_peek(IFR), 0x00); // not rearmed without writing T1C-H
+}
+
+#[test]
+fn timer1_free_run_toggles_pb7() {
+    // arrange
+    let lines = Rc::new(RefCell::new(Lines::default()));
+    let mut via = MOS6522::new();
+    via.attach_peripheral(Port::B, Box::new(lines.clone()));
+    via.int_write(ACR, 0xC0); // free-run, PB7 output
+    via.int_write(T1CL, 4);
+
+    // act
+    via.int_write(T1CH, 0);
+    let mut pb7 = vec![via.int_peek(ORB) & 0x80];
+    for _ in 0..3 {
+        ticks(&mut via, 6); // latch + 2
+        pb7.push(via.int_peek(ORB) & 0x80);
+    }
+
+    // assert
+    assert_eq!(pb7, vec![0x00, 0x80, 0x00, 0x80]);
+    assert_eq!(via.int_peek(IFR) & IRQ_T1, IRQ_T1);
+    assert_eq!(lines.borrow().port_b.len(), 1 + 1 + 3);
+}
+
+#[test]
+fn timer2_one_shot_and_pulse_counting() {
+    // arrange
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let mut one_shot = MOS6522::new();
+    let mut counting = MOS6522::new();
+    counting.set_input_channel(rx);
+    counting.int_write(ACR, 0x20); // count PB6 pulses
+
+    // act
+    one_shot.int_write(T2CL, 3);
+    one_shot.int_write(T2CH, 0);
+    ticks(&mut one_shot, 3);
+    let one_shot_early = one_shot.int_peek(IFR) & IRQ_T2;
+    one_shot.tick();
+
+    counting.int_write(T2CL, 2);
+    counting.int_write(T2CH, 0);
+    for _ in 0..2 {
+        tx.send(InputSignal::IRB(0xBF)).unwrap();
+        tx.send(InputSignal::IRB(0xFF)).unwrap();
+    }
+    ticks(&mut counting, 1);
+
+    // assert
+    assert_eq!(one_shot_early, 0);
+    assert_eq!(one_shot.int_peek(IFR) & IRQ_T2, IRQ_T2);
+    assert_eq!(counting.int_peek(IFR) & IRQ_T2, IRQ_T2);
+    assert_eq!(counting.int_read(T2CL), 0);
+}
+
+#[test]
+fn shift_register_shifts_out_under_phi2() {
+    // arrange
+    let lines = Rc::new(RefCell::new(Lines::default()));
+    let mut via = MOS6522::new();
+    via.attach_peripheral(Port::B, Box::new(lines.clone()));
+    via.int_write(ACR, 0x18); // shift out under phi2
+
+    // act
+    via.int_write(SR, 0b1010_0110);
+    ticks(&mut via, 7);
+    let before_last_bit = via.int_peek(IFR) & IRQ_SR;
+    ticks(&mut via, 1);
+
+    // assert: CB2 follows the bits, MSB first, CB1 clocked once per cycle
+    let levels: Vec<u8> = lines
+        .borrow()
+        .c2
+        .iter()
+        .map(|s| (*s == Signal::Rise) as u8)
+        .collect();
+    assert_eq!(levels, vec![0, 1, 0, 1, 0]); // changes of 1,0,1,0,0,1,1,0 from high
+    assert_eq!(lines.borrow().clock.len(), 16);
+    assert_eq!(before_last_bit, 0);
+    assert_eq!(via.int_peek(IFR) & IRQ_SR, IRQ_SR);
+    assert_eq!(via.int_peek(SR), 0b1010_0110); // rotated all the way round
+}
+
+#[test]
+fn shift_register_shifts_in_with_external_clock() {
+    // arrange
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let mut via = MOS6522::new();
+    via.set_input_channel(rx);
+    via.int_write(ACR, 0x0C); // shift in under CB1 control
+    via.int_read(SR);
+
+    // act
+    for bit in [1, 1, 0, 0, 1, 0, 1, 1] {
+        let data = if bit == 1 { Signal::Rise } else { Signal::Fall };
+        tx.send(InputSignal::CB2(data)).unwrap();
+        tx.send(InputSignal::CB1(Signal::Fall)).unwrap();
+        tx.send(InputSignal::CB1(Signal::Rise)).unwrap();
+    }
+    via.tick();
+
+    // assert
+    assert_eq!(via.int_peek(IFR) & IRQ_SR, IRQ_SR);
+    assert_eq!(via.int_read(SR), 0b1100_1011);
+    assert_eq!(via.int_peek(IFR) & IRQ_SR, 0);
+}
+
+#[test]
+fn interrupt_enable_and_flag_registers() {
+    // arrange
+    let mut via = MOS6522::new();
+    via.int_write(T1CL, 0);
+    via.int_write(T1CH, 0);
+    ticks(&mut via, 1);
+
+    // act
+    let flagged_not_enabled = via.int_peek(IFR);
+    via.int_write(IER, 0x80 | IRQ_T1 | IRQ_CA1);
+    let enabled = (via.int_peek(IER), via.int_peek(IFR), via.irq());
+    via.int_write(IER, IRQ_CA1);
+    let ier_after_clear = via.int_peek(IER);
+    via.int_write(IFR, IRQ_T1);
+
+    // assert
+    assert_eq!(flagged_not_enabled, IRQ_T1);
+    assert_eq!(enabled, (0x80 | IRQ_T1 | IRQ_CA1, 0x80 | IRQ_T1, true));
+    assert_eq!(ier_after_clear, 0x80 | IRQ_T1);
+    assert_eq!(via.int_peek(IFR), 0);
+    assert!(!via.irq());
+}
+
+#[test]
+fn ca1_latches_port_a_and_ends_ca2_handshake() {
+    // arrange
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let mut via = MOS6522::new();
+    via.set_input_channel(rx);
+    via.int_write(ACR, 0x01); // latch port A
+    via.int_write(PCR, 0x09); // CA2 handshake output, CA1 positive edge
+
+    // act
+    tx.send(InputSignal::IRA(0x41)).unwrap();
+    tx.send(InputSignal::CA1(Signal::Fall)).unwrap();
+    tx.send(InputSignal::CA1(Signal::Rise)).unwrap();
+    tx.send(InputSignal::IRA(0x42)).unwrap();
+    via.tick();
+    let flags = via.int_peek(IFR);
+    let latched = via.int_read(ORA);
+    via.tick();
+    let ca2_after_read = via.get_ca2();
+    tx.send(InputSignal::CA1(Signal::Fall)).unwrap();
+    tx.send(InputSignal::CA1(Signal::Rise)).unwrap();
+    via.tick();
+
+    // assert
+    assert_eq!(flags, IRQ_CA1);
+    assert_eq!(latched, 0x41);
+    assert_eq!(ca2_after_read, Signal::Fall);
+    assert_eq!(via.get_ca2(), Signal::Rise);
+}
+
+#[test]
+fn cb2_pulse_after_port_b_write() {
+    // arrange
+    let mut via = MOS6522::new();
+    via.int_write(DDRB, 0xFF);
+    via.int_write(PCR, 0xA0); // CB2 pulse output
+
+    // act
+    via.int_write(ORB, 0x55);
+    let after_write = via.get_cb2();
+    via.tick();
+    let pulse = via.get_cb2();
+    via.tick();
+
+    // assert
+    assert_eq!(after_write, Signal::Rise);
+    assert_eq!(pulse, Signal::Fall);
+    assert_eq!(via.get_cb2(), Signal::Rise);
+    assert_eq!(via.int_peek(ORB), 0x55);
+}
+
+#[test]
+fn save_and_load_state() {
+    // arrange
+    let mut via = MOS6522::new();
+    via.int_write(DDRB, 0x0F);
+    via.int_write(ORB, 0x05);
+    via.int_write(T1CL, 0x34);
+    via.int_write(T1CH, 0x12);
+    via.int_write(IER, 0x80 | IRQ_T2);
+    let state = via.save_state();
+
+    // act
+    let mut restored = MOS6522::new();
+    let actual = restored.load_state(&state);
+
+    // assert
+    assert!(actual.is_ok());
+    assert_eq!(restored.int_peek(T1CH), 0x12);
+    assert_eq!(restored.int_peek(ORB) & 0x0F, 0x05);
+    assert_eq!(restored.save_state(), state);
+}
+
+#[test]
+fn save_and_load_state_during_one_shot_timer() {
+    // arrange
+    let mut via = MOS6522::new();
+    via.int_write(T1CL, 0x10);
+    via.int_write(T1CH, 0x00);
+    ticks(&mut via, 8);
+    let state = via.save_state();
+
+    // act
+    let mut restored = MOS6522::new();
+    restored.load_state(&state).unwrap();
+    ticks(&mut restored, 8);
+    let before_timeout = restored.int_peek(IFR) & IRQ_T1;
+    ticks(&mut restored, 1);
+
+    // assert: armed, so the remaining half of the count still flags the timeout
+    assert_eq!(before_timeout, 0);
+    assert_eq!(restored.int_peek(IFR) & IRQ_T1, IRQ_T1);
+}
+
+#[test]
+fn save_and_load_state_during_shift() {
+    // arrange
+    let mut via = MOS6522::new();
+    via.int_write(T2CL, 0x00); // CB1 edge every other cycle
+    via.int_write(ACR, 0x14); // shift out under timer 2
+    via.int_write(SR, 0b1100_0011);
+    ticks(&mut via, 9); // 2 bits of 8, halfway through the next clock period
+    let state = via.save_state();
+
+    // act
+    let mut restored = MOS6522::new();
+    restored.load_state(&state).unwrap();
+    ticks(&mut via, 22);
+    ticks(&mut restored, 22);
+    let before_last_bit = restored.int_peek(IFR) & IRQ_SR;
+    ticks(&mut via, 1);
+    ticks(&mut restored, 1);
+
+    // assert
+    assert_eq!(before_last_bit, 0);
+    assert_eq!(restored.int_peek(IFR) & IRQ_SR, IRQ_SR);
+    assert_eq!(restored.int_peek(SR), 0b1100_0011);
+    assert_eq!(restored.save_state(), via.save_state());
+}
+
+#[test]
+fn load_state_clamps_shift_countdown() {
+    // arrange: a running shift with no cycles left to its next edge
+    let mut via = MOS6522::new();
+    via.int_write(T2CL, 0x00);
+    via.int_write(ACR, 0x14);
+    via.int_write(SR, 0b1100_0011);
+    let mut state = via.save_state();
+    state[32] = 0;
+    state[33] = 0;
+
+    // act
+    let mut restored = MOS6522::new();
+    restored.load_state(&state).unwrap();
+    let cb1 = restored.cb1;
+    ticks(&mut restored, 1);
+
+    // assert: the edge comes in the next cycle
+    assert_ne!(restored.cb1, cb1);
+}
@@ -0,0 +1,380 @@
+#[cfg(test)]
+mod tests;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crossbeam_channel::{Receiver, Sender};
+
+use crate::address_bus::{AddressingError, InternalAddressing};
+use crate::interrupt::{InterruptLine, InterruptOutput};
+use crate::mc6821::{ChannelPeripheral, InputSignal, PiaPeripheral, Port};
+
+// interrupt flag register bits
+pub const IRQ_TIMER: u8 = 0x80;
+pub const IRQ_PA7: u8 = 0x40;
+
+pub const RAM_SIZE: usize = 128;
+
+/// I/O ports, interval timer and edge detection of a 6532 RIOT; its RAM is a separate
+/// component (`ram`), as the chip selects it with its own RS input.
+pub struct MOS6532 {
+    ora: u8,  // output register A
+    ira: u8,  // input pins A
+    ddra: u8, // data direction register A (Output=1, Input=0)
+    orb: u8,  // output register B
+    irb: u8,  // input pins B
+    ddrb: u8, // data direction register B (Output=1, Input=0)
+
+    timer: u8,
+    prescale: u16,   // 1, 8, 64 or 1024 cycles per count
+    countdown: u16,  // cycles to the next count
+    underflow: bool, // counting every cycle since passing 0
+
+    pa7: bool,          // level on PA7 at the last check
+    pa7_positive: bool, // edge detection on a positive transition
+    pa7_irq: bool,
+    timer_irq: bool,
+    flags: u8,
+
+    ram: Rc<RefCell<[u8; RAM_SIZE]>>,
+    peripherals: Vec<(Port, Box<dyn PiaPeripheral>)>,
+    irq: InterruptOutput,
+}
+
+impl Default for MOS6532 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MOS6532 {
+    pub fn new() -> MOS6532 {
+        MOS6532 {
+            ora: 0,
+            ira: 0xFF,
+            ddra: 0,
+            orb: 0,
+            irb: 0xFF,
+            ddrb: 0,
+
+            timer: 0xFF,
+            prescale: 1024,
+            countdown: 1024,
+            underflow: false,
+
+            pa7: true,
+            pa7_positive: false,
+            pa7_irq: false,
+            timer_irq: false,
+            flags: 0,
+
+            ram: Rc::new(RefCell::new([0; RAM_SIZE])),
+            peripherals: vec![],
+            irq: InterruptOutput::default(),
+        }
+    }
+
+    /// The 128 bytes of RAM, to be mapped where the chip's RS input selects them.
+    pub fn ram(&self) -> RiotRam {
+        RiotRam {
+            ram: self.ram.clone(),
+        }
+    }
+
+    /// Attach a device to a port; several devices can share a port.
+    pub fn attach_peripheral(&mut self, port: Port, peripheral: Box<dyn PiaPeripheral>) {
+        self.peripherals.push((port, peripheral));
+    }
+
+    pub fn set_input_channel(&mut self, rx: Receiver<InputSignal>) {
+        self.attach_peripheral(Port::A, Box::new(ChannelPeripheral::new(Some(rx), None)));
+    }
+
+    pub fn set_output_channel_a(&mut self, tx: Sender<u8>) {
+        self.attach_peripheral(Port::A, Box::new(ChannelPeripheral::new(None, Some(tx))));
+    }
+
+    pub fn set_output_channel_b(&mut self, tx: Sender<u8>) {
+        self.attach_peripheral(Port::B, Box::new(ChannelPeripheral::new(None, Some(tx))));
+    }
+
+    pub fn connect_irq(&mut self, line: &InterruptLine) {
+        self.irq.connect(line);
+    }
+
+    /// Level of the IRQ output.
+    pub fn irq(&self) -> bool {
+        self.irq.is_active()
+    }
+
+    /// Take pending input pin changes from all peripherals; also done on every `tick`.
+    /// Control line signals have no pins on the 6532 and are ignored.
+    pub fn process_input(&mut self) {
+        for i in 0..self.peripherals.len() {
+            while let Some(input) = self.peripherals[i].1.poll() {
+                match input {
+                    InputSignal::IRA(b) => {
+                        self.ira = b;
+                        self.check_pa7();
+                    }
+                    InputSignal::IRB(b) => self.irb = b,
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    fn notify(&mut self, port: Port, mut f: impl FnMut(&mut dyn PiaPeripheral)) {
+        for (_, peripheral) in self.peripherals.iter_mut().filter(|(p, _)| *p == port) {
+            f(peripheral.as_mut());
+        }
+    }
+
+    fn update_irq(&mut self) {
+        let timer = self.timer_irq && self.flags & IRQ_TIMER != 0;
+        let pa7 = self.pa7_irq && self.flags & IRQ_PA7 != 0;
+        self.irq.set(timer || pa7);
+    }
+
+    fn port_a(&self) -> u8 {
+        (self.ora & self.ddra) | (self.ira & !self.ddra)
+    }
+
+    fn port_b(&self) -> u8 {
+        (self.orb & self.ddrb) | (self.irb & !self.ddrb)
+    }
+
+    fn port_output_changed(&mut self, port: Port) {
+        let data = match port {
+            Port::A => self.port_a(),
+            Port::B => self.port_b(),
+        };
+        self.notify(port, |peripheral| peripheral.port_written(data));
+        if port == Port::A {
+            self.check_pa7();
+        }
+    }
+
+    // PA7 edge detection, on the pin as input or output
+    fn check_pa7(&mut self) {
+        let pa7 = self.port_a() & 0x80 != 0;
+        if pa7 != self.pa7 && pa7 == self.pa7_positive {
+            self.flags |= IRQ_PA7;
+            self.update_irq();
+        }
+        self.pa7 = pa7;
+    }
+
+    fn clock_timer(&mut self) {
+        self.countdown -= 1;
+        if self.countdown > 0 {
+            return;
+        }
+
+        if self.timer == 0 {
+            self.underflow = true;
+            self.flags |= IRQ_TIMER;
+            self.update_irq();
+        }
+        self.timer = self.timer.wrapping_sub(1);
+        self.countdown = if self.underflow { 1 } else { self.prescale };
+    }
+}
+
+impl InternalAddressing for MOS6532 {
+    fn int_read(&mut self, addr: u16) -> u8 {
+        self.process_input();
+
+        let data = self.int_peek(addr);
+
+        if addr & 0x04 == 0 {
+            match addr & 0x03 {
+                0x0 => self.notify(Port::A, |peripheral| peripheral.port_read(data)),
+                0x2 => self.notify(Port::B, |peripheral| peripheral.port_read(data)),
+                _ => (),
+            }
+        } else if addr & 0x01 == 0 {
+            // timer, A3 sets the timer interrupt enable
+            self.timer_irq = addr & 0x08 != 0;
+            self.flags &= !IRQ_TIMER;
+            self.update_irq();
+        } else {
+            self.flags &= !IRQ_PA7;
+            self.update_irq();
+        }
+
+        data
+    }
+
+    fn int_peek(&self, addr: u16) -> u8 {
+        if addr & 0x04 == 0 {
+            match addr & 0x03 {
+                0x0 => self.port_a(),
+                0x1 => self.ddra,
+                0x2 => self.port_b(),
+                _ => self.ddrb,
+            }
+        } else if addr & 0x01 == 0 {
+            self.timer
+        } else {
+            self.flags
+        }
+    }
+
+    fn int_poke(&mut self, addr: u16, data: u8) {
+        if addr & 0x04 == 0 {
+            match addr & 0x03 {
+                0x0 => self.ora = data,
+                0x1 => self.ddra = data,
+                0x2 => self.orb = data,
+                _ => self.ddrb = data,
+            }
+        } else if addr & 0x10 != 0 {
+            self.timer = data;
+        } else {
+            self.flags = data & (IRQ_TIMER | IRQ_PA7);
+        }
+    }
+
+    fn int_write(&mut self, addr: u16, data: u8) {
+        if addr & 0x04 == 0 {
+            match addr & 0x03 {
+                0x0 => {
+                    self.ora = data;
+                    self.port_output_changed(Port::A);
+                }
+                0x1 => {
+                    self.ddra = data;
+                    self.port_output_changed(Port::A);
+                }
+                0x2 => {
+                    self.orb = data;
+                    self.port_output_changed(Port::B);
+                }
+                _ => {
+                    self.ddrb = data;
+                    self.port_output_changed(Port::B);
+                }
+            }
+        } else if addr & 0x10 != 0 {
+            // timer: A0-A1 select the prescaler, A3 the interrupt enable
+            self.timer = data;
+            self.prescale = [1, 8, 64, 1024][(addr & 0x03) as usize];
+            self.countdown = 1; // first count on the next cycle
+            self.underflow = false;
+            self.timer_irq = addr & 0x08 != 0;
+            self.flags &= !IRQ_TIMER;
+            self.update_irq();
+        } else {
+            // edge detect control: A0 positive edge, A1 interrupt enable
+            self.pa7_positive = addr & 0x01 != 0;
+            self.pa7_irq = addr & 0x02 != 0;
+            self.update_irq();
+        }
+    }
+
+    fn len(&self) -> usize {
+        32
+    }
+
+    fn tick(&mut self) {
+        for (_, peripheral) in self.peripherals.iter_mut() {
+            peripheral.tick();
+        }
+        self.process_input();
+        self.clock_timer();
+    }
+
+    fn describe(&self) -> String {
+        "MOS6532 RIOT".to_string()
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        let [prescale_low, prescale_high] = self.prescale.to_le_bytes();
+        let [countdown_low, countdown_high] = self.countdown.to_le_bytes();
+        vec![
+            self.ora,
+            self.ira,
+            self.ddra,
+            self.orb,
+            self.irb,
+            self.ddrb,
+            self.timer,
+            prescale_low,
+            prescale_high,
+            countdown_low,
+            countdown_high,
+            self.underflow as u8,
+            self.pa7_positive as u8 | (self.pa7_irq as u8) << 1 | (self.timer_irq as u8) << 2,
+            self.flags,
+        ]
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), AddressingError> {
+        if state.len() != 14 {
+            return Err(AddressingError::new("load_state", 0));
+        }
+
+        self.ora = state[0];
+        self.ira = state[1];
+        self.ddra = state[2];
+        self.orb = state[3];
+        self.irb = state[4];
+        self.ddrb = state[5];
+        self.timer = state[6];
+        self.prescale = u16::from_le_bytes([state[7], state[8]]);
+        self.countdown = u16::from_le_bytes([state[9], state[10]]).max(1);
+        self.underflow = state[11] != 0;
+        self.pa7_positive = state[12] & 0x01 != 0;
+        self.pa7_irq = state[12] & 0x02 != 0;
+        self.timer_irq = state[12] & 0x04 != 0;
+        self.flags = state[13];
+        self.pa7 = self.port_a() & 0x80 != 0;
+        self.update_irq();
+        Ok(())
+    }
+}
+
+/// RAM of a `MOS6532`, addressed by the low 7 address lines.
+pub struct RiotRam {
+    ram: Rc<RefCell<[u8; RAM_SIZE]>>,
+}
+
+impl InternalAddressing for RiotRam {
+    fn int_read(&mut self, addr: u16) -> u8 {
+        self.int_peek(addr)
+    }
+
+    fn int_write(&mut self, addr: u16, data: u8) {
+        self.ram.borrow_mut()[addr as usize % RAM_SIZE] = data;
+    }
+
+    fn int_peek(&self, addr: u16) -> u8 {
+        self.ram.borrow()[addr as usize % RAM_SIZE]
+    }
+
+    fn int_poke(&mut self, addr: u16, data: u8) {
+        self.int_write(addr, data)
+    }
+
+    fn len(&self) -> usize {
+        RAM_SIZE
+    }
+
+    fn describe(&self) -> String {
+        "MOS6532 RIOT RAM".to_string()
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        self.ram.borrow().to_vec()
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), AddressingError> {
+        if state.len() != RAM_SIZE {
+            return Err(AddressingError::new("load_state", 0));
+        }
+        self.ram.borrow_mut().copy_from_slice(state);
+        Ok(())
+    }
+}
@@ -0,0 +1,154 @@
+#[cfg(test)]
+use super::*;
+use crate::address_bus::{AddressBus, ExternalAddressing};
+
+const DRA: u16 = 0x00;
+const DDRA: u16 = 0x01;
+const DRB: u16 = 0x02;
+const DDRB: u16 = 0x03;
+const READ_TIMER: u16 = 0x04;
+const READ_TIMER_IRQ: u16 = 0x0C;
+const READ_FLAGS: u16 = 0x05;
+const EDGE_NEGATIVE_IRQ: u16 = 0x06;
+const EDGE_POSITIVE_IRQ: u16 = 0x07;
+const TIMER_8_IRQ: u16 = 0x1D;
+const TIMER_1024: u16 = 0x17;
+
+fn ticks(riot: &mut MOS6532, n: usize) {
+    for _ in 0..n {
+        riot.tick();
+    }
+}
+
+#[test]
+fn timer_interrupts_after_count_times_prescale() {
+    // arrange
+    let line = InterruptLine::new();
+    let mut riot = MOS6532::new();
+    riot.connect_irq(&line);
+
+    // act
+    riot.int_write(TIMER_8_IRQ, 2);
+    ticks(&mut riot, 1);
+    let after_first_cycle = riot.int_peek(READ_TIMER);
+    ticks(&mut riot, 15);
+    let before_underflow = (riot.int_peek(READ_TIMER), line.is_active());
+    ticks(&mut riot, 1);
+    let at_underflow = (riot.int_peek(READ_TIMER), riot.int_peek(READ_FLAGS));
+    ticks(&mut riot, 3);
+    let counting_every_cycle = riot.int_read(READ_TIMER_IRQ);
+
+    // assert
+    assert_eq!(after_first_cycle, 1);
+    assert_eq!(before_underflow, (0, false));
+    assert_eq!(at_underflow, (0xFF, IRQ_TIMER));
+    assert_eq!(counting_every_cycle, 0xFC);
+    assert_eq!(riot.int_peek(READ_FLAGS), 0);
+    assert!(!line.is_active());
+}
+
+#[test]
+fn timer_prescalers() {
+    // arrange
+    let mut riot = MOS6532::new();
+    let mut counts = vec![];
+
+    // act
+    for (addr, prescale) in [(0x14, 1), (0x15, 8), (0x16, 64), (TIMER_1024, 1024)] {
+        riot.int_write(addr, 10);
+        ticks(&mut riot, 1 + 3 * prescale);
+        counts.push(riot.int_peek(READ_TIMER));
+    }
+
+    // assert
+    assert_eq!(counts, vec![6, 6, 6, 6]);
+    assert!(!riot.irq()); // timer interrupt not enabled
+}
+
+#[test]
+fn pa7_edge_detection() {
+    // arrange
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let mut riot = MOS6532::new();
+    riot.set_input_channel(rx);
+    riot.int_write(EDGE_POSITIVE_IRQ, 0);
+
+    // act
+    tx.send(InputSignal::IRA(0x7F)).unwrap();
+    riot.tick();
+    let falling = riot.irq();
+    tx.send(InputSignal::IRA(0xFF)).unwrap();
+    riot.tick();
+    let rising = (riot.irq(), riot.int_read(READ_FLAGS));
+    riot.int_write(EDGE_NEGATIVE_IRQ, 0);
+    riot.int_write(DDRA, 0x80);
+    let driven_low = riot.irq();
+
+    // assert
+    assert!(!falling);
+    assert_eq!(rising, (true, IRQ_PA7));
+    assert!(driven_low);
+}
+
+#[test]
+fn ports_combine_output_and_input_pins() {
+    // arrange
+    let (tx_input, rx_input) = crossbeam_channel::unbounded();
+    let (tx_output, rx_output) = crossbeam_channel::unbounded();
+    let mut riot = MOS6532::new();
+    riot.set_input_channel(rx_input);
+    riot.set_output_channel_b(tx_output);
+
+    // act
+    riot.int_write(DDRB, 0xF0);
+    riot.int_write(DRB, 0xA5);
+    tx_input.send(InputSignal::IRA(0x3C)).unwrap();
+    let port_a = riot.int_read(DRA);
+
+    // assert
+    assert_eq!(port_a, 0x3C);
+    assert_eq!(riot.int_read(DRB), 0xAF);
+    assert_eq!(rx_output.try_iter().collect::<Vec<_>>(), vec![0x0F, 0xAF]);
+}
+
+#[test]
+fn ram_and_io_mapped_separately() {
+    // arrange
+    let mut riot = MOS6532::new();
+    let mut ram = riot.ram();
+    let mut bus = AddressBus::new(0x10);
+    bus.add_component(0x0080, RAM_SIZE, &mut ram).unwrap();
+    bus.add_component(0x0280, 0x20, &mut riot).unwrap();
+
+    // act
+    bus.write(0x0080, 0x11).unwrap();
+    bus.write(0x00FF, 0x22).unwrap();
+    bus.write(0x0294, 0x40).unwrap(); // timer, divide by 1
+
+    // assert
+    assert_eq!(bus.read(0x0080).unwrap(), 0x11);
+    assert_eq!(bus.read(0x00FF).unwrap(), 0x22);
+    assert_eq!(bus.read(0x0284).unwrap(), 0x40);
+    assert!(bus.read(0x0100).is_err());
+}
+
+#[test]
+fn save_and_load_state() {
+    // arrange
+    let mut riot = MOS6532::new();
+    riot.int_write(DDRA, 0x0F);
+    riot.int_write(DRA, 0x05);
+    riot.int_write(TIMER_8_IRQ, 0);
+    ticks(&mut riot, 2);
+    let state = riot.save_state();
+
+    // act
+    let mut restored = MOS6532::new();
+    let actual = restored.load_state(&state);
+
+    // assert
+    assert!(actual.is_ok());
+    assert!(restored.irq());
+    assert_eq!(restored.int_peek(DRA) & 0x0F, 0x05);
+    assert_eq!(restored.save_state(), state);
+}
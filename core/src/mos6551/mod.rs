@@ -0,0 +1,269 @@
+#[cfg(test)]
+mod tests;
+
+use crate::address_bus::{AddressingError, InternalAddressing};
+use crate::interrupt::{InterruptLine, InterruptOutput};
+use crate::serial::SerialBackend;
+
+// status register bits
+pub const STATUS_PARITY_ERROR: u8 = 0x01;
+pub const STATUS_FRAMING_ERROR: u8 = 0x02;
+pub const STATUS_OVERRUN: u8 = 0x04;
+pub const STATUS_RDRF: u8 = 0x08; // receive data register full
+pub const STATUS_TDRE: u8 = 0x10; // transmit data register empty
+pub const STATUS_IRQ: u8 = 0x80;
+
+// baud rates selected by control bits 0-3; 0 is the external clock, taken as 115200 baud
+const BAUD_RATES: [f64; 16] = [
+    115200.0, 50.0, 75.0, 109.92, 134.58, 150.0, 300.0, 600.0, 1200.0, 1800.0, 2400.0, 3600.0,
+    4800.0, 7200.0, 9600.0, 19200.0,
+];
+
+pub struct MOS6551 {
+    rdr: u8,     // receive data register
+    tdr: u8,     // transmit data register
+    tsr: u8,     // transmit shift register
+    status: u8,  // status register
+    command: u8, // command register
+    control: u8, // control register
+
+    tsr_full: bool,
+    tx_countdown: u64, // cycles until the character in the shift register is sent
+    rx_countdown: u64, // cycles until the receiver takes the next character
+
+    clock_hz: u64,
+    backend: Box<dyn SerialBackend>,
+    irq: InterruptOutput,
+}
+
+impl MOS6551 {
+    /// An ACIA driven by a CPU clock of `clock_hz`, transmitting to and receiving from `backend`.
+    pub fn new(clock_hz: u64, backend: Box<dyn SerialBackend>) -> MOS6551 {
+        MOS6551 {
+            rdr: 0,
+            tdr: 0,
+            tsr: 0,
+            status: STATUS_TDRE,
+            command: 0x02,
+            control: 0,
+
+            tsr_full: false,
+            tx_countdown: 0,
+            rx_countdown: 0,
+
+            clock_hz,
+            backend,
+            irq: InterruptOutput::default(),
+        }
+    }
+
+    pub fn connect_irq(&mut self, line: &InterruptLine) {
+        self.irq.connect(line);
+    }
+
+    /// Level of the IRQ output.
+    pub fn irq(&self) -> bool {
+        self.irq.is_active()
+    }
+
+    /// CPU cycles one character takes on the line, start and stop bits included.
+    pub fn character_cycles(&self) -> u64 {
+        let data_bits = 8 - (self.control >> 5 & 0x03) as u64;
+        let parity_bits = (self.command >> 5 & 0x01) as u64;
+        let stop_bits = if self.control & 0x80 != 0 { 2 } else { 1 };
+        let bits = 1 + data_bits + parity_bits + stop_bits;
+        let baud = BAUD_RATES[(self.control & 0x0F) as usize];
+        ((self.clock_hz * bits) as f64 / baud).round().max(1.0) as u64
+    }
+
+    fn data_mask(&self) -> u8 {
+        0xFF >> (self.control >> 5 & 0x03)
+    }
+
+    // DTR off disables receiver, transmitter and interrupts
+    fn enabled(&self) -> bool {
+        self.command & 0x01 != 0
+    }
+
+    fn transmitter_enabled(&self) -> bool {
+        self.command & 0x0C != 0
+    }
+
+    fn tx_irq_enabled(&self) -> bool {
+        self.command & 0x0C == 0x04
+    }
+
+    fn rx_irq_enabled(&self) -> bool {
+        self.command & 0x02 == 0
+    }
+
+    // echo needs the transmitter off
+    fn echo(&self) -> bool {
+        self.command & 0x1C == 0x10
+    }
+
+    fn interrupt(&mut self) {
+        self.status |= STATUS_IRQ;
+        self.irq.set(true);
+    }
+
+    fn update_irq(&mut self) {
+        self.irq.set(self.status & STATUS_IRQ != 0);
+    }
+
+    fn clock_transmitter(&mut self) {
+        if self.tsr_full {
+            self.tx_countdown = self.tx_countdown.saturating_sub(1);
+            if self.tx_countdown == 0 {
+                let data = self.tsr & self.data_mask();
+                self.backend.transmit(data);
+                self.tsr_full = false;
+            }
+        }
+
+        if !self.tsr_full && self.status & STATUS_TDRE == 0 && self.transmitter_enabled() {
+            self.tsr = self.tdr;
+            self.tsr_full = true;
+            self.tx_countdown = self.character_cycles();
+            self.status |= STATUS_TDRE;
+            if self.tx_irq_enabled() {
+                self.interrupt();
+            }
+        }
+    }
+
+    // the backend is asked once per character time, so it is not polled on every cycle
+    fn clock_receiver(&mut self) {
+        self.rx_countdown = self.rx_countdown.saturating_sub(1);
+        if self.rx_countdown > 0 {
+            return;
+        }
+        self.rx_countdown = self.character_cycles();
+
+        if let Some(data) = self.backend.receive() {
+            if self.status & STATUS_RDRF != 0 {
+                self.status |= STATUS_OVERRUN; // the character in the RDR is kept
+            } else {
+                self.rdr = data & self.data_mask();
+                self.status |= STATUS_RDRF;
+            }
+            if self.echo() {
+                self.backend.transmit(data);
+            }
+            if self.rx_irq_enabled() {
+                self.interrupt();
+            }
+        }
+    }
+}
+
+impl InternalAddressing for MOS6551 {
+    fn int_read(&mut self, addr: u16) -> u8 {
+        let data = self.int_peek(addr);
+
+        match addr & 0x03 {
+            0 => {
+                self.status &=
+                    !(STATUS_RDRF | STATUS_OVERRUN | STATUS_FRAMING_ERROR | STATUS_PARITY_ERROR)
+            }
+            1 => {
+                self.status &= !STATUS_IRQ;
+                self.update_irq();
+            }
+            _ => (),
+        }
+
+        data
+    }
+
+    fn int_peek(&self, addr: u16) -> u8 {
+        match addr & 0x03 {
+            0 => self.rdr,
+            1 => self.status,
+            2 => self.command,
+            _ => self.control,
+        }
+    }
+
+    fn int_poke(&mut self, addr: u16, data: u8) {
+        match addr & 0x03 {
+            0 => self.rdr = data,
+            1 => self.status = data,
+            2 => self.command = data,
+            _ => self.control = data,
+        }
+    }
+
+    fn int_write(&mut self, addr: u16, data: u8) {
+        match addr & 0x03 {
+            0 => {
+                self.tdr = data;
+                self.status &= !STATUS_TDRE;
+            }
+
+            // programmed reset: the value written does not matter
+            1 => {
+                self.command &= 0xE0;
+                self.status &= !STATUS_OVERRUN;
+            }
+
+            2 => {
+                self.command = data;
+                if self.enabled() && self.tx_irq_enabled() && self.status & STATUS_TDRE != 0 {
+                    self.interrupt();
+                }
+            }
+
+            _ => self.control = data,
+        }
+    }
+
+    fn len(&self) -> usize {
+        4
+    }
+
+    fn tick(&mut self) {
+        if self.enabled() {
+            self.clock_transmitter();
+            self.clock_receiver();
+        }
+    }
+
+    fn describe(&self) -> String {
+        "MOS6551 ACIA".to_string()
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        vec![
+            self.rdr,
+            self.tdr,
+            self.tsr,
+            self.status,
+            self.command,
+            self.control,
+            self.tsr_full as u8,
+        ]
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), AddressingError> {
+        if state.len() != 7 {
+            return Err(AddressingError::new("load_state", 0));
+        }
+
+        self.rdr = state[0];
+        self.tdr = state[1];
+        self.tsr = state[2];
+        self.status = state[3];
+        self.command = state[4];
+        self.control = state[5];
+        self.tsr_full = state[6] != 0;
+        self.tx_countdown = if self.tsr_full {
+            self.character_cycles()
+        } else {
+            0
+        };
+        self.rx_countdown = 0;
+        self.update_irq();
+        Ok(())
+    }
+}
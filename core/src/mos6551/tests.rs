@@ -0,0 +1,159 @@
+#[cfg(test)]
+use super::*;
+use crate::serial::ChannelSerial;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+
+const DATA: u16 = 0x0;
+const STATUS: u16 = 0x1;
+const COMMAND: u16 = 0x2;
+const CONTROL: u16 = 0x3;
+
+fn acia() -> (MOS6551, Sender<u8>, Receiver<u8>) {
+    let (tx_input, rx_input) = unbounded();
+    let (tx_output, rx_output) = unbounded();
+    let acia = MOS6551::new(1_000_000, Box::new(ChannelSerial::new(rx_input, tx_output)));
+    (acia, tx_input, rx_output)
+}
+
+fn ticks(acia: &mut MOS6551, n: u64) {
+    for _ in 0..n {
+        acia.tick();
+    }
+}
+
+#[test]
+fn character_time_follows_baud_rate_and_format() {
+    // arrange
+    let (mut acia, _, _) = acia();
+
+    // act
+    acia.int_write(CONTROL, 0x1F); // 19200 baud, 8N1
+    let fast = acia.character_cycles();
+    acia.int_write(CONTROL, 0xB6); // 300 baud, 7 bits, 2 stop bits
+    acia.int_write(COMMAND, 0x2B); // odd parity
+    let slow = acia.character_cycles();
+
+    // assert
+    assert_eq!(fast, 521);
+    assert_eq!(slow, 36667); // 11 bits
+}
+
+#[test]
+fn transmits_after_character_time() {
+    // arrange
+    let (mut acia, _, output) = acia();
+    acia.int_write(CONTROL, 0x1F);
+    acia.int_write(COMMAND, 0x0B); // DTR, no interrupts, transmitter on
+
+    // act
+    acia.int_write(DATA, b'A');
+    let status_after_write = acia.int_peek(STATUS);
+    acia.tick();
+    let status_after_load = acia.int_peek(STATUS);
+    acia.int_write(DATA, b'B');
+    ticks(&mut acia, 520);
+    let too_early = output.try_recv();
+    acia.tick();
+    let first = output.try_recv();
+    ticks(&mut acia, 521);
+
+    // assert
+    assert_eq!(status_after_write & STATUS_TDRE, 0);
+    assert_eq!(status_after_load & STATUS_TDRE, STATUS_TDRE);
+    assert!(too_early.is_err());
+    assert_eq!(first, Ok(b'A'));
+    assert_eq!(output.try_recv(), Ok(b'B'));
+    assert!(!acia.irq());
+}
+
+#[test]
+fn receives_with_interrupt_and_overrun() {
+    // arrange
+    let line = InterruptLine::new();
+    let (mut acia, input, _) = acia();
+    acia.connect_irq(&line);
+    acia.int_write(CONTROL, 0x1F);
+    acia.int_write(COMMAND, 0x09); // DTR, receiver interrupt on
+
+    // act
+    input.send(b'x').unwrap();
+    input.send(b'y').unwrap();
+    acia.tick();
+    let status = acia.int_read(STATUS);
+    let after_status_read = line.is_active();
+    ticks(&mut acia, 521);
+    let overrun = acia.int_peek(STATUS);
+    let data = acia.int_read(DATA);
+
+    // assert
+    assert_eq!(status, STATUS_IRQ | STATUS_TDRE | STATUS_RDRF);
+    assert!(!after_status_read);
+    assert_eq!(overrun & STATUS_OVERRUN, STATUS_OVERRUN);
+    assert!(line.is_active());
+    assert_eq!(data, b'x');
+    assert_eq!(acia.int_peek(STATUS) & (STATUS_RDRF | STATUS_OVERRUN), 0);
+}
+
+#[test]
+fn transmit_interrupt_when_register_empty() {
+    // arrange
+    let (mut acia, _, output) = acia();
+    acia.int_write(CONTROL, 0x1F);
+
+    // act
+    acia.int_write(COMMAND, 0x07); // receiver interrupt off, transmitter interrupt on
+    let enabled = acia.irq();
+    acia.int_read(STATUS);
+    acia.int_write(DATA, 0x55);
+    acia.tick();
+    let loaded = acia.irq();
+    ticks(&mut acia, 521);
+
+    // assert
+    assert!(enabled);
+    assert!(loaded);
+    assert_eq!(output.try_recv(), Ok(0x55));
+}
+
+#[test]
+fn disabled_without_dtr_and_echoes_in_echo_mode() {
+    // arrange
+    let (mut acia, input, output) = acia();
+    acia.int_write(CONTROL, 0x1F);
+    input.send(b'e').unwrap();
+
+    // act
+    ticks(&mut acia, 1000);
+    let status_without_dtr = acia.int_peek(STATUS);
+    acia.int_write(COMMAND, 0x13); // DTR, echo, no interrupts
+    acia.tick();
+    let echoed = output.try_recv();
+    acia.int_write(STATUS, 0x00); // programmed reset
+
+    // assert
+    assert_eq!(status_without_dtr & STATUS_RDRF, 0);
+    assert_eq!(echoed, Ok(b'e'));
+    assert_eq!(acia.int_peek(DATA), b'e');
+    assert_eq!(acia.int_peek(COMMAND), 0x00);
+}
+
+#[test]
+fn save_and_load_state() {
+    // arrange
+    let (mut acia, input, _) = acia();
+    acia.int_write(CONTROL, 0x1E);
+    acia.int_write(COMMAND, 0x09);
+    input.send(0x42).unwrap();
+    acia.tick();
+    let state = acia.save_state();
+
+    // act
+    let (mut restored, _, _) = self::acia();
+    let actual = restored.load_state(&state);
+
+    // assert
+    assert!(actual.is_ok());
+    assert!(restored.irq());
+    assert_eq!(restored.int_peek(DATA), 0x42);
+    assert_eq!(restored.save_state(), state);
+}
@@ -0,0 +1,279 @@
+#[cfg(test)]
+mod tests;
+
+use std::fmt;
+
+/// A known dump; load address and entry point are `None` where they do not apply or are not known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KnownRom {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub size: usize,
+    pub crc32: u32,
+    pub sha1: &'static str,
+    pub load_addr: Option<u16>,
+    pub entry_point: Option<u16>,
+}
+
+pub const KNOWN_ROMS: &[KnownRom] = &[
+    KnownRom {
+        name: "Woz Monitor",
+        version: "1976",
+        size: 256,
+        crc32: 0xA30B6AF5,
+        sha1: "224767aa499dc98767e042f375ced1359be8a35f",
+        load_addr: Some(0xFF00),
+        entry_point: Some(0xFF00),
+    },
+    KnownRom {
+        name: "Integer BASIC",
+        version: "1976",
+        size: 4096,
+        crc32: 0x52A2859F,
+        sha1: "3ab34d5bcd79b44c42efe20f85b100b23ecfa5c2",
+        load_addr: Some(0xE000),
+        entry_point: Some(0xE000),
+    },
+    KnownRom {
+        name: "Integer BASIC",
+        version: "display at $D0F2",
+        size: 4096,
+        crc32: 0xD5E86EFC,
+        sha1: "04269c1c66e7d5b4aa5035462c6e612bf2ae9b91",
+        load_addr: Some(0xE000),
+        entry_point: Some(0xE000),
+    },
+    KnownRom {
+        name: "Apple 30th anniversary demo",
+        version: "2006",
+        size: 3456,
+        crc32: 0x7BFF6CF1,
+        sha1: "48108e577cc118ef87f902fe25cdebfe15b260b0",
+        load_addr: Some(0x0300),
+        entry_point: Some(0x0300),
+    },
+    KnownRom {
+        name: "Microchess",
+        version: "Apple-1 port",
+        size: 2248,
+        crc32: 0xF98F21BD,
+        sha1: "3fd3103933c9e9a76dddafe2a206cfedb1f1a093",
+        load_addr: Some(0x0300),
+        entry_point: Some(0x0300),
+    },
+    KnownRom {
+        name: "Life",
+        version: "Apple-1",
+        size: 440,
+        crc32: 0x51863CEC,
+        sha1: "fe5ba803b2e8f5a0b6a29e981ec482e08cf552c2",
+        load_addr: Some(0x2000),
+        entry_point: Some(0x2000),
+    },
+    KnownRom {
+        name: "Lunar Lander",
+        version: "Apple-1",
+        size: 1721,
+        crc32: 0xE48A3D01,
+        sha1: "194689c858acb1d97111c2af7e7c6baf3c78a17c",
+        load_addr: Some(0x0300),
+        entry_point: Some(0x0300),
+    },
+    KnownRom {
+        name: "Character generator",
+        version: "Signetics 2513",
+        size: 1024,
+        crc32: 0xD4CA329D,
+        sha1: "4fb828985b811e5461da0641122e2c262d884f40",
+        load_addr: None, // not in the CPU address space
+        entry_point: None,
+    },
+    KnownRom {
+        name: "6502 functional test",
+        version: "Klaus Dormann",
+        size: 65536,
+        crc32: 0xB2292999,
+        sha1: "55c9ab5b137c8ced3c666bfdb55c8285782baad9",
+        load_addr: Some(0x0000),
+        entry_point: Some(0x0400),
+    },
+    KnownRom {
+        name: "6502 decimal mode test",
+        version: "Bruce Clark",
+        size: 234,
+        crc32: 0xC9ADA2C5,
+        sha1: "ee58ff7c30a9659c437cc2f8fb7f0b167dd4707f",
+        load_addr: Some(0x0200),
+        entry_point: Some(0x0200),
+    },
+];
+
+/// Outcome of checking an image against `KNOWN_ROMS`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RomCheck {
+    Known(&'static KnownRom),
+    /// a known dump, but loaded somewhere else than expected
+    Misplaced {
+        rom: &'static KnownRom,
+        load_addr: u16,
+    },
+    /// same size as the dump expected at this address, but different contents
+    Corrupt {
+        expected: &'static KnownRom,
+        crc32: u32,
+        sha1: String,
+    },
+    Unknown {
+        size: usize,
+        crc32: u32,
+        sha1: String,
+    },
+}
+
+impl RomCheck {
+    pub fn is_known(&self) -> bool {
+        matches!(self, RomCheck::Known(_))
+    }
+}
+
+impl fmt::Display for KnownRom {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.version)
+    }
+}
+
+impl fmt::Display for RomCheck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RomCheck::Known(rom) => write!(f, "{}", rom),
+            RomCheck::Misplaced { rom, load_addr } => write!(
+                f,
+                "{} loaded at ${:04X}, expected at ${:04X}",
+                rom,
+                load_addr,
+                rom.load_addr.unwrap_or_default()
+            ),
+            RomCheck::Corrupt {
+                expected,
+                crc32,
+                sha1,
+            } => write!(
+                f,
+                "corrupt image of {}: CRC32 {:08X} SHA-1 {}, expected {:08X} {}",
+                expected, crc32, sha1, expected.crc32, expected.sha1
+            ),
+            RomCheck::Unknown { size, crc32, sha1 } => write!(
+                f,
+                "unknown image of {} bytes: CRC32 {:08X} SHA-1 {}",
+                size, crc32, sha1
+            ),
+        }
+    }
+}
+
+pub fn identify(data: &[u8]) -> Option<&'static KnownRom> {
+    lookup(data, crc32(data), &sha1_hex(data))
+}
+
+/// Identify `data` and check it against the load address the known dump expects.
+pub fn check(data: &[u8], load_addr: u16) -> RomCheck {
+    let crc32 = crc32(data);
+    let sha1 = sha1_hex(data);
+
+    match lookup(data, crc32, &sha1) {
+        Some(rom) => match rom.load_addr {
+            Some(expected) if expected != load_addr => RomCheck::Misplaced { rom, load_addr },
+            _ => RomCheck::Known(rom),
+        },
+        None => match KNOWN_ROMS
+            .iter()
+            .find(|rom| rom.size == data.len() && rom.load_addr == Some(load_addr))
+        {
+            Some(expected) => RomCheck::Corrupt {
+                expected,
+                crc32,
+                sha1,
+            },
+            None => RomCheck::Unknown {
+                size: data.len(),
+                crc32,
+                sha1,
+            },
+        },
+    }
+}
+
+fn lookup(data: &[u8], crc32: u32, sha1: &str) -> Option<&'static KnownRom> {
+    KNOWN_ROMS
+        .iter()
+        .find(|rom| rom.size == data.len() && rom.crc32 == crc32 && rom.sha1 == sha1)
+}
+
+/// CRC-32 as used by zip and PNG (reflected, polynomial $EDB88320).
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, b| {
+        (0..8).fold(crc ^ *b as u32, |crc, _| {
+            if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+pub fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (h, v) in h.iter_mut().zip([a, b, c, d, e]) {
+            *h = h.wrapping_add(v);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (i, word) in h.iter().enumerate() {
+        digest[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+fn sha1_hex(data: &[u8]) -> String {
+    sha1(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,85 @@
+#[cfg(test)]
+use super::*;
+use std::fs;
+
+fn rom(file: &str) -> Vec<u8> {
+    fs::read(format!("../roms/{}", file)).unwrap()
+}
+
+#[test]
+fn computes_crc32_and_sha1() {
+    // act
+    let crc = crc32(b"123456789");
+    let digest = sha1_hex(b"abc");
+    let empty = sha1_hex(b"");
+
+    // assert
+    assert_eq!(crc, 0xCBF43926);
+    assert_eq!(digest, "a9993e364706816aba3e25717850c26c9cd0d89d");
+    assert_eq!(empty, "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+}
+
+#[test]
+fn identifies_all_images_in_roms() {
+    // arrange
+    let files = [
+        "Apple1_HexMonitor.bin",
+        "Apple1_Basic.bin",
+        "Apple1_Basic-2.bin",
+        "Apple1_Basic30.bin",
+        "Apple1_ASMmchess.bin",
+        "Apple1_Life.bin",
+        "Apple1_Lunar.bin",
+        "Apple1_charmap.bin",
+        "6502_functional_test.bin",
+        "6502_decimal_test.bin",
+    ];
+
+    // act
+    let actual: Vec<Option<&KnownRom>> = files.iter().map(|f| identify(&rom(f))).collect();
+
+    // assert
+    for (file, known) in files.iter().zip(&actual) {
+        assert!(known.is_some(), "{} not identified", file);
+    }
+    assert_eq!(actual[1].unwrap().name, "Integer BASIC");
+    assert_ne!(actual[1], actual[2]);
+}
+
+#[test]
+fn checks_load_address() {
+    // arrange
+    let monitor = rom("Apple1_HexMonitor.bin");
+
+    // act
+    let at_ff00 = check(&monitor, 0xFF00);
+    let at_e000 = check(&monitor, 0xE000);
+
+    // assert
+    assert!(at_ff00.is_known());
+    assert!(matches!(
+        at_e000,
+        RomCheck::Misplaced {
+            load_addr: 0xE000,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn reports_corrupt_and_unknown_images() {
+    // arrange
+    let mut basic = rom("Apple1_Basic.bin");
+    basic[0x100] ^= 0x01;
+
+    // act
+    let corrupt = check(&basic, 0xE000);
+    let unknown = check(&[0xEA; 16], 0x0300);
+
+    // assert
+    assert!(
+        matches!(corrupt, RomCheck::Corrupt { expected, .. } if expected.name == "Integer BASIC")
+    );
+    assert!(matches!(unknown, RomCheck::Unknown { size: 16, .. }));
+    assert!(unknown.to_string().starts_with("unknown image of 16 bytes"));
+}
@@ -0,0 +1,137 @@
+#[cfg(test)]
+mod tests;
+
+#[cfg(unix)]
+mod pty;
+
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::thread;
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+
+#[cfg(unix)]
+pub use pty::PtySerial;
+
+/// Host side of a serial line of an ACIA; called synchronously and must not block.
+pub trait SerialBackend {
+    /// Next byte from the host, if one is waiting.
+    fn receive(&mut self) -> Option<u8>;
+
+    fn transmit(&mut self, data: u8);
+}
+
+/// Bytes through channels, e.g. for a terminal thread or tests.
+pub struct ChannelSerial {
+    input: Receiver<u8>,
+    output: Sender<u8>,
+}
+
+impl ChannelSerial {
+    pub fn new(input: Receiver<u8>, output: Sender<u8>) -> ChannelSerial {
+        ChannelSerial { input, output }
+    }
+}
+
+impl SerialBackend for ChannelSerial {
+    fn receive(&mut self) -> Option<u8> {
+        self.input.try_recv().ok()
+    }
+
+    fn transmit(&mut self, data: u8) {
+        let _ = self.output.send(data); // nobody listening is like an unplugged cable
+    }
+}
+
+/// The emulator's own stdin and stdout; stdin is read by a background thread.
+pub struct StdioSerial {
+    input: Receiver<u8>,
+}
+
+impl StdioSerial {
+    pub fn new() -> StdioSerial {
+        let (tx, rx) = unbounded();
+        thread::spawn(move || {
+            for b in io::stdin().lock().bytes() {
+                match b {
+                    Ok(b) if tx.send(b).is_ok() => (),
+                    _ => break,
+                }
+            }
+        });
+        StdioSerial { input: rx }
+    }
+}
+
+impl Default for StdioSerial {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SerialBackend for StdioSerial {
+    fn receive(&mut self) -> Option<u8> {
+        self.input.try_recv().ok()
+    }
+
+    fn transmit(&mut self, data: u8) {
+        let mut stdout = io::stdout().lock();
+        let _ = stdout.write_all(&[data]).and_then(|_| stdout.flush());
+    }
+}
+
+/// A local TCP port, e.g. for `telnet` or `nc`; one client at a time, output without a client
+/// is dropped.
+pub struct TcpSerial {
+    listener: TcpListener,
+    client: Option<TcpStream>,
+}
+
+impl TcpSerial {
+    pub fn listen(addr: impl ToSocketAddrs) -> io::Result<TcpSerial> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(TcpSerial {
+            listener,
+            client: None,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    fn client(&mut self) -> Option<&mut TcpStream> {
+        if self.client.is_none() {
+            if let Ok((stream, _)) = self.listener.accept() {
+                if stream.set_nonblocking(true).is_ok() {
+                    let _ = stream.set_nodelay(true);
+                    self.client = Some(stream);
+                }
+            }
+        }
+        self.client.as_mut()
+    }
+}
+
+impl SerialBackend for TcpSerial {
+    fn receive(&mut self) -> Option<u8> {
+        let mut buf = [0u8; 1];
+        match self.client()?.read(&mut buf) {
+            Ok(1) => Some(buf[0]),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => None,
+            _ => {
+                self.client = None; // disconnected
+                None
+            }
+        }
+    }
+
+    fn transmit(&mut self, data: u8) {
+        if let Some(client) = self.client() {
+            if client.write_all(&[data]).is_err() {
+                self.client = None;
+            }
+        }
+    }
+}
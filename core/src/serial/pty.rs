@@ -0,0 +1,76 @@
+use std::ffi::CStr;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::path::{Path, PathBuf};
+
+use crate::serial::SerialBackend;
+
+/// A pseudo-terminal; terminal programs such as `screen` or `minicom` connect to `path()`.
+pub struct PtySerial {
+    master: File,
+    _slave: File, // kept open, so reads do not fail while no terminal is connected
+    path: PathBuf,
+}
+
+impl PtySerial {
+    pub fn open() -> io::Result<PtySerial> {
+        // SAFETY: plain libc calls on a file descriptor owned by `master` from here on
+        unsafe {
+            let fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let master = File::from_raw_fd(fd);
+            if libc::grantpt(fd) != 0 || libc::unlockpt(fd) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let name = libc::ptsname(fd);
+            if name.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+            let path = PathBuf::from(CStr::from_ptr(name).to_string_lossy().into_owned());
+
+            let slave = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .custom_flags(libc::O_NOCTTY)
+                .open(&path)?;
+            let mut termios: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(slave.as_raw_fd(), &mut termios) == 0 {
+                libc::cfmakeraw(&mut termios); // no echo or line editing
+                libc::tcsetattr(slave.as_raw_fd(), libc::TCSANOW, &termios);
+            }
+
+            let flags = libc::fcntl(fd, libc::F_GETFL);
+            if flags < 0 || libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(PtySerial {
+                master,
+                _slave: slave,
+                path,
+            })
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl SerialBackend for PtySerial {
+    fn receive(&mut self) -> Option<u8> {
+        let mut buf = [0u8; 1];
+        match self.master.read(&mut buf) {
+            Ok(1) => Some(buf[0]),
+            _ => None,
+        }
+    }
+
+    fn transmit(&mut self, data: u8) {
+        let _ = self.master.write_all(&[data]);
+    }
+}
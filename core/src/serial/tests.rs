@@ -0,0 +1,77 @@
+#[cfg(test)]
+use super::*;
+use std::time::{Duration, Instant};
+
+fn receive_within(backend: &mut dyn SerialBackend, timeout: Duration) -> Option<u8> {
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        if let Some(b) = backend.receive() {
+            return Some(b);
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    None
+}
+
+#[test]
+fn channel_serial_passes_bytes() {
+    // arrange
+    let (tx_input, rx_input) = unbounded();
+    let (tx_output, rx_output) = unbounded();
+    let mut serial = ChannelSerial::new(rx_input, tx_output);
+
+    // act
+    tx_input.send(b'A').unwrap();
+    let received = serial.receive();
+    let nothing = serial.receive();
+    serial.transmit(b'B');
+
+    // assert
+    assert_eq!(received, Some(b'A'));
+    assert_eq!(nothing, None);
+    assert_eq!(rx_output.try_recv(), Ok(b'B'));
+}
+
+#[test]
+fn tcp_serial_talks_to_client() {
+    // arrange
+    let mut serial = TcpSerial::listen("127.0.0.1:0").unwrap();
+    let mut client = TcpStream::connect(serial.local_addr().unwrap()).unwrap();
+    client
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    // act
+    client.write_all(b"x").unwrap();
+    let received = receive_within(&mut serial, Duration::from_secs(5));
+    serial.transmit(b'y');
+    let mut buf = [0u8; 1];
+    client.read_exact(&mut buf).unwrap();
+
+    // assert
+    assert_eq!(received, Some(b'x'));
+    assert_eq!(buf[0], b'y');
+}
+
+#[cfg(unix)]
+#[test]
+fn pty_serial_talks_to_terminal() {
+    // arrange
+    let mut serial = PtySerial::open().unwrap();
+    let mut terminal = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(serial.path())
+        .unwrap();
+
+    // act
+    terminal.write_all(b"x").unwrap();
+    let received = receive_within(&mut serial, Duration::from_secs(5));
+    serial.transmit(b'y');
+    let mut buf = [0u8; 1];
+    terminal.read_exact(&mut buf).unwrap();
+
+    // assert
+    assert_eq!(received, Some(b'x'));
+    assert_eq!(buf[0], b'y');
+}